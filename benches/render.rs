@@ -0,0 +1,22 @@
+// times rendering with more and more patterned materials. Materials are
+// borrowed for every hit, so neither the time nor the allocations, which
+// tests/allocations.rs counts, should grow much with the patterns
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use ray_tracer::examples::patterned_scene;
+
+fn render(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("render");
+    for pattern_count in [0, 1, 4] {
+        let (world, camera) = patterned_scene(32, 32, pattern_count);
+        group.bench_with_input(
+            BenchmarkId::new("patterns", pattern_count),
+            &pattern_count,
+            |bencher, _| bencher.iter(|| camera.render(&world)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,318 @@
+use std::io::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use super::Track;
+use crate::base_types::Point;
+use crate::matrices::Matrix;
+use crate::visuals::{Color, Material};
+use crate::world::{Camera, Object, World};
+
+pub const DEFAULT_FRAME_RATE: f64 = 24.0;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialProperty {
+    Ambient,
+    Diffuse,
+    Specular,
+    Shininess,
+    Reflective,
+    Transparency,
+    RefractiveIndex,
+}
+
+impl MaterialProperty {
+    fn value_mut(self, material: &mut Material) -> &mut f64 {
+        match self {
+            Self::Ambient => &mut material.ambient,
+            Self::Diffuse => &mut material.diffuse,
+            Self::Specular => &mut material.specular,
+            Self::Shininess => &mut material.shininess,
+            Self::Reflective => &mut material.reflective,
+            Self::Transparency => &mut material.transparency,
+            Self::RefractiveIndex => &mut material.refractive_index,
+        }
+    }
+}
+
+// one animated value in the scene. Objects and lights are picked by their
+// index in the world
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[serde(tag = "target", rename_all = "snake_case")]
+pub enum Channel {
+    CameraTransform {
+        keyframes: Track<Matrix>,
+    },
+    ObjectTransform {
+        object: usize,
+        keyframes: Track<Matrix>,
+    },
+    LightPosition {
+        light: usize,
+        keyframes: Track<Point>,
+    },
+    MaterialColor {
+        object: usize,
+        keyframes: Track<Color>,
+    },
+    Material {
+        object: usize,
+        property: MaterialProperty,
+        keyframes: Track<f64>,
+    },
+}
+
+impl Channel {
+    pub fn end_time(&self) -> f64 {
+        match self {
+            Self::CameraTransform { keyframes } | Self::ObjectTransform { keyframes, .. } => {
+                keyframes.end_time()
+            }
+            Self::LightPosition { keyframes, .. } => keyframes.end_time(),
+            Self::MaterialColor { keyframes, .. } => keyframes.end_time(),
+            Self::Material { keyframes, .. } => keyframes.end_time(),
+        }
+    }
+
+    pub fn apply(&self, time: f64, world: &mut World, camera: &mut Camera) -> Result<(), Error> {
+        match self {
+            Self::CameraTransform { keyframes } => camera.set_transform(keyframes.value_at(time)),
+            Self::ObjectTransform { object, keyframes } => {
+                world_object(world, *object)?.set_transform(keyframes.value_at(time))
+            }
+            Self::LightPosition { light, keyframes } => {
+                let light_count = world.lights.len();
+                world
+                    .lights
+                    .get_mut(*light)
+                    .ok_or_else(|| missing("light", *light, light_count))?
+                    .position = keyframes.value_at(time)
+            }
+            Self::MaterialColor { object, keyframes } => {
+                world_object(world, *object)?.get_material_mut().color = keyframes.value_at(time)
+            }
+            Self::Material {
+                object,
+                property,
+                keyframes,
+            } => {
+                *property.value_mut(world_object(world, *object)?.get_material_mut()) =
+                    keyframes.value_at(time)
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn world_object(world: &mut World, index: usize) -> Result<&mut Box<dyn Object>, Error> {
+    let object_count = world.objects.len();
+    world
+        .objects
+        .get_mut(index)
+        .ok_or_else(|| missing("object", index, object_count))
+}
+
+fn missing(kind: &str, index: usize, count: usize) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "animation uses {} {}, but the world only has {}",
+            kind, index, count
+        ),
+    )
+}
+
+fn default_frame_rate() -> f64 {
+    DEFAULT_FRAME_RATE
+}
+
+// everything that moves in a scene. The animation runs from time 0 until
+// the last keyframe of any channel
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Animation {
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: f64,
+    pub channels: Vec<Channel>,
+}
+
+impl Animation {
+    pub fn new(frame_rate: f64, channels: Vec<Channel>) -> Self {
+        Self {
+            frame_rate,
+            channels,
+        }
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.channels
+            .iter()
+            .map(Channel::end_time)
+            .fold(0.0, f64::max)
+    }
+
+    // number of frames, both the first frame at time 0 and the last one at
+    // the end are rendered
+    pub fn frame_count(&self) -> usize {
+        (self.duration() * self.frame_rate + crate::utils::FLOAT_DIFF).floor() as usize + 1
+    }
+
+    pub fn frame_time(&self, frame: usize) -> f64 {
+        frame as f64 / self.frame_rate
+    }
+
+    // moves everything to where it is at the given time
+    pub fn apply(&self, time: f64, world: &mut World, camera: &mut Camera) -> Result<(), Error> {
+        for channel in self.channels.iter() {
+            channel.apply(time, world, camera)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::animation::{Easing, Keyframe};
+    use crate::world::Sphere;
+
+    fn track<T>(keyframes: Vec<(f64, T)>) -> Track<T> {
+        Track::new(
+            keyframes
+                .into_iter()
+                .map(|(time, value)| Keyframe::new(time, value, Easing::Linear))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn apply_every_channel() {
+        let mut world = World::default();
+        let mut camera = Camera::new(10, 10, 1.0);
+        let animation = Animation::new(
+            DEFAULT_FRAME_RATE,
+            vec![
+                Channel::CameraTransform {
+                    keyframes: track(vec![
+                        (0.0, Matrix::new_identity()),
+                        (2.0, Matrix::translation(0.0, 0.0, -4.0)),
+                    ]),
+                },
+                Channel::ObjectTransform {
+                    object: 1,
+                    keyframes: track(vec![(0.0, Matrix::scaling(2.0, 2.0, 2.0))]),
+                },
+                Channel::LightPosition {
+                    light: 0,
+                    keyframes: track(vec![
+                        (0.0, Point::new_point(0.0, 0.0, 0.0)),
+                        (1.0, Point::new_point(0.0, 10.0, 0.0)),
+                    ]),
+                },
+                Channel::MaterialColor {
+                    object: 0,
+                    keyframes: track(vec![
+                        (0.0, Color::new(0.0, 0.0, 0.0)),
+                        (4.0, Color::new(1.0, 1.0, 1.0)),
+                    ]),
+                },
+                Channel::Material {
+                    object: 0,
+                    property: MaterialProperty::Reflective,
+                    keyframes: track(vec![(0.0, 0.0), (1.0, 0.5)]),
+                },
+            ],
+        );
+
+        animation.apply(1.0, &mut world, &mut camera).unwrap();
+
+        assert!(camera
+            .get_transform()
+            .matrix()
+            .equal(&Matrix::translation(0.0, 0.0, -2.0)));
+        assert!(world.objects[1]
+            .get_transform()
+            .matrix()
+            .equal(&Matrix::scaling(2.0, 2.0, 2.0)));
+        assert!(world.lights[0]
+            .position
+            .equal(Point::new_point(0.0, 10.0, 0.0)));
+        assert!(world.objects[0]
+            .get_material()
+            .color
+            .equal(Color::new(0.25, 0.25, 0.25)));
+        assert_eq!(world.objects[0].get_material().reflective, 0.5);
+        assert_eq!(animation.duration(), 4.0);
+    }
+
+    #[test]
+    fn missing_object() {
+        let mut world = World::new();
+        world.add_object(Box::new(Sphere::default()));
+        let mut camera = Camera::new(10, 10, 1.0);
+        let animation = Animation::new(
+            DEFAULT_FRAME_RATE,
+            vec![Channel::Material {
+                object: 1,
+                property: MaterialProperty::Ambient,
+                keyframes: track(vec![(0.0, 1.0)]),
+            }],
+        );
+
+        let error = animation.apply(0.0, &mut world, &mut camera).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            error.to_string(),
+            "animation uses object 1, but the world only has 1"
+        );
+    }
+
+    #[test]
+    fn frames() {
+        let animation = Animation::new(
+            4.0,
+            vec![Channel::Material {
+                object: 0,
+                property: MaterialProperty::Ambient,
+                keyframes: track(vec![(0.0, 0.0), (1.5, 1.0)]),
+            }],
+        );
+
+        assert_eq!(animation.frame_count(), 7);
+        assert_eq!(animation.frame_time(6), 1.5);
+        assert_eq!(Animation::new(24.0, Vec::new()).frame_count(), 1);
+    }
+
+    #[test]
+    fn deserialize() {
+        let animation: Animation = serde_json::from_str(
+            r#"{
+                "channels": [
+                    {
+                        "target": "material",
+                        "object": 0,
+                        "property": "refractive_index",
+                        "keyframes": [{ "time": 0, "value": 1.0 }, { "time": 2, "value": 1.5 }]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(animation.frame_rate, DEFAULT_FRAME_RATE);
+        assert_eq!(
+            animation.channels[0],
+            Channel::Material {
+                object: 0,
+                property: MaterialProperty::RefractiveIndex,
+                keyframes: track(vec![(0.0, 1.0), (2.0, 1.5)]),
+            }
+        );
+    }
+}
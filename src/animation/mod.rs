@@ -0,0 +1,7 @@
+mod channel;
+mod sequence;
+mod track;
+
+pub use channel::*;
+pub use sequence::*;
+pub use track::*;
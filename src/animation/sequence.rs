@@ -0,0 +1,139 @@
+use std::io::{Error, ErrorKind};
+
+use super::Animation;
+use crate::visuals::{Canvas, ImageFormat};
+use crate::world::{Camera, World};
+
+// the frame number replaces the run of '#'s in the pattern, padded with
+// zeros to its length, e.g., frames/spin_###.png gives frames/spin_007.png
+pub fn frame_path(pattern: &str, frame: usize) -> Result<String, Error> {
+    let start = pattern.find('#').ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} needs a run of '#'s where the frame number goes",
+                pattern
+            ),
+        )
+    })?;
+    let width = pattern[start..]
+        .find(|character| character != '#')
+        .unwrap_or(pattern.len() - start);
+
+    Ok(format!(
+        "{}{:0width$}{}",
+        &pattern[..start],
+        frame,
+        &pattern[start + width..],
+        width = width
+    ))
+}
+
+// renders every frame of the animation in order and hands it to
+// handle_frame along with its number. The world and camera are left as
+// they are in the last frame
+pub fn render_frames<F>(
+    world: &mut World,
+    camera: &mut Camera,
+    animation: &Animation,
+    mut handle_frame: F,
+) -> Result<usize, Error>
+where
+    F: FnMut(usize, Canvas) -> Result<(), Error>,
+{
+    if !animation.frame_rate.is_finite() || animation.frame_rate <= 0.0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("frame rate must be above 0, got {}", animation.frame_rate),
+        ));
+    }
+
+    let frame_count = animation.frame_count();
+    for frame in 0..frame_count {
+        animation.apply(animation.frame_time(frame), world, camera)?;
+        handle_frame(frame, camera.render(world))?;
+    }
+
+    Ok(frame_count)
+}
+
+// renders the animation into numbered image files, see frame_path, and
+// returns how many were written
+pub fn save_frames(
+    world: &mut World,
+    camera: &mut Camera,
+    animation: &Animation,
+    pattern: &str,
+    format: ImageFormat,
+) -> Result<usize, Error> {
+    // catch a bad pattern before spending time on the first frame
+    frame_path(pattern, 0)?;
+
+    render_frames(world, camera, animation, |frame, canvas| {
+        canvas.save_canvas_as(&frame_path(pattern, frame)?, format)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::animation::{Channel, Easing, Keyframe, Track};
+    use crate::base_types::Point;
+    use crate::examples::plane_scene;
+
+    #[test]
+    fn frame_paths() {
+        assert_eq!(
+            frame_path("out/spin_###.png", 7).unwrap(),
+            "out/spin_007.png"
+        );
+        assert_eq!(frame_path("#.png", 12).unwrap(), "12.png");
+        assert_eq!(frame_path("frame_##", 123).unwrap(), "frame_123");
+        assert_eq!(
+            frame_path("out/spin.png", 1).err().unwrap().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn renders_every_frame() {
+        let (mut world, mut camera) = plane_scene(8, 6);
+        let animation = Animation::new(
+            2.0,
+            vec![Channel::LightPosition {
+                light: 0,
+                keyframes: Track::new(vec![
+                    Keyframe::new(0.0, Point::new_point(-10.0, 10.0, -10.0), Easing::Linear),
+                    Keyframe::new(1.0, Point::new_point(10.0, 10.0, -10.0), Easing::EaseInOut),
+                ])
+                .unwrap(),
+            }],
+        );
+
+        let mut frames = Vec::new();
+        let frame_count = render_frames(&mut world, &mut camera, &animation, |frame, canvas| {
+            frames.push((frame, canvas));
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(frame_count, 3);
+        assert_eq!(
+            frames.iter().map(|(frame, _)| *frame).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        // the light moved, so the frames differ
+        assert!(!frames[0].1.approx_equal(&frames[2].1, 0.0));
+        assert!(world.lights[0]
+            .position
+            .equal(Point::new_point(10.0, 10.0, -10.0)));
+    }
+
+    #[test]
+    fn invalid_frame_rate() {
+        let (mut world, mut camera) = plane_scene(8, 6);
+        let animation = Animation::new(0.0, Vec::new());
+
+        assert!(render_frames(&mut world, &mut camera, &animation, |_, _| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use std::io::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::Point;
+use crate::matrices::Matrix;
+use crate::visuals::Color;
+
+// how the value moves from one keyframe to the next
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    // starts slow and speeds up
+    EaseIn,
+    // starts fast and slows down
+    EaseOut,
+    // slow at both ends
+    EaseInOut,
+}
+
+impl Easing {
+    // maps the linear progress t, between 0 and 1, to the eased progress
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2.0 - t),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+// values that can be blended between two keyframes, t is between 0 and 1
+pub trait Interpolate: Clone {
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Point {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Matrix {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        Matrix::interpolate(self, other, t)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Keyframe<T> {
+    // in seconds
+    pub time: f64,
+    pub value: T,
+    // used on the way to the next keyframe
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+impl<T> Keyframe<T> {
+    pub fn new(time: f64, value: T, easing: Easing) -> Self {
+        Self {
+            time,
+            value,
+            easing,
+        }
+    }
+}
+
+// the keyframes of one animated value, sorted by time. Before the first
+// keyframe and after the last one the value holds still
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[serde(try_from = "Vec<Keyframe<T>>", into = "Vec<Keyframe<T>>")]
+#[serde(bound(
+    serialize = "T: Serialize + Clone",
+    deserialize = "T: Deserialize<'de>"
+))]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn value_at(&self, time: f64) -> T {
+        // keyframes after time
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+
+        if next == 0 {
+            return self.keyframes[0].value.clone();
+        }
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1].value.clone();
+        }
+
+        let from = &self.keyframes[next - 1];
+        let to = &self.keyframes[next];
+        let progress = (time - from.time) / (to.time - from.time);
+
+        from.value
+            .interpolate(&to.value, from.easing.apply(progress))
+    }
+}
+
+impl<T> Track<T> {
+    pub fn new(mut keyframes: Vec<Keyframe<T>>) -> Result<Self, Error> {
+        if keyframes.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "a track needs at least one keyframe",
+            ));
+        }
+        if let Some(keyframe) = keyframes.iter().find(|keyframe| !keyframe.time.is_finite()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("keyframe time must be a number, got {}", keyframe.time),
+            ));
+        }
+
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        if let Some(pair) = keyframes
+            .windows(2)
+            .find(|pair| pair[0].time == pair[1].time)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("two keyframes at time {}", pair[0].time),
+            ));
+        }
+
+        Ok(Self { keyframes })
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    // time of the last keyframe
+    pub fn end_time(&self) -> f64 {
+        self.keyframes[self.keyframes.len() - 1].time
+    }
+}
+
+impl<T> TryFrom<Vec<Keyframe<T>>> for Track<T> {
+    type Error = Error;
+
+    fn try_from(keyframes: Vec<Keyframe<T>>) -> Result<Self, Error> {
+        Self::new(keyframes)
+    }
+}
+
+impl<T> From<Track<T>> for Vec<Keyframe<T>> {
+    fn from(track: Track<T>) -> Self {
+        track.keyframes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn track(keyframes: &[(f64, f64, Easing)]) -> Track<f64> {
+        Track::new(
+            keyframes
+                .iter()
+                .map(|&(time, value, easing)| Keyframe::new(time, value, easing))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn easing() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+        assert!(Easing::EaseInOut.apply(0.25) < 0.25);
+        assert!(Easing::EaseInOut.apply(0.75) > 0.75);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn value_at() {
+        let track = track(&[
+            (1.0, 10.0, Easing::Linear),
+            (3.0, 20.0, Easing::EaseIn),
+            (4.0, 40.0, Easing::Linear),
+        ]);
+
+        // holds before the first and after the last keyframe
+        assert_eq!(track.value_at(0.0), 10.0);
+        assert_eq!(track.value_at(5.0), 40.0);
+
+        assert_eq!(track.value_at(1.0), 10.0);
+        assert_eq!(track.value_at(2.0), 15.0);
+        assert_eq!(track.value_at(3.0), 20.0);
+        // the easing of the keyframe before is used
+        assert_eq!(track.value_at(3.5), 25.0);
+        assert_eq!(track.end_time(), 4.0);
+    }
+
+    #[test]
+    fn keyframes_are_sorted() {
+        let track = track(&[(2.0, 1.0, Easing::Linear), (0.0, 0.0, Easing::Linear)]);
+
+        assert_eq!(track.keyframes()[0].time, 0.0);
+        assert_eq!(track.value_at(1.0), 0.5);
+    }
+
+    #[test]
+    fn invalid_tracks() {
+        assert!(Track::<f64>::new(Vec::new()).is_err());
+        assert!(Track::new(vec![
+            Keyframe::new(1.0, 0.0, Easing::Linear),
+            Keyframe::new(1.0, 1.0, Easing::Linear),
+        ])
+        .is_err());
+        assert!(Track::new(vec![Keyframe::new(f64::NAN, 0.0, Easing::Linear)]).is_err());
+    }
+
+    #[test]
+    fn interpolate_point_and_color() {
+        let point =
+            Point::new_point(0.0, 2.0, 4.0).interpolate(&Point::new_point(2.0, 2.0, 0.0), 0.5);
+        let color = Color::new(1.0, 0.0, 0.0).interpolate(&Color::new(0.0, 0.0, 1.0), 0.25);
+
+        assert!(point.equal(Point::new_point(1.0, 2.0, 2.0)));
+        assert!(color.equal(Color::new(0.75, 0.0, 0.25)));
+    }
+
+    #[test]
+    fn deserialize() {
+        let track: Track<Point> = serde_json::from_str(
+            r#"[
+                { "time": 1, "value": { "x": 1, "y": 0, "z": 0 }, "easing": "ease_out" },
+                { "time": 0, "value": { "x": 0, "y": 0, "z": 0 } }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(track.keyframes()[0].easing, Easing::Linear);
+        assert_eq!(track.keyframes()[1].easing, Easing::EaseOut);
+        assert!(track.value_at(0.5).equal(Point::new_point(0.5, 0.0, 0.0)));
+        assert!(serde_json::from_str::<Track<f64>>("[]").is_err());
+    }
+}
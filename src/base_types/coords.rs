@@ -1,5 +1,8 @@
-use std::ops;
+use super::{Point, Vector};
 
+// a point or vector in homogeneous coordinates, which is what the matrices
+// multiply. Points have a w of 1 so translations move them, vectors have a w
+// of 0 so they only get rotated and scaled
 #[derive(Clone, Copy)]
 #[cfg_attr(test, derive(Debug, PartialEq))]
 pub struct Coordinates {
@@ -9,99 +12,62 @@ pub struct Coordinates {
     pub w: f64,
 }
 
-impl ops::Add for Coordinates {
-    type Output = Self;
-
-    fn add(self, _rhs: Self) -> Self {
-        let w = self.w + _rhs.w;
-        if w > 1.0 {
-            panic!("Cannot add two points together!");
-        }
-
+impl From<Point> for Coordinates {
+    fn from(point: Point) -> Self {
         Self {
-            x: self.x + _rhs.x,
-            y: self.y + _rhs.y,
-            z: self.z + _rhs.z,
-            w,
+            x: point.x,
+            y: point.y,
+            z: point.z,
+            w: 1.0,
         }
     }
 }
 
-impl ops::Add<f64> for Coordinates {
-    type Output = Self;
-
-    fn add(self, _rhs: f64) -> Self {
+impl From<Vector> for Coordinates {
+    fn from(vec: Vector) -> Self {
         Self {
-            x: self.x + _rhs,
-            y: self.y + _rhs,
-            z: self.z + _rhs,
-            w: self.w,
+            x: vec.x,
+            y: vec.y,
+            z: vec.z,
+            w: 0.0,
         }
     }
 }
 
-impl ops::Sub for Coordinates {
-    type Output = Self;
-
-    fn sub(self, _rhs: Self) -> Self {
-        let w = self.w - _rhs.w;
-        if w < 0.0 {
-            panic!("Cannot subtract a point from a vector!");
-        }
-        Self {
-            x: self.x - _rhs.x,
-            y: self.y - _rhs.y,
-            z: self.z - _rhs.z,
-            w,
-        }
+impl From<Coordinates> for Point {
+    fn from(coords: Coordinates) -> Self {
+        Self::new_point(coords.x, coords.y, coords.z)
     }
 }
 
-impl ops::Neg for Coordinates {
-    type Output = Self;
-
-    fn neg(self) -> Self {
-        Self {
-            x: -self.x,
-            y: -self.y,
-            z: -self.z,
-            w: self.w,
-        }
-    }
-}
-
-impl ops::Mul<f64> for Coordinates {
-    type Output = Self;
-
-    fn mul(self, _rhs: f64) -> Self {
-        Self {
-            x: self.x * _rhs,
-            y: self.y * _rhs,
-            z: self.z * _rhs,
-            w: self.w,
-        }
-    }
-}
-
-impl ops::Div<f64> for Coordinates {
-    type Output = Self;
-
-    fn div(self, _rhs: f64) -> Self {
-        Self {
-            x: self.x / _rhs,
-            y: self.y / _rhs,
-            z: self.z / _rhs,
-            w: self.w,
-        }
+impl From<Coordinates> for Vector {
+    fn from(coords: Coordinates) -> Self {
+        Self::new_vector(coords.x, coords.y, coords.z)
     }
 }
 
 #[cfg(test)]
 impl Coordinates {
-    pub fn equal(self: Self, rhs: Self) -> bool {
+    pub fn equal(self, rhs: Self) -> bool {
         crate::utils::float_equal(self.x, rhs.x)
             && crate::utils::float_equal(self.y, rhs.y)
             && crate::utils::float_equal(self.z, rhs.z)
             && crate::utils::float_equal(self.w, rhs.w)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn w_from_type() {
+        let point: Coordinates = Point::new_point(1.0, 2.0, 3.0).into();
+        let vec: Coordinates = Vector::new_vector(1.0, 2.0, 3.0).into();
+
+        assert_eq!(point.w, 1.0);
+        assert_eq!(vec.w, 0.0);
+        assert_eq!(Point::from(point), Point::new_point(1.0, 2.0, 3.0));
+        assert_eq!(Vector::from(vec), Vector::new_vector(1.0, 2.0, 3.0));
+    }
+}
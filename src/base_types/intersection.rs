@@ -20,6 +20,8 @@ pub struct IntersectionDetails<'a> {
     // the sphere surface as a point
     // that needs to be shadowed
     pub over_point: Point,
+    // time of the ray that made the hit
+    pub time: f64,
 }
 
 #[derive(Clone)]
@@ -68,7 +70,7 @@ pub fn prepare_computations(
     intersections: Vec<Intersection>,
 ) -> IntersectionDetails {
     let point = ray.position(intersections[hit_index].distance);
-    let mut surface_normal = intersections[hit_index].object.normal_at(point);
+    let mut surface_normal = intersections[hit_index].object.normal_at(point, ray.time);
     let eye_normal = -ray.direction;
     let is_inside: bool;
 
@@ -89,16 +91,14 @@ pub fn prepare_computations(
         reflect_vector: ray.direction.reflect(surface_normal),
         refractive_exit_index: 1.0,
         refractive_entry_index: 1.0,
+        time: ray.time,
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{
-        base_types::intersection,
-        world::{Plane, Sphere},
-    };
+    use crate::world::{Plane, Sphere};
 
     #[test]
     fn hit_test() {
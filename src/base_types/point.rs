@@ -1,9 +1,105 @@
-use super::Coordinates;
+use std::ops;
 
-pub type Point = Coordinates;
+use serde::{Deserialize, Serialize};
+
+use super::Vector;
+
+// a position in space. Points can be moved by vectors, and the difference of
+// two points is the vector between them, but adding two points together
+// means nothing so it doesn't compile
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
 
 impl Point {
     pub fn new_point(x: f64, y: f64, z: f64) -> Self {
-        Self { x, y, z, w: 1.0 }
+        Self { x, y, z }
+    }
+}
+
+impl ops::Add<Vector> for Point {
+    type Output = Self;
+
+    fn add(self, _rhs: Vector) -> Self {
+        Self {
+            x: self.x + _rhs.x,
+            y: self.y + _rhs.y,
+            z: self.z + _rhs.z,
+        }
+    }
+}
+
+// offsets every axis by the same amount
+impl ops::Add<f64> for Point {
+    type Output = Self;
+
+    fn add(self, _rhs: f64) -> Self {
+        Self {
+            x: self.x + _rhs,
+            y: self.y + _rhs,
+            z: self.z + _rhs,
+        }
+    }
+}
+
+impl ops::Sub for Point {
+    type Output = Vector;
+
+    fn sub(self, _rhs: Self) -> Vector {
+        Vector::new_vector(self.x - _rhs.x, self.y - _rhs.y, self.z - _rhs.z)
+    }
+}
+
+impl ops::Sub<Vector> for Point {
+    type Output = Self;
+
+    fn sub(self, _rhs: Vector) -> Self {
+        Self {
+            x: self.x - _rhs.x,
+            y: self.y - _rhs.y,
+            z: self.z - _rhs.z,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Point {
+    pub fn equal(self, rhs: Self) -> bool {
+        crate::utils::float_equal(self.x, rhs.x)
+            && crate::utils::float_equal(self.y, rhs.y)
+            && crate::utils::float_equal(self.z, rhs.z)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn add_vector() {
+        let point = Point::new_point(3.0, -2.0, 5.0);
+        let vec = Vector::new_vector(-2.0, 3.0, 1.0);
+
+        assert_eq!(point + vec, Point::new_point(1.0, 1.0, 6.0));
+    }
+
+    #[test]
+    fn sub_point() {
+        let point1 = Point::new_point(3.0, 2.0, 1.0);
+        let point2 = Point::new_point(5.0, 6.0, 7.0);
+
+        assert_eq!(point1 - point2, Vector::new_vector(-2.0, -4.0, -6.0));
+    }
+
+    #[test]
+    fn sub_vector() {
+        let point = Point::new_point(3.0, 2.0, 1.0);
+        let vec = Vector::new_vector(5.0, 6.0, 7.0);
+
+        assert_eq!(point - vec, Point::new_point(-2.0, -4.0, -6.0));
     }
 }
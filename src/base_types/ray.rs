@@ -6,11 +6,23 @@ use crate::matrices::Matrix;
 pub struct Ray {
     pub origin: Point,
     pub direction: Vector,
+    // when the ray was sent, from 0 at the start of the camera's shutter
+    // interval to 1 at its end. Moving objects are intersected where they
+    // are at that time
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Point, direction: Vector) -> Self {
-        Self { origin, direction }
+        Self {
+            origin,
+            direction,
+            time: 0.0,
+        }
+    }
+
+    pub fn with_time(self, time: f64) -> Self {
+        Self { time, ..self }
     }
 
     pub fn position(&self, t: f64) -> Point {
@@ -21,6 +33,7 @@ impl Ray {
         Self {
             origin: matrix.coords_mul(self.origin),
             direction: matrix.coords_mul(self.direction),
+            time: self.time,
         }
     }
 }
@@ -66,4 +79,16 @@ mod test {
         assert_eq!(ray2.origin, Point::new_point(2.0, 6.0, 12.0));
         assert_eq!(ray2.direction, Vector::new_vector(0.0, 3.0, 0.0));
     }
+
+    #[test]
+    fn transform_keeps_time() {
+        let ray = Ray::new(
+            Point::new_point(1.0, 2.0, 3.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        )
+        .with_time(0.25);
+
+        assert_eq!(ray.time, 0.25);
+        assert_eq!(ray.transform(&Matrix::scaling(2.0, 3.0, 4.0)).time, 0.25);
+    }
 }
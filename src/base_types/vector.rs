@@ -1,10 +1,19 @@
-use super::Coordinates;
+use std::ops;
 
-pub type Vector = Coordinates;
+use serde::{Deserialize, Serialize};
+
+// a direction and length, e.g., a ray direction or a surface normal
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Vector {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
 
 impl Vector {
     pub fn new_vector(x: f64, y: f64, z: f64) -> Self {
-        Self { x, y, z, w: 0.0 }
+        Self { x, y, z }
     }
 
     pub fn magnitude(self) -> f64 {
@@ -24,7 +33,6 @@ impl Vector {
             x: self.y * vec.z - self.z * vec.y,
             y: self.z * vec.x - self.x * vec.z,
             z: self.x * vec.y - self.y * vec.x,
-            w: 0.0,
         }
     }
 
@@ -33,10 +41,86 @@ impl Vector {
     }
 }
 
+impl ops::Add for Vector {
+    type Output = Self;
+
+    fn add(self, _rhs: Self) -> Self {
+        Self {
+            x: self.x + _rhs.x,
+            y: self.y + _rhs.y,
+            z: self.z + _rhs.z,
+        }
+    }
+}
+
+impl ops::Sub for Vector {
+    type Output = Self;
+
+    fn sub(self, _rhs: Self) -> Self {
+        Self {
+            x: self.x - _rhs.x,
+            y: self.y - _rhs.y,
+            z: self.z - _rhs.z,
+        }
+    }
+}
+
+impl ops::Neg for Vector {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
+impl ops::Mul<f64> for Vector {
+    type Output = Self;
+
+    fn mul(self, _rhs: f64) -> Self {
+        Self {
+            x: self.x * _rhs,
+            y: self.y * _rhs,
+            z: self.z * _rhs,
+        }
+    }
+}
+
+impl ops::Div<f64> for Vector {
+    type Output = Self;
+
+    fn div(self, _rhs: f64) -> Self {
+        Self {
+            x: self.x / _rhs,
+            y: self.y / _rhs,
+            z: self.z / _rhs,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Vector {
+    pub fn equal(self, rhs: Self) -> bool {
+        crate::utils::float_equal(self.x, rhs.x)
+            && crate::utils::float_equal(self.y, rhs.y)
+            && crate::utils::float_equal(self.z, rhs.z)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::Vector;
 
+    #[test]
+    fn neg() {
+        let vec = Vector::new_vector(1.0, -2.0, 3.0);
+
+        assert_eq!(-vec, Vector::new_vector(-1.0, 2.0, -3.0));
+    }
+
     #[test]
     fn add() {
         let vec1 = Vector::new_vector(1.01, 3.0, 5.5);
@@ -0,0 +1,724 @@
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::thread;
+
+use clap::{Parser, ValueEnum};
+
+use ray_tracer::animation::{render_frames, save_frames};
+use ray_tracer::examples;
+use ray_tracer::scene::{load_json_animation, load_json_scene, load_yaml_scene, save_json_scene};
+use ray_tracer::visuals::{
+    save_animation, AnimationFormat, AnimationTiming, Canvas, ImageFormat, StereoLayout,
+    DEFAULT_WEBP_QUALITY,
+};
+use ray_tracer::world::{Camera, Integrator, Projection, StereoCamera, World};
+
+const OUTPUT_DIR: &str = "./examples";
+
+#[derive(Parser)]
+#[command(
+    name = "ray_tracer",
+    about = "Renders one of the built-in example scenes or a scene file"
+)]
+pub struct Args {
+    /// Built-in example to render
+    #[arg(short, long, value_enum, default_value_t = Example::Plane)]
+    example: Example,
+
+    /// YAML or JSON scene file to render instead of a built-in example
+    #[arg(short, long, conflicts_with = "example")]
+    scene: Option<String>,
+
+    /// Also save the rendered scene, with any overrides applied, as JSON
+    #[arg(long)]
+    save_scene: Option<String>,
+
+    /// Where to write the image, the format is picked from the extension
+    /// [default: ./examples/<example or scene name>.webp]
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// JSON animation to render instead of a single image, e.g.,
+    /// scenes/plane_turntable.json. With a run of '#'s in the output every
+    /// frame is saved as its own numbered image, otherwise they are put
+    /// together into an animated .gif or .webp
+    /// [default output: ./examples/<example or scene name>_####.webp]
+    #[arg(short, long)]
+    animation: Option<String>,
+
+    /// How long each frame of an animated gif or webp is shown, in
+    /// milliseconds [default: from the animation's frame rate]
+    #[arg(long, requires = "animation")]
+    frame_delay: Option<u32>,
+
+    /// How many times an animated gif or webp plays, 0 loops forever
+    #[arg(long, default_value_t = 0, requires = "animation")]
+    loop_count: u16,
+
+    /// Render a stereo pair from two eyes either side of the camera, put
+    /// together in the given layout. Width and height are per eye
+    #[arg(long, value_enum, conflicts_with = "animation")]
+    stereo: Option<StereoOutput>,
+
+    /// Distance between the eyes of a stereo pair, in scene units
+    #[arg(long, default_value_t = 0.065, requires = "stereo")]
+    interocular: f64,
+
+    /// How far ahead the eyes of a stereo pair turn in to look at the same
+    /// point [default: they look straight ahead, as headsets expect]
+    #[arg(long, requires = "stereo")]
+    convergence: Option<f64>,
+
+    /// Image format, overrides the one picked from the output extension
+    #[arg(short, long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// Quality of lossy webp output, from 0 to 100
+    #[arg(short, long, default_value_t = DEFAULT_WEBP_QUALITY)]
+    quality: f32,
+
+    /// Image width in pixels [default: the scene's own width]
+    #[arg(short = 'W', long)]
+    width: Option<usize>,
+
+    /// Image height in pixels [default: keeps the scene's aspect ratio]
+    #[arg(short = 'H', long)]
+    height: Option<usize>,
+
+    /// Field of view in degrees, under 180 for perspective cameras and up to
+    /// 360 for fisheye ones [default: the scene's own field of view]
+    #[arg(long)]
+    fov: Option<f64>,
+
+    /// Rays averaged per pixel to smooth jagged edges [default: the
+    /// scene's own setting, or 1]
+    #[arg(long)]
+    samples: Option<usize>,
+
+    /// Number of render threads [default: number of cpus]
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// How many times a ray can bounce off reflective surfaces
+    /// [default: the scene's own setting, or 5]
+    #[arg(long)]
+    max_depth: Option<usize>,
+
+    /// How light is worked out. The path tracer follows light bouncing
+    /// between surfaces, and needs many --samples to settle
+    /// [default: the scene's own setting, or whitted]
+    #[arg(long, value_enum)]
+    integrator: Option<IntegratorOption>,
+
+    /// Seed for the path tracer's noise, the same seed renders the same
+    /// image [default: the scene's own seed, or 0]
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Turns on ambient occlusion, which darkens creases by tracing this
+    /// many rays around every hit to see how much ambient light reaches it
+    /// [default: the scene's own setting, or off]
+    #[arg(long)]
+    occlusion_samples: Option<usize>,
+
+    /// How far away something can be and still block ambient light, in
+    /// scene units. Also turns on ambient occlusion
+    /// [default: the scene's own setting, or 1]
+    #[arg(long)]
+    occlusion_distance: Option<f64>,
+
+    /// Render just the ambient occlusion, as a grayscale image that is white
+    /// where nothing is near and darker in creases
+    #[arg(long, conflicts_with_all = ["animation", "stereo"])]
+    occlusion_pass: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Example {
+    World,
+    Plane,
+    Perturbed,
+    ShadedSphere,
+    Sphere,
+    Clock,
+    Projectile,
+}
+
+impl Example {
+    fn name(self) -> &'static str {
+        match self {
+            Self::World => "world",
+            Self::Plane => "plane",
+            Self::Perturbed => "perturbed",
+            Self::ShadedSphere => "shaded_sphere",
+            Self::Sphere => "sphere",
+            Self::Clock => "clock",
+            Self::Projectile => "projectile",
+        }
+    }
+
+    // examples that are rendered through a camera, along with the size
+    // they are rendered at by default
+    fn camera_scene_size(self) -> Option<(usize, usize)> {
+        match self {
+            Self::World | Self::Plane => Some((764, 528)),
+            Self::Perturbed => Some((250, 125)),
+            _ => None,
+        }
+    }
+
+    fn camera_scene(self, hsize: usize, vsize: usize) -> (World, Camera) {
+        match self {
+            Self::World => examples::world_scene(hsize, vsize),
+            Self::Plane => examples::plane_scene(hsize, vsize),
+            Self::Perturbed => examples::perturbed_scene(hsize, vsize),
+            _ => unreachable!("{} is not rendered through a camera", self.name()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum IntegratorOption {
+    Whitted,
+    PathTracer,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StereoOutput {
+    SideBySide,
+    OverUnder,
+    Anaglyph,
+}
+
+impl StereoOutput {
+    fn layout(self) -> StereoLayout {
+        match self {
+            Self::SideBySide => StereoLayout::SideBySide,
+            Self::OverUnder => StereoLayout::OverUnder,
+            Self::Anaglyph => StereoLayout::Anaglyph,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Png,
+    Ppm,
+    PpmPlain,
+    Webp,
+    WebpLossless,
+    Hdr,
+    Exr,
+    Pfm,
+}
+
+// renders the requested scene and returns the path the image was written to
+pub fn run(args: Args) -> Result<String, Error> {
+    if let Some(scene) = args.scene.clone() {
+        return run_scene_file(&args, &scene);
+    }
+
+    let example = args.example;
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| default_output(&args, example.name()));
+
+    match example.camera_scene_size() {
+        Some(default_size) => {
+            let kind = output_kind(&args, &output)?;
+            let (world, camera) = prepare_camera_scene(&args, default_size, |hsize, vsize| {
+                example.camera_scene(hsize, vsize)
+            })?;
+            render_camera_scene(&args, world, camera, &output, kind)
+        }
+        None => {
+            let format = output_format(&args, &output)?;
+            render_canvas_example(&args, example)?.save_canvas_as(&output, format)?;
+            Ok(output)
+        }
+    }
+}
+
+fn run_scene_file(args: &Args, scene: &str) -> Result<String, Error> {
+    let output = match args.output.clone() {
+        Some(output) => output,
+        None => {
+            let name = Path::new(scene)
+                .file_stem()
+                .map_or("scene".into(), |stem| stem.to_string_lossy());
+            default_output(args, &name)
+        }
+    };
+    let kind = output_kind(args, &output)?;
+
+    let is_json = Path::new(scene)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let (world, camera) = if is_json {
+        load_json_scene(scene)?
+    } else {
+        load_yaml_scene(scene)?
+    };
+    let default_size = (camera.hsize(), camera.vsize());
+    let (world, camera) = prepare_camera_scene(args, default_size, |hsize, vsize| {
+        // keep where and how the scene's camera looks, at the requested size
+        (world, camera.resized(hsize, vsize))
+    })?;
+
+    render_camera_scene(args, world, camera, &output, kind)
+}
+
+fn default_output(args: &Args, name: &str) -> String {
+    match args.animation {
+        Some(_) => format!("{}/{}_####.webp", OUTPUT_DIR, name),
+        None => format!("{}/{}.webp", OUTPUT_DIR, name),
+    }
+}
+
+// what the output of a camera scene is written as
+enum OutputKind {
+    Image(ImageFormat),
+    // one numbered image per animation frame
+    Frames(ImageFormat),
+    // all animation frames in a single file
+    Animated(AnimationFormat),
+}
+
+fn output_kind(args: &Args, output: &str) -> Result<OutputKind, Error> {
+    if args.animation.is_none() {
+        return Ok(OutputKind::Image(output_format(args, output)?));
+    }
+    if output.contains('#') {
+        return Ok(OutputKind::Frames(output_format(args, output)?));
+    }
+
+    // checks the quality
+    output_format(args, "quality.webp")?;
+    let format = match args.format {
+        Some(OutputFormat::Webp) => AnimationFormat::Webp(args.quality),
+        Some(OutputFormat::WebpLossless) => AnimationFormat::WebpLossless,
+        Some(_) => {
+            return Err(invalid_input(
+                "only webp and webp-lossless can hold an animation, put a run of \
+                 '#'s in the output to save numbered frames instead",
+            ))
+        }
+        None => match AnimationFormat::from_path(output) {
+            Ok(AnimationFormat::Webp(_)) => AnimationFormat::Webp(args.quality),
+            Ok(format) => format,
+            Err(_) => {
+                return Err(invalid_input(&format!(
+                    "{} is not an animated gif or webp, put a run of '#'s in it \
+                     to save numbered frames instead",
+                    output
+                )))
+            }
+        },
+    };
+
+    Ok(OutputKind::Animated(format))
+}
+
+fn output_format(args: &Args, output: &str) -> Result<ImageFormat, Error> {
+    if !(0.0..=100.0).contains(&args.quality) {
+        return Err(invalid_input(&format!(
+            "--quality must be between 0 and 100, got {}",
+            args.quality
+        )));
+    }
+
+    let format = match args.format {
+        Some(OutputFormat::Png) => ImageFormat::Png,
+        Some(OutputFormat::Ppm) => ImageFormat::PpmBinary,
+        Some(OutputFormat::PpmPlain) => ImageFormat::PpmPlain,
+        Some(OutputFormat::Webp) => ImageFormat::Webp(args.quality),
+        Some(OutputFormat::WebpLossless) => ImageFormat::WebpLossless,
+        Some(OutputFormat::Hdr) => ImageFormat::RadianceHdr,
+        Some(OutputFormat::Exr) => ImageFormat::OpenExr,
+        Some(OutputFormat::Pfm) => ImageFormat::Pfm,
+        None => match ImageFormat::from_path(output) {
+            Ok(ImageFormat::Webp(_)) => ImageFormat::Webp(args.quality),
+            Ok(format) => format,
+            Err(_) => {
+                return Err(invalid_input(&format!(
+                    "cannot tell the image format of {}, use an extension of \
+                     png, ppm, webp, hdr, exr or pfm, or pass --format",
+                    output
+                )))
+            }
+        },
+    };
+
+    Ok(format)
+}
+
+// build_scene creates the world and camera for the given image size, the
+// options are then applied on top of them
+fn prepare_camera_scene<F>(
+    args: &Args,
+    default_size: (usize, usize),
+    build_scene: F,
+) -> Result<(World, Camera), Error>
+where
+    F: FnOnce(usize, usize) -> (World, Camera),
+{
+    let (default_width, default_height) = default_size;
+    let (width, height) = match (args.width, args.height) {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, width * default_height / default_width),
+        (None, Some(height)) => (height * default_width / default_height, height),
+        (None, None) => default_size,
+    };
+    check_positive("--width/--height", width.min(height))?;
+
+    let (mut world, mut camera) = build_scene(width, height);
+
+    if let Some(fov) = args.fov {
+        check_field_of_view(fov, camera.projection())?;
+        camera.field_of_view = fov.to_radians();
+        camera = camera.resized(width, height);
+    }
+
+    // scene files can set these, the options only override them
+    if let Some(samples) = args.samples {
+        check_positive("--samples", samples)?;
+        camera.samples_per_pixel = samples;
+    }
+
+    camera.threads = match args.threads {
+        Some(threads) => threads,
+        None => thread::available_parallelism().map_or(1, |threads| threads.get()),
+    };
+    check_positive("--threads", camera.threads)?;
+
+    if let Some(max_depth) = args.max_depth {
+        world.max_reflect_depth = max_depth;
+    }
+
+    let scene_seed = match world.integrator {
+        Integrator::PathTracer { seed } => Some(seed),
+        Integrator::Whitted => None,
+    };
+    let path_trace = match args.integrator {
+        Some(IntegratorOption::Whitted) => false,
+        Some(IntegratorOption::PathTracer) => true,
+        None => scene_seed.is_some(),
+    };
+    world.integrator = if path_trace {
+        Integrator::PathTracer {
+            seed: args.seed.or(scene_seed).unwrap_or(0),
+        }
+    } else if args.seed.is_some() {
+        return Err(invalid_input(
+            "--seed only applies to the path tracer, add --integrator path-tracer",
+        ));
+    } else {
+        Integrator::Whitted
+    };
+
+    if args.occlusion_samples.is_some() || args.occlusion_distance.is_some() {
+        let mut settings = world.ambient_occlusion.unwrap_or_default();
+        if let Some(samples) = args.occlusion_samples {
+            check_positive("--occlusion-samples", samples)?;
+            settings.samples = samples;
+        }
+        if let Some(distance) = args.occlusion_distance {
+            if distance <= 0.0 || distance.is_nan() {
+                return Err(invalid_input(&format!(
+                    "--occlusion-distance must be above 0, got {}",
+                    distance
+                )));
+            }
+            settings.distance = distance;
+        }
+        world.ambient_occlusion = Some(settings);
+    }
+
+    if let Some(path) = &args.save_scene {
+        save_json_scene(path, &world, &camera)?;
+    }
+
+    Ok((world, camera))
+}
+
+// renders a single image, or every frame when an animation is given, and
+// returns what was written
+fn render_camera_scene(
+    args: &Args,
+    mut world: World,
+    mut camera: Camera,
+    output: &str,
+    kind: OutputKind,
+) -> Result<String, Error> {
+    let Some(path) = &args.animation else {
+        let OutputKind::Image(format) = kind else {
+            unreachable!("only animations are saved as several frames")
+        };
+        let canvas = match args.stereo {
+            Some(stereo) => stereo_camera(args, camera)?.render_layout(&world, stereo.layout()),
+            None if args.occlusion_pass => {
+                camera.render_occlusion(&world, &world.ambient_occlusion.unwrap_or_default())
+            }
+            None => camera.render(&world),
+        };
+        canvas.save_canvas_as(output, format)?;
+        return Ok(output.to_string());
+    };
+
+    let animation = load_json_animation(path)?;
+    let frame_count = match kind {
+        OutputKind::Image(_) => unreachable!("animations are saved as several frames"),
+        OutputKind::Frames(format) => {
+            save_frames(&mut world, &mut camera, &animation, output, format)?
+        }
+        OutputKind::Animated(format) => {
+            let frame_delay = args
+                .frame_delay
+                .unwrap_or((1000.0 / animation.frame_rate).round() as u32);
+            let mut frames = Vec::new();
+            render_frames(&mut world, &mut camera, &animation, |_, canvas| {
+                frames.push(canvas);
+                Ok(())
+            })?;
+
+            save_animation(
+                output,
+                &frames,
+                format,
+                AnimationTiming::new(frame_delay, args.loop_count),
+            )?;
+            frames.len()
+        }
+    };
+
+    Ok(format!("{} ({} frames)", output, frame_count))
+}
+
+fn stereo_camera(args: &Args, camera: Camera) -> Result<StereoCamera, Error> {
+    if args.interocular < 0.0 || args.interocular.is_nan() {
+        return Err(invalid_input(&format!(
+            "--interocular can not be negative, got {}",
+            args.interocular
+        )));
+    }
+
+    let mut stereo = StereoCamera::new(camera, args.interocular);
+    if let Some(convergence) = args.convergence {
+        if convergence <= 0.0 || convergence.is_nan() {
+            return Err(invalid_input(&format!(
+                "--convergence must be above 0, got {}",
+                convergence
+            )));
+        }
+        stereo.convergence_distance = convergence;
+    }
+
+    Ok(stereo)
+}
+
+fn render_canvas_example(args: &Args, example: Example) -> Result<Canvas, Error> {
+    let camera_options = [
+        ("--fov", args.fov.is_some()),
+        ("--samples", args.samples.is_some()),
+        ("--threads", args.threads.is_some()),
+        ("--max-depth", args.max_depth.is_some()),
+        ("--save-scene", args.save_scene.is_some()),
+        ("--animation", args.animation.is_some()),
+        ("--stereo", args.stereo.is_some()),
+        ("--integrator", args.integrator.is_some()),
+        ("--seed", args.seed.is_some()),
+        ("--occlusion-samples", args.occlusion_samples.is_some()),
+        ("--occlusion-distance", args.occlusion_distance.is_some()),
+        ("--occlusion-pass", args.occlusion_pass),
+    ];
+    for (option, is_set) in camera_options {
+        if is_set {
+            return Err(invalid_input(&format!(
+                "{} only applies to the world, plane and perturbed examples",
+                option
+            )));
+        }
+    }
+
+    if let Example::ShadedSphere = example {
+        let size = match (args.width, args.height) {
+            (Some(width), Some(height)) if width != height => {
+                return Err(invalid_input(
+                    "the shaded_sphere example is square, --width and --height must match",
+                ))
+            }
+            (Some(size), _) | (None, Some(size)) => size,
+            (None, None) => 500,
+        };
+        check_positive("--width/--height", size)?;
+
+        return Ok(examples::render_shaded_sphere(size));
+    }
+
+    if args.width.is_some() || args.height.is_some() {
+        return Err(invalid_input(&format!(
+            "the {} example has a fixed size",
+            example.name()
+        )));
+    }
+
+    Ok(match example {
+        Example::Sphere => examples::render_sphere(),
+        Example::Clock => examples::render_clock(),
+        _ => examples::render_projectile(),
+    })
+}
+
+// perspective cameras can not see 180 degrees or more, the projections
+// check their own limits
+fn check_field_of_view(fov: f64, projection: Projection) -> Result<(), Error> {
+    if let Projection::Perspective = projection {
+        if !(fov > 0.0 && fov < 180.0) {
+            return Err(invalid_input(&format!(
+                "--fov must be between 0 and 180 degrees, got {}",
+                fov
+            )));
+        }
+    }
+
+    projection
+        .validate(fov.to_radians())
+        .map_err(|error| invalid_input(&format!("--fov of {} degrees: {}", fov, error)))
+}
+
+fn check_positive(option: &str, value: usize) -> Result<(), Error> {
+    if value == 0 {
+        return Err(invalid_input(&format!("{} must be at least 1", option)));
+    }
+
+    Ok(())
+}
+
+fn invalid_input(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // runs the command line up to the first error, which comes before
+    // anything is rendered or written
+    fn error_message(arguments: &[&str]) -> String {
+        let args = Args::try_parse_from([&["ray_tracer"], arguments].concat()).unwrap();
+        let error = run(args).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        error.to_string()
+    }
+
+    #[test]
+    fn field_of_view() {
+        for fov in ["0", "180", "NaN"] {
+            assert_eq!(
+                error_message(&["--fov", fov]),
+                format!("--fov must be between 0 and 180 degrees, got {}", fov)
+            );
+        }
+    }
+
+    #[test]
+    fn fisheye_field_of_view() {
+        assert!(check_field_of_view(220.0, Projection::Fisheye).is_ok());
+        assert!(check_field_of_view(220.0, Projection::Perspective).is_err());
+        assert_eq!(
+            check_field_of_view(400.0, Projection::Fisheye)
+                .unwrap_err()
+                .to_string(),
+            "--fov of 400 degrees: fisheye field of view must be above 0 and at most 2 pi"
+        );
+        assert!(check_field_of_view(f64::NAN, Projection::Fisheye).is_err());
+    }
+
+    #[test]
+    fn stereo() {
+        assert_eq!(
+            error_message(&["--stereo", "anaglyph", "--interocular=-1"]),
+            "--interocular can not be negative, got -1"
+        );
+        assert_eq!(
+            error_message(&["--stereo", "anaglyph", "--interocular", "NaN"]),
+            "--interocular can not be negative, got NaN"
+        );
+        for convergence in ["0", "NaN"] {
+            assert_eq!(
+                error_message(&["--stereo", "anaglyph", "--convergence", convergence]),
+                format!("--convergence must be above 0, got {}", convergence)
+            );
+        }
+    }
+
+    #[test]
+    fn counts() {
+        assert_eq!(
+            error_message(&["--samples", "0"]),
+            "--samples must be at least 1"
+        );
+        assert_eq!(
+            error_message(&["--threads", "0"]),
+            "--threads must be at least 1"
+        );
+        assert_eq!(
+            error_message(&["--width", "0"]),
+            "--width/--height must be at least 1"
+        );
+        assert_eq!(
+            error_message(&["--occlusion-samples", "0"]),
+            "--occlusion-samples must be at least 1"
+        );
+        assert_eq!(
+            error_message(&["--occlusion-distance", "NaN"]),
+            "--occlusion-distance must be above 0, got NaN"
+        );
+    }
+
+    #[test]
+    fn output() {
+        assert_eq!(
+            error_message(&["--quality", "101"]),
+            "--quality must be between 0 and 100, got 101"
+        );
+        assert_eq!(
+            error_message(&["--output", "image.bmp"]),
+            "cannot tell the image format of image.bmp, use an extension of png, ppm, \
+             webp, hdr, exr or pfm, or pass --format"
+        );
+    }
+
+    #[test]
+    fn options_that_do_not_apply() {
+        assert_eq!(
+            error_message(&["--seed", "3"]),
+            "--seed only applies to the path tracer, add --integrator path-tracer"
+        );
+        assert_eq!(
+            error_message(&["--example", "clock", "--samples", "4"]),
+            "--samples only applies to the world, plane and perturbed examples"
+        );
+        assert_eq!(
+            error_message(&["--example", "clock", "--width", "10"]),
+            "the clock example has a fixed size"
+        );
+        assert_eq!(
+            error_message(&["--example", "shaded-sphere", "-W", "10", "-H", "20"]),
+            "the shaded_sphere example is square, --width and --height must match"
+        );
+    }
+
+    #[test]
+    fn conflicting_options() {
+        for arguments in [
+            &["--stereo", "anaglyph", "--occlusion-pass"][..],
+            &["--example", "world", "--scene", "scene.yaml"][..],
+            &["--interocular", "0.1"][..],
+        ] {
+            assert!(Args::try_parse_from([&["ray_tracer"], arguments].concat()).is_err());
+        }
+    }
+}
@@ -6,6 +6,10 @@ use crate::matrices::Matrix;
 use crate::visuals::{Canvas, Color};
 
 pub fn generate_clock(dir: &str) -> Result<(), Error> {
+    render_clock().save_canvas(format!("{}/{}", dir, "clock.webp").as_str())
+}
+
+pub fn render_clock() -> Canvas {
     let mut cnvs = Canvas::new(500, 500);
 
     let origin = Point::new_point(250.0, 250.0, 0.0);
@@ -21,5 +25,5 @@ pub fn generate_clock(dir: &str) -> Result<(), Error> {
         );
     }
 
-    cnvs.save_canvas(format!("{}/{}", dir, "clock.webp").as_str())
+    cnvs
 }
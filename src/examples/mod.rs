@@ -1,4 +1,5 @@
 mod clock;
+mod patterned;
 mod perturbed;
 mod plane;
 mod projectile;
@@ -7,6 +8,7 @@ mod sphere;
 mod world;
 
 pub use clock::*;
+pub use patterned::*;
 pub use perturbed::*;
 pub use plane::*;
 pub use projectile::*;
@@ -0,0 +1,60 @@
+use crate::base_types::{Point, Vector};
+use crate::matrices::Matrix;
+use crate::visuals::{CheckerPattern, Color, Material, Pattern, StripePattern};
+use crate::world::{Camera, Plane, Sphere, World};
+
+// a reflective material with the given number of nested checker patterns,
+// for measuring what patterns cost while shading
+pub fn patterned_material(pattern_count: usize) -> Material {
+    let mut patterns: Vec<Box<dyn Pattern>> = Vec::new();
+    for i in 0..pattern_count {
+        let mut pattern = CheckerPattern::new(
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+            Some([
+                Box::new(StripePattern::new(
+                    Color::new(1.0, 1.0, 1.0),
+                    Color::new(0.0, 0.0, 0.0),
+                    None,
+                )),
+                Box::new(StripePattern::new(
+                    Color::new(0.0, 1.0, 0.0),
+                    Color::new(0.0, 0.0, 0.0),
+                    None,
+                )),
+            ]),
+        );
+        pattern.set_transform(Matrix::scaling(0.25, 0.25, 0.25).rotate_y(i as f64));
+        patterns.push(Box::new(pattern));
+    }
+
+    Material {
+        patterns,
+        reflective: 0.3,
+        ..Material::default()
+    }
+}
+
+// a sphere on a floor, both with patterned_material
+pub fn patterned_scene(hsize: usize, vsize: usize, pattern_count: usize) -> (World, Camera) {
+    let mut world = World::new();
+    world.objects.push(Box::new(Plane::new(
+        Matrix::translation(0.0, -1.0, 0.0),
+        patterned_material(pattern_count),
+    )));
+    world.objects.push(Box::new(Sphere::new(
+        Point::new_point(0.0, 0.0, 0.0),
+        1.0,
+        Matrix::new_identity(),
+        patterned_material(pattern_count),
+    )));
+
+    let mut camera = Camera::new(hsize, vsize, 1.0);
+    camera.set_transform(Matrix::view_transform(
+        Point::new_point(0.0, 1.0, -5.0),
+        Point::new_point(0.0, 0.0, 0.0),
+        Vector::new_vector(0.0, 1.0, 0.0),
+    ));
+
+    (world, camera)
+}
@@ -7,6 +7,15 @@ use crate::visuals::{CheckerPattern, Color, Material, Pattern, PerturbPattern, S
 use crate::world::{Camera, Light, Object, Plane, Sphere, World};
 
 pub fn generate_perturbed(dir: &str) -> Result<(), Error> {
+    // let (world, camera) = perturbed_scene(764, 528);
+    let (world, camera) = perturbed_scene(250, 125);
+
+    camera
+        .render(&world)
+        .save_canvas(format!("{}/{}", dir, "perturbed.webp").as_str())
+}
+
+pub fn perturbed_scene(hsize: usize, vsize: usize) -> (World, Camera) {
     let mut floor = Plane::default();
     floor.set_material(Material {
         color: Color::new(1.0, 0.9, 0.9),
@@ -90,15 +99,12 @@ pub fn generate_perturbed(dir: &str) -> Result<(), Error> {
         // ),
     ];
 
-    let mut camera = Camera::new(250, 125, PI / 2.0);
-    // let mut camera = Camera::new(764, 528, PI / 2.0);
-    camera.transform = Matrix::view_transform(
+    let mut camera = Camera::new(hsize, vsize, PI / 2.0);
+    camera.set_transform(Matrix::view_transform(
         Point::new_point(0.0, 1.5, -5.0),
         Point::new_point(0.0, 1.0, 0.0),
         Vector::new_vector(0.0, 1.0, 0.0),
-    );
+    ));
 
-    camera
-        .render(&world)
-        .save_canvas(format!("{}/{}", dir, "perturbed.webp").as_str())
+    (world, camera)
 }
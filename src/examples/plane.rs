@@ -9,6 +9,15 @@ use crate::visuals::{
 use crate::world::{Camera, Light, Object, Plane, Sphere, World};
 
 pub fn generate_plane(dir: &str) -> Result<(), Error> {
+    // let (world, camera) = plane_scene(250, 125);
+    let (world, camera) = plane_scene(764, 528);
+
+    camera
+        .render(&world)
+        .save_canvas(format!("{}/{}", dir, "plane.webp").as_str())
+}
+
+pub fn plane_scene(hsize: usize, vsize: usize) -> (World, Camera) {
     let mut floor = Plane::default();
     floor.set_material(Material {
         color: Color::new(1.0, 0.9, 0.9),
@@ -90,8 +99,11 @@ pub fn generate_plane(dir: &str) -> Result<(), Error> {
         patterns: vec![Box::new(ring_pattern)],
         ..Material::default()
     });
-    right_sphere
-        .set_transform(Matrix::translation(1.5, 0.5, -0.5).mul(&Matrix::scaling(0.5, 0.5, 0.5)));
+    right_sphere.set_transform(
+        Matrix::new_identity()
+            .scale(0.5, 0.5, 0.5)
+            .translate(1.5, 0.5, -0.5),
+    );
 
     let mut left_sphere = Sphere::default();
     let gradient_pattern =
@@ -105,9 +117,10 @@ pub fn generate_plane(dir: &str) -> Result<(), Error> {
         ..Material::default()
     });
     left_sphere.set_transform(
-        Matrix::translation(-1.0, 1.0, -0.75)
-            .mul(&Matrix::scaling(0.33, 0.33, 0.33))
-            .mul(&Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+        Matrix::new_identity()
+            .shear(1.0, 0.0, 0.0, 0.0, 0.0, 0.0)
+            .scale(0.33, 0.33, 0.33)
+            .translate(-1.0, 1.0, -0.75),
     );
 
     let mut world = World::new();
@@ -128,15 +141,12 @@ pub fn generate_plane(dir: &str) -> Result<(), Error> {
         // ),
     ];
 
-    // let mut camera = Camera::new(250, 125, PI / 2.0);
-    let mut camera = Camera::new(764, 528, PI / 2.0);
-    camera.transform = Matrix::view_transform(
+    let mut camera = Camera::new(hsize, vsize, PI / 2.0);
+    camera.set_transform(Matrix::view_transform(
         Point::new_point(0.0, 1.5, -5.0),
         Point::new_point(0.0, 1.0, 0.0),
         Vector::new_vector(0.0, 1.0, 0.0),
-    );
+    ));
 
-    camera
-        .render(&world)
-        .save_canvas(format!("{}/{}", dir, "plane.webp").as_str())
+    (world, camera)
 }
@@ -14,6 +14,10 @@ struct Environment {
 }
 
 pub fn generate_projectile(dir: &str) -> Result<(), Error> {
+    render_projectile().save_canvas(format!("{}/{}", dir, "projectile.webp").as_str())
+}
+
+pub fn render_projectile() -> Canvas {
     let mut proj = Projectile {
         point: Point::new_point(0.0, 1.0, 0.0),
         velocity: Vector::new_vector(1.0, 1.8, 0.0).normalize() * 11.25,
@@ -35,7 +39,7 @@ pub fn generate_projectile(dir: &str) -> Result<(), Error> {
         );
     }
 
-    cnvs.save_canvas(format!("{}/{}", dir, "projectile.webp").as_str())
+    cnvs
 }
 
 fn tick(env: &Environment, proj: Projectile) -> Projectile {
@@ -3,23 +3,32 @@ use std::io::Error;
 use crate::base_types::{Point, Ray, Vector};
 use crate::matrices::Matrix;
 use crate::visuals::{Canvas, Color};
-use crate::world::{Light, Object, Sphere};
+use crate::world::{Light, Object, Sphere, SurfacePoint};
+
+// size the scene below was laid out for, everything is scaled relative to it
+const SHADED_SPHERE_SIZE: usize = 500;
 
 pub fn generate_shaded_sphere(dir: &str) -> Result<(), Error> {
-    let mut cnvs = Canvas::new(500, 500);
+    render_shaded_sphere(SHADED_SPHERE_SIZE)
+        .save_canvas(format!("{}/{}", dir, "shaded_sphere.webp").as_str())
+}
+
+pub fn render_shaded_sphere(size: usize) -> Canvas {
+    let scale = size as f64 / SHADED_SPHERE_SIZE as f64;
+    let mut cnvs = Canvas::new(size, size);
 
     let mut sphere = Sphere::default();
-    sphere.set_transform(
-        Matrix::translation(250.0, 250.0, 10.0).mul(&Matrix::scaling(150.0, 150.0, 150.0)),
-    );
+    sphere.set_transform(Matrix::translation(250.0 * scale, 250.0 * scale, 10.0).mul(
+        &Matrix::scaling(150.0 * scale, 150.0 * scale, 150.0 * scale),
+    ));
     sphere.get_material_mut().color = Color::new(1.0, 0.2, 1.0);
     let light_source = Light::new(
-        Point::new_point(100.0, 450.0, -10.0),
+        Point::new_point(100.0 * scale, 450.0 * scale, -10.0),
         Color::new(1.0, 1.0, 1.0),
     );
 
-    for i in 0..500 {
-        for j in 0..500 {
+    for i in 0..size {
+        for j in 0..size {
             let ray = Ray::new(
                 Point::new_point(i as f64, j as f64, -5.0),
                 Vector::new_vector(0.0, 0.0, 1.0),
@@ -30,9 +39,11 @@ pub fn generate_shaded_sphere(dir: &str) -> Result<(), Error> {
                     j,
                     light_source.lighting(
                         &sphere,
-                        ray.position(interection[0].distance),
-                        -ray.direction,
-                        sphere.normal_at(Point::new_point(i as f64, j as f64, 0.0)),
+                        &SurfacePoint::new(
+                            ray.position(interection[0].distance),
+                            -ray.direction,
+                            sphere.normal_at(Point::new_point(i as f64, j as f64, 0.0), 0.0),
+                        ),
                         false,
                     ),
                 );
@@ -40,5 +51,5 @@ pub fn generate_shaded_sphere(dir: &str) -> Result<(), Error> {
         }
     }
 
-    cnvs.save_canvas(format!("{}/{}", dir, "shaded_sphere.webp").as_str())
+    cnvs
 }
@@ -6,6 +6,10 @@ use crate::visuals::{Canvas, Color};
 use crate::world::{Object, Sphere};
 
 pub fn generate_sphere(dir: &str) -> Result<(), Error> {
+    render_sphere().save_canvas(format!("{}/{}", dir, "sphere.webp").as_str())
+}
+
+pub fn render_sphere() -> Canvas {
     let mut cnvs = Canvas::new(500, 500);
 
     let mut sphere = Sphere::default();
@@ -25,5 +29,5 @@ pub fn generate_sphere(dir: &str) -> Result<(), Error> {
         }
     }
 
-    cnvs.save_canvas(format!("{}/{}", dir, "sphere.webp").as_str())
+    cnvs
 }
@@ -7,6 +7,15 @@ use crate::visuals::{Color, Material};
 use crate::world::{Camera, Light, Object, Sphere, World};
 
 pub fn generate_world(dir: &str) -> Result<(), Error> {
+    // let (world, camera) = world_scene(250, 125);
+    let (world, camera) = world_scene(764, 528);
+
+    camera
+        .render(&world)
+        .save_canvas(format!("{}/{}", dir, "world.webp").as_str())
+}
+
+pub fn world_scene(hsize: usize, vsize: usize) -> (World, Camera) {
     let floor_material = Material {
         color: Color::new(1.0, 0.9, 0.9),
         diffuse: 0.7,
@@ -16,27 +25,32 @@ pub fn generate_world(dir: &str) -> Result<(), Error> {
     // floor is sphere that is flattened with matte texture
     let mut floor = Sphere::default();
     floor.set_material(floor_material.clone());
-    floor
-        .set_transform(Matrix::scaling(10.0, 0.01, 10.0).mul(&Matrix::translation(0.0, -1.0, 0.0)));
+    floor.set_transform(
+        Matrix::new_identity()
+            .translate(0.0, -1.0, 0.0)
+            .scale(10.0, 0.01, 10.0),
+    );
 
     // left_wall is sphere that is rotated into place
     let mut left_wall = Sphere::default();
     left_wall.set_material(floor_material.clone());
     left_wall.set_transform(
-        Matrix::translation(0.0, 0.0, 5.0)
-            .mul(&Matrix::rotation_y(-PI / 4.0))
-            .mul(&Matrix::rotation_x(PI / 2.0))
-            .mul(&Matrix::scaling(10.0, 0.01, 10.0)),
+        Matrix::new_identity()
+            .scale(10.0, 0.01, 10.0)
+            .rotate_x(PI / 2.0)
+            .rotate_y(-PI / 4.0)
+            .translate(0.0, 0.0, 5.0),
     );
 
     // right_wall is sphere that is rotated into place
     let mut right_wall = Sphere::default();
     right_wall.set_material(floor_material);
     right_wall.set_transform(
-        Matrix::translation(0.0, 0.0, 5.0)
-            .mul(&Matrix::rotation_y(PI / 4.0))
-            .mul(&Matrix::rotation_x(PI / 2.0))
-            .mul(&Matrix::scaling(10.0, 0.01, 10.0)),
+        Matrix::new_identity()
+            .scale(10.0, 0.01, 10.0)
+            .rotate_x(PI / 2.0)
+            .rotate_y(PI / 4.0)
+            .translate(0.0, 0.0, 5.0),
     );
 
     let mut middle_sphere = Sphere::default();
@@ -55,8 +69,11 @@ pub fn generate_world(dir: &str) -> Result<(), Error> {
         specular: 0.3,
         ..Material::default()
     });
-    right_sphere
-        .set_transform(Matrix::translation(1.5, 0.5, -0.5).mul(&Matrix::scaling(0.5, 0.5, 0.5)));
+    right_sphere.set_transform(
+        Matrix::new_identity()
+            .scale(0.5, 0.5, 0.5)
+            .translate(1.5, 0.5, -0.5),
+    );
 
     let mut left_sphere = Sphere::default();
     left_sphere.set_material(Material {
@@ -66,9 +83,10 @@ pub fn generate_world(dir: &str) -> Result<(), Error> {
         ..Material::default()
     });
     left_sphere.set_transform(
-        Matrix::translation(-1.0, 1.0, -0.75)
-            .mul(&Matrix::scaling(0.33, 0.33, 0.33))
-            .mul(&Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+        Matrix::new_identity()
+            .shear(1.0, 0.0, 0.0, 0.0, 0.0, 0.0)
+            .scale(0.33, 0.33, 0.33)
+            .translate(-1.0, 1.0, -0.75),
     );
 
     let mut world = World::new();
@@ -90,15 +108,12 @@ pub fn generate_world(dir: &str) -> Result<(), Error> {
         ),
     ];
 
-    // let mut camera = Camera::new(250, 125, PI / 3.0);
-    let mut camera = Camera::new(764, 528, PI / 3.0);
-    camera.transform = Matrix::view_transform(
+    let mut camera = Camera::new(hsize, vsize, PI / 3.0);
+    camera.set_transform(Matrix::view_transform(
         Point::new_point(0.0, 1.5, -5.0),
         Point::new_point(0.0, 1.0, 0.0),
         Vector::new_vector(0.0, 1.0, 0.0),
-    );
+    ));
 
-    camera
-        .render(&world)
-        .save_canvas(format!("{}/{}", dir, "world.webp").as_str())
+    (world, camera)
 }
@@ -0,0 +1,8 @@
+pub mod animation;
+pub mod base_types;
+pub mod examples;
+pub mod matrices;
+pub mod scene;
+pub mod utils;
+pub mod visuals;
+pub mod world;
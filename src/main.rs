@@ -1,24 +1,18 @@
-use std::io::Error;
+use std::process::ExitCode;
 
-mod base_types;
-mod matrices;
-mod utils;
-mod visuals;
-mod world;
+use clap::Parser;
 
-#[allow(dead_code)]
-mod examples;
+mod cli;
 
-const OUTPUT_DIR: &str = "./examples";
-
-fn main() -> Result<(), Error> {
-    // examples::generate_projectile(OUTPUT_DIR)?;
-    // examples::generate_clock(OUTPUT_DIR)?;
-    // examples::generate_sphere(OUTPUT_DIR)?;
-    // examples::generate_shaded_sphere(OUTPUT_DIR)?;
-    // examples::generate_perturbed(OUTPUT_DIR)?;
-    // examples::generate_world(OUTPUT_DIR)?;
-    examples::generate_plane(OUTPUT_DIR)?;
-
-    Ok(())
+fn main() -> ExitCode {
+    match cli::run(cli::Args::parse()) {
+        Ok(output) => {
+            println!("wrote {}", output);
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
 }
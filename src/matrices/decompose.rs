@@ -0,0 +1,207 @@
+use super::m_4d::Matrix;
+use super::quaternion::Quaternion;
+use crate::base_types::Vector;
+
+// how far apart the axes of a matrix may be from right angles and still be
+// read as rotation and scaling without shearing
+const SHEAR_TOLERANCE: f64 = 1e-6;
+
+// an affine transform split into parts that can each be interpolated,
+// applied as scaling first, then rotation, then translation
+#[derive(Clone, Copy)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Decomposition {
+    pub translation: Vector,
+    pub rotation: Quaternion,
+    pub scale: Vector,
+}
+
+impl Decomposition {
+    pub fn to_matrix(&self) -> Matrix {
+        Matrix::scaling(self.scale.x, self.scale.y, self.scale.z)
+            .then(&self.rotation.to_matrix())
+            .translate(self.translation.x, self.translation.y, self.translation.z)
+    }
+}
+
+impl Matrix {
+    // None when the matrix is not made of only translation, rotation and
+    // scaling, e.g., it shears, projects or flattens
+    pub fn decompose(&self) -> Option<Decomposition> {
+        if self.rows[3] != [0.0, 0.0, 0.0, 1.0] {
+            return None;
+        }
+
+        let translation = Vector::new_vector(self.rows[0][3], self.rows[1][3], self.rows[2][3]);
+        let mut axes = [0, 1, 2]
+            .map(|col| Vector::new_vector(self.rows[0][col], self.rows[1][col], self.rows[2][col]));
+        let mut scale = axes.map(Vector::magnitude);
+        if scale
+            .iter()
+            .any(|&length| length < crate::utils::FLOAT_DIFF)
+        {
+            return None;
+        }
+        for (axis, length) in axes.iter_mut().zip(scale) {
+            *axis = *axis / length;
+        }
+
+        if axes[0].dot(axes[1]).abs() > SHEAR_TOLERANCE
+            || axes[0].dot(axes[2]).abs() > SHEAR_TOLERANCE
+            || axes[1].dot(axes[2]).abs() > SHEAR_TOLERANCE
+        {
+            return None;
+        }
+
+        // a mirrored matrix is not a rotation, so the mirroring goes into
+        // the scale instead
+        if axes[0].cross(axes[1]).dot(axes[2]) < 0.0 {
+            scale[0] = -scale[0];
+            axes[0] = -axes[0];
+        }
+
+        let mut rotation = Matrix::new_identity();
+        for (col, axis) in axes.iter().enumerate() {
+            rotation.rows[0][col] = axis.x;
+            rotation.rows[1][col] = axis.y;
+            rotation.rows[2][col] = axis.z;
+        }
+
+        Some(Decomposition {
+            translation,
+            rotation: Quaternion::from_matrix(&rotation),
+            scale: Vector::new_vector(scale[0], scale[1], scale[2]),
+        })
+    }
+
+    // blends from self at t = 0 to other at t = 1. The transforms are split
+    // into translation, rotation and scaling so that rotations turn smoothly
+    // instead of shrinking halfway through, like blending the matrices
+    // directly would. Matrices that can't be split, e.g., ones that shear,
+    // are blended entry by entry
+    pub fn interpolate(&self, other: &Matrix, t: f64) -> Matrix {
+        if let (Some(from), Some(to)) = (self.decompose(), other.decompose()) {
+            return Decomposition {
+                translation: from.translation + (to.translation - from.translation) * t,
+                rotation: from.rotation.slerp(to.rotation, t),
+                scale: from.scale + (to.scale - from.scale) * t,
+            }
+            .to_matrix();
+        }
+
+        let mut matrix = self.clone();
+        for (row, other_row) in matrix.rows.iter_mut().zip(other.rows) {
+            for (value, other_value) in row.iter_mut().zip(other_row) {
+                *value += (other_value - *value) * t;
+            }
+        }
+
+        matrix
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn decompose() {
+        let matrix = Matrix::new_identity()
+            .scale(2.0, 3.0, 4.0)
+            .rotate_y(PI / 2.0)
+            .translate(1.0, -2.0, 5.0);
+        let decomposition = matrix.decompose().unwrap();
+
+        assert!(decomposition
+            .translation
+            .equal(Vector::new_vector(1.0, -2.0, 5.0)));
+        assert!(decomposition.scale.equal(Vector::new_vector(2.0, 3.0, 4.0)));
+        assert!(decomposition
+            .rotation
+            .same_rotation(Quaternion::from_axis_angle(
+                Vector::new_vector(0.0, 1.0, 0.0),
+                PI / 2.0
+            )));
+        assert!(decomposition.to_matrix().equal(&matrix));
+    }
+
+    #[test]
+    fn decompose_mirrored() {
+        let matrix = Matrix::scaling(-1.0, 1.0, 1.0).rotate_x(0.5);
+        let decomposition = matrix.decompose().unwrap();
+
+        assert!(decomposition
+            .scale
+            .equal(Vector::new_vector(-1.0, 1.0, 1.0)));
+        assert!(decomposition.to_matrix().equal(&matrix));
+    }
+
+    #[test]
+    fn not_decomposable() {
+        assert!(Matrix::scaling(0.0, 1.0, 1.0).decompose().is_none());
+        assert!(Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0)
+            .decompose()
+            .is_none());
+
+        let mut projection = Matrix::new_identity();
+        projection.rows[3][2] = 1.0;
+        assert!(projection.decompose().is_none());
+    }
+
+    #[test]
+    fn interpolate_matrix_rotates() {
+        let from = Matrix::new_identity().translate(0.0, 1.0, 0.0);
+        let to = Matrix::new_identity()
+            .scale(3.0, 3.0, 3.0)
+            .rotate_y(PI / 2.0)
+            .translate(2.0, 1.0, 0.0);
+        let halfway = from.interpolate(&to, 0.5);
+
+        assert!(halfway.equal(
+            &Matrix::new_identity()
+                .scale(2.0, 2.0, 2.0)
+                .rotate_y(PI / 4.0)
+                .translate(1.0, 1.0, 0.0)
+        ));
+        assert!(from.interpolate(&to, 1.0).equal(&to));
+    }
+
+    #[test]
+    fn interpolate_sheared_matrix() {
+        let from = Matrix::new_identity();
+        let to = Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+
+        assert!(from
+            .interpolate(&to, 0.5)
+            .equal(&Matrix::shearing(0.5, 0.0, 0.0, 0.0, 0.0, 0.0)));
+    }
+
+    fn scale_strategy() -> impl Strategy<Value = f64> {
+        prop_oneof![-5.0..-0.1, 0.1..5.0]
+    }
+
+    proptest! {
+        #[test]
+        fn decompose_round_trip(
+            (sx, sy, sz) in (scale_strategy(), scale_strategy(), scale_strategy()),
+            (ax, ay, az) in (-1.0..1.0, -1.0..1.0, -1.0..1.0),
+            theta in -PI..PI,
+            (tx, ty, tz) in (-10.0..10.0, -10.0..10.0, -10.0..10.0),
+        ) {
+            let axis = Vector::new_vector(ax, ay, az);
+            prop_assume!(axis.magnitude() > 0.01);
+            let matrix = Matrix::scaling(sx, sy, sz)
+                .rotate_axis(axis, theta)
+                .translate(tx, ty, tz);
+            let recomposed = matrix.decompose().unwrap().to_matrix();
+
+            for (row, expected_row) in recomposed.rows.iter().zip(matrix.rows) {
+                for (value, expected) in row.iter().zip(expected_row) {
+                    prop_assert!((value - expected).abs() < 1e-8);
+                }
+            }
+        }
+    }
+}
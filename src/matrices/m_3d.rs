@@ -12,7 +12,7 @@ impl Matrix3D {
     pub fn cofactor(&self, row: usize, col: usize) -> f64 {
         let minor = self.minor(row, col);
 
-        if (row + col) % 2 == 0 || minor == 0.0 {
+        if (row + col).is_multiple_of(2) || minor == 0.0 {
             return minor;
         }
 
@@ -1,8 +1,23 @@
+use std::ops;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
 use super::m_3d::Matrix3D;
-use crate::base_types::Coordinates;
+use crate::base_types::{Coordinates, Point, Vector};
+
+// the determinant can be no bigger than the product of the row lengths,
+// or of the column lengths, and only gets near zero against them when the
+// rows nearly line up. below this fraction of the smaller product the
+// matrix is taken to have no inverse. comparing against the entries keeps
+// tiny but valid scalings, e.g., by 1e-4 with a determinant of 1e-12,
+// invertible
+pub const MIN_DETERMINANT_RATIO: f64 = 1e-12;
 
 #[cfg_attr(test, derive(Debug, PartialEq))]
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+// saved as just the rows
+#[serde(transparent)]
 pub struct Matrix {
     pub rows: [[f64; 4]; 4],
 }
@@ -29,33 +44,94 @@ impl Matrix {
         Self { rows }
     }
 
+    // closed form inverse built from the 2x2 determinants of the top two
+    // and bottom two rows, see https://www.geometrictools.com/Documentation/LaplaceExpansionTheorem.pdf
     pub fn inverse(&self) -> Option<Self> {
-        let determinant = self.determinant();
-        // no determinant means this matrix cannot be inverted
-        if determinant == 0.0 {
+        let a = &self.rows;
+        let (s, c) = self.sub_determinants();
+        let determinant = Self::determinant_from(&s, &c);
+        let row_lengths: f64 = (0..4)
+            .map(|i| (0..4).map(|j| a[i][j] * a[i][j]).sum::<f64>().sqrt())
+            .product();
+        let column_lengths: f64 = (0..4)
+            .map(|j| (0..4).map(|i| a[i][j] * a[i][j]).sum::<f64>().sqrt())
+            .product();
+        // NaN entries, e.g., from normalizing a zero vector, give no inverse
+        if determinant.is_nan()
+            || determinant.abs() <= MIN_DETERMINANT_RATIO * row_lengths.min(column_lengths)
+        {
             return None;
         }
 
-        let mut rows = [[0.0; 4]; 4];
+        let rows = [
+            [
+                a[1][1] * c[5] - a[1][2] * c[4] + a[1][3] * c[3],
+                -a[0][1] * c[5] + a[0][2] * c[4] - a[0][3] * c[3],
+                a[3][1] * s[5] - a[3][2] * s[4] + a[3][3] * s[3],
+                -a[2][1] * s[5] + a[2][2] * s[4] - a[2][3] * s[3],
+            ],
+            [
+                -a[1][0] * c[5] + a[1][2] * c[2] - a[1][3] * c[1],
+                a[0][0] * c[5] - a[0][2] * c[2] + a[0][3] * c[1],
+                -a[3][0] * s[5] + a[3][2] * s[2] - a[3][3] * s[1],
+                a[2][0] * s[5] - a[2][2] * s[2] + a[2][3] * s[1],
+            ],
+            [
+                a[1][0] * c[4] - a[1][1] * c[2] + a[1][3] * c[0],
+                -a[0][0] * c[4] + a[0][1] * c[2] - a[0][3] * c[0],
+                a[3][0] * s[4] - a[3][1] * s[2] + a[3][3] * s[0],
+                -a[2][0] * s[4] + a[2][1] * s[2] - a[2][3] * s[0],
+            ],
+            [
+                -a[1][0] * c[3] + a[1][1] * c[1] - a[1][2] * c[0],
+                a[0][0] * c[3] - a[0][1] * c[1] + a[0][2] * c[0],
+                -a[3][0] * s[3] + a[3][1] * s[1] - a[3][2] * s[0],
+                a[2][0] * s[3] - a[2][1] * s[1] + a[2][2] * s[0],
+            ],
+        ];
+
+        Some(Self::new_with_rows(rows).scalar_div(determinant))
+    }
 
-        for (i, row) in rows.iter_mut().enumerate() {
-            for (j, entry) in row.iter_mut().enumerate() {
-                *entry = self.cofactor(i, j);
-            }
-        }
+    pub fn determinant(&self) -> f64 {
+        let (s, c) = self.sub_determinants();
+        Self::determinant_from(&s, &c)
+    }
 
-        let inv_matrix = Self::new_with_rows(rows).transpose();
-        Some(inv_matrix.scalar_div(determinant))
+    // determinants of every 2x2 matrix that can be made from two columns of
+    // the top two rows (s) and of the bottom two rows (c)
+    fn sub_determinants(&self) -> ([f64; 6], [f64; 6]) {
+        let a = &self.rows;
+        let s = [
+            a[0][0] * a[1][1] - a[1][0] * a[0][1],
+            a[0][0] * a[1][2] - a[1][0] * a[0][2],
+            a[0][0] * a[1][3] - a[1][0] * a[0][3],
+            a[0][1] * a[1][2] - a[1][1] * a[0][2],
+            a[0][1] * a[1][3] - a[1][1] * a[0][3],
+            a[0][2] * a[1][3] - a[1][2] * a[0][3],
+        ];
+        let c = [
+            a[2][0] * a[3][1] - a[3][0] * a[2][1],
+            a[2][0] * a[3][2] - a[3][0] * a[2][2],
+            a[2][0] * a[3][3] - a[3][0] * a[2][3],
+            a[2][1] * a[3][2] - a[3][1] * a[2][2],
+            a[2][1] * a[3][3] - a[3][1] * a[2][3],
+            a[2][2] * a[3][3] - a[3][2] * a[2][3],
+        ];
+
+        (s, c)
+    }
+
+    fn determinant_from(s: &[f64; 6], c: &[f64; 6]) -> f64 {
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
     }
 
     pub fn transpose(&self) -> Self {
         let mut rows = [[0.0; 4]; 4];
 
-        for i in 0..4 {
-            for j in i..4 {
-                let temp = self.rows[i][j];
-                rows[i][j] = self.rows[j][i];
-                rows[j][i] = temp;
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = self.rows[j][i];
             }
         }
 
@@ -76,7 +152,13 @@ impl Matrix {
         Self { rows: new_rows }
     }
 
-    pub fn coords_mul(&self, _rhs: Coordinates) -> Coordinates {
+    // points come back as points and vectors as vectors, with the w picked by
+    // the type so vectors are never translated
+    pub fn coords_mul<T>(&self, _rhs: T) -> T
+    where
+        T: Into<Coordinates> + From<Coordinates>,
+    {
+        let _rhs: Coordinates = _rhs.into();
         let mut new_coords = Coordinates {
             x: 0.0,
             y: 0.0,
@@ -99,7 +181,7 @@ impl Matrix {
             + self.rows[2][2] * _rhs.z
             + self.rows[2][3] * _rhs.w;
 
-        new_coords
+        new_coords.into()
     }
 
     fn scalar_div(self, _rhs: f64) -> Self {
@@ -112,11 +194,65 @@ impl Matrix {
 
         Self { rows: new_rows }
     }
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
+impl ops::Mul for Matrix {
+    type Output = Self;
+
+    fn mul(self, _rhs: Self) -> Self {
+        Matrix::mul(&self, &_rhs)
+    }
+}
+
+impl ops::Mul<&Matrix> for &Matrix {
+    type Output = Matrix;
+
+    fn mul(self, _rhs: &Matrix) -> Matrix {
+        Matrix::mul(self, _rhs)
+    }
+}
+
+impl<T> ops::Mul<T> for &Matrix
+where
+    T: Into<Coordinates> + From<Coordinates>,
+{
+    type Output = T;
+
+    fn mul(self, _rhs: T) -> T {
+        self.coords_mul(_rhs)
+    }
+}
+
+impl ops::Mul<Point> for Matrix {
+    type Output = Point;
+
+    fn mul(self, _rhs: Point) -> Point {
+        self.coords_mul(_rhs)
+    }
+}
+
+impl ops::Mul<Vector> for Matrix {
+    type Output = Vector;
+
+    fn mul(self, _rhs: Vector) -> Vector {
+        self.coords_mul(_rhs)
+    }
+}
+
+// helpers for the tests, including the cofactor expansion the closed form
+// inverse is checked against
+#[cfg(test)]
+impl Matrix {
     fn cofactor(&self, row: usize, col: usize) -> f64 {
         let minor = self.minor(row, col);
 
-        if (row + col) % 2 == 0 || minor == 0.0 {
+        if (row + col).is_multiple_of(2) || minor == 0.0 {
             return minor;
         }
 
@@ -127,7 +263,8 @@ impl Matrix {
         self.sub_matrix(row, col).determinant()
     }
 
-    fn determinant(&self) -> f64 {
+    // determinant by cofactor expansion, slow but easy to check by hand
+    fn cofactor_determinant(&self) -> f64 {
         self.rows[0][0] * self.cofactor(0, 0)
             + self.rows[0][1] * self.cofactor(0, 1)
             + self.rows[0][2] * self.cofactor(0, 2)
@@ -158,16 +295,7 @@ impl Matrix {
 
         Matrix3D::new_with_rows(rows)
     }
-}
 
-impl Default for Matrix {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-#[cfg(test)]
-impl Matrix {
     pub fn equal(&self, _rhs: &Self) -> bool {
         let col_length = self.rows.len();
         let row_length = self.rows[0].len();
@@ -187,6 +315,8 @@ impl Matrix {
 mod test {
     use super::*;
     use crate::base_types::Vector;
+    use proptest::prelude::*;
+    use std::f64::consts::PI;
 
     #[test]
     fn equal() {
@@ -327,6 +457,7 @@ mod test {
         assert_eq!(matrix.cofactor(0, 1), 447.0);
         assert_eq!(matrix.cofactor(0, 2), 210.0);
         assert_eq!(matrix.cofactor(0, 3), 51.0);
+        assert_eq!(matrix.cofactor_determinant(), -4071.0);
         assert_eq!(matrix.determinant(), -4071.0);
     }
 
@@ -362,4 +493,126 @@ mod test {
         assert!(matrix2_inv.is_some());
         assert!(matrix1.equal(&(product_matrix.mul(&matrix2_inv.unwrap()))));
     }
+
+    #[test]
+    fn mul_operators() {
+        let matrix1 = Matrix::translation(1.0, 2.0, 3.0);
+        let matrix2 = Matrix::scaling(2.0, 2.0, 2.0);
+        let point = Point::new_point(1.0, 1.0, 1.0);
+        let vec = Vector::new_vector(1.0, 1.0, 1.0);
+
+        assert_eq!(&matrix1 * &matrix2, matrix1.mul(&matrix2));
+        assert_eq!(matrix1.clone() * matrix2.clone(), matrix1.mul(&matrix2));
+        assert_eq!(&matrix1 * point, matrix1.coords_mul(point));
+        assert_eq!(&matrix1 * vec, vec);
+        assert_eq!(matrix1 * matrix2 * point, Point::new_point(3.0, 4.0, 5.0));
+    }
+
+    #[test]
+    fn non_invertible() {
+        let row1 = [-4.0, 2.0, -2.0, -3.0];
+        let row2 = [9.0, 6.0, 2.0, 6.0];
+        let row3 = [0.0, -5.0, 1.0, -5.0];
+        let row4 = [0.0, 0.0, 0.0, 0.0];
+        let matrix = Matrix::new_with_rows([row1, row2, row3, row4]);
+
+        assert_eq!(matrix.determinant(), 0.0);
+        assert!(matrix.inverse().is_none());
+
+        // rounding leaves a tiny determinant rather than exactly zero
+        let row1 = [0.1, 0.7, 0.3, 0.0];
+        let row2 = [0.1 * 3.0, 0.7 * 3.0, 0.3 * 3.0, 0.0];
+        let matrix = Matrix::new_with_rows([row1, row2, [0.0, 0.0, 1.0, 0.0], [1.0; 4]]);
+        assert!(matrix.inverse().is_none());
+    }
+
+    #[test]
+    fn inverse_of_small_scaling() {
+        // tiny objects have tiny determinants but are still invertible
+        for scale in [1e-3, 1e-6] {
+            let matrix = Matrix::scaling(scale, scale, scale).translate(1.0, 2.0, 3.0);
+            let inverse = matrix.inverse().unwrap();
+            assert!(is_near_identity(&(&inverse * &matrix), 1e-9));
+        }
+
+        let flattened = Matrix::scaling(1.0, 1e-6, 1.0);
+        assert!(flattened.inverse().is_some());
+    }
+
+    #[test]
+    fn inverse_matches_cofactor_expansion() {
+        let row1 = [8.0, -5.0, 9.0, 2.0];
+        let row2 = [7.0, 5.0, 6.0, 1.0];
+        let row3 = [-6.0, 0.0, 9.0, 6.0];
+        let row4 = [-3.0, 0.0, -9.0, -4.0];
+        let matrix = Matrix::new_with_rows([row1, row2, row3, row4]);
+        let determinant = matrix.cofactor_determinant();
+
+        let mut rows = [[0.0; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = matrix.cofactor(j, i) / determinant;
+            }
+        }
+
+        assert!(matrix
+            .inverse()
+            .unwrap()
+            .equal(&Matrix::new_with_rows(rows)));
+    }
+
+    fn matrix_strategy() -> impl Strategy<Value = Matrix> {
+        prop::array::uniform4(prop::array::uniform4(-10.0..10.0_f64))
+            .prop_map(Matrix::new_with_rows)
+    }
+
+    // chains of the transforms scenes are built from
+    fn transform_strategy() -> impl Strategy<Value = Matrix> {
+        let scale = prop_oneof![-5.0..-0.1_f64, 0.1..5.0_f64];
+        (
+            prop::array::uniform3(-50.0..50.0_f64),
+            prop::array::uniform3(scale),
+            prop::array::uniform3(-PI..PI),
+            prop::array::uniform6(-0.3..0.3_f64),
+        )
+            .prop_map(|(translation, scale, rotation, shear)| {
+                Matrix::new_identity()
+                    .shear(shear[0], shear[1], shear[2], shear[3], shear[4], shear[5])
+                    .scale(scale[0], scale[1], scale[2])
+                    .rotate_x(rotation[0])
+                    .rotate_y(rotation[1])
+                    .rotate_z(rotation[2])
+                    .translate(translation[0], translation[1], translation[2])
+            })
+    }
+
+    fn is_near_identity(matrix: &Matrix, tolerance: f64) -> bool {
+        let identity = Matrix::new_identity();
+        (0..4).all(|i| (0..4).all(|j| (matrix.rows[i][j] - identity.rows[i][j]).abs() < tolerance))
+    }
+
+    proptest! {
+        #[test]
+        fn inverse_of_random_matrix(matrix in matrix_strategy()) {
+            // keep away from nearly flat matrices, where any inverse is
+            // dominated by rounding
+            prop_assume!(matrix.determinant().abs() > 1e-3);
+            let inverse = matrix.inverse().unwrap();
+
+            prop_assert!(is_near_identity(&(&inverse * &matrix), 1e-6));
+            prop_assert!(is_near_identity(&(&matrix * &inverse), 1e-6));
+            prop_assert!(
+                (matrix.determinant() - matrix.cofactor_determinant()).abs()
+                    < 1e-9 * matrix.cofactor_determinant().abs().max(1.0)
+            );
+        }
+
+        #[test]
+        fn inverse_of_transform(matrix in transform_strategy()) {
+            let inverse = matrix.inverse();
+
+            prop_assert!(inverse.is_some());
+            prop_assert!(is_near_identity(&(&inverse.unwrap() * &matrix), 1e-8));
+        }
+    }
 }
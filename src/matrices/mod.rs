@@ -1,6 +1,15 @@
+mod decompose;
+#[cfg(test)]
 mod m_2d;
+#[cfg(test)]
 mod m_3d;
 mod m_4d;
+mod quaternion;
+mod transform;
+mod transform_builder;
 mod transformations;
 
+pub use decompose::*;
 pub use m_4d::*;
+pub use quaternion::*;
+pub use transform::*;
@@ -0,0 +1,312 @@
+use std::ops;
+
+use serde::{Deserialize, Serialize};
+
+use super::m_4d::Matrix;
+use crate::base_types::Vector;
+
+// below this the two rotations are so close that slerp would divide by
+// almost zero, so they are blended linearly instead
+const SLERP_LINEAR_THRESHOLD: f64 = 0.9995;
+
+// a rotation stored as a unit quaternion. Unlike rotation matrices these can
+// be interpolated smoothly, which is what animating a rotation needs
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    pub fn new_identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    // turns the same way as Matrix::rotation_axis
+    pub fn from_axis_angle(axis: Vector, theta: f64) -> Self {
+        let axis = axis.normalize();
+        let sin = (theta / 2.0).sin();
+
+        Self::new(
+            (theta / 2.0).cos(),
+            axis.x * sin,
+            axis.y * sin,
+            axis.z * sin,
+        )
+    }
+
+    // the angle is between 0 and 2 * PI. Without any rotation the axis is
+    // arbitrary, x is returned
+    pub fn to_axis_angle(self) -> (Vector, f64) {
+        let quaternion = self.normalize();
+        let theta = 2.0 * quaternion.w.clamp(-1.0, 1.0).acos();
+        let sin = (1.0 - quaternion.w * quaternion.w).max(0.0).sqrt();
+
+        if sin < crate::utils::FLOAT_DIFF {
+            return (Vector::new_vector(1.0, 0.0, 0.0), theta);
+        }
+
+        (
+            Vector::new_vector(quaternion.x / sin, quaternion.y / sin, quaternion.z / sin),
+            theta,
+        )
+    }
+
+    // reads the rotation from the upper 3x3 of the matrix, which has to be
+    // a pure rotation; use Matrix::decompose to split off scaling first
+    pub fn from_matrix(matrix: &Matrix) -> Self {
+        let m = &matrix.rows;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        // pick the largest component to divide by, the others can get
+        // arbitrarily close to zero
+        let quaternion = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self::new(
+                s / 4.0,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            Self::new(
+                (m[2][1] - m[1][2]) / s,
+                s / 4.0,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            Self::new(
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                s / 4.0,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            Self::new(
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                s / 4.0,
+            )
+        };
+
+        quaternion.normalize()
+    }
+
+    pub fn to_matrix(self) -> Matrix {
+        let Self { w, x, y, z } = self.normalize();
+
+        let mut matrix = Matrix::new_identity();
+        matrix.rows[0][0] = 1.0 - 2.0 * (y * y + z * z);
+        matrix.rows[0][1] = 2.0 * (x * y - w * z);
+        matrix.rows[0][2] = 2.0 * (x * z + w * y);
+        matrix.rows[1][0] = 2.0 * (x * y + w * z);
+        matrix.rows[1][1] = 1.0 - 2.0 * (x * x + z * z);
+        matrix.rows[1][2] = 2.0 * (y * z - w * x);
+        matrix.rows[2][0] = 2.0 * (x * z - w * y);
+        matrix.rows[2][1] = 2.0 * (y * z + w * x);
+        matrix.rows[2][2] = 1.0 - 2.0 * (x * x + y * y);
+
+        matrix
+    }
+
+    pub fn magnitude(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Self {
+        let magnitude = self.magnitude();
+        Self::new(
+            self.w / magnitude,
+            self.x / magnitude,
+            self.y / magnitude,
+            self.z / magnitude,
+        )
+    }
+
+    pub fn dot(self, rhs: Self) -> f64 {
+        self.w * rhs.w + self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn conjugate(self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    // spherical linear interpolation, turning at a constant speed from self
+    // at t = 0 to other at t = 1 along the shortest way round
+    pub fn slerp(self, other: Self, t: f64) -> Self {
+        let from = self.normalize();
+        let mut to = other.normalize();
+        let mut cos = from.dot(to);
+
+        // q and -q are the same rotation, but only one of them is the short way
+        if cos < 0.0 {
+            to = to.scalar_mul(-1.0);
+            cos = -cos;
+        }
+
+        if cos > SLERP_LINEAR_THRESHOLD {
+            return from.scalar_mul(1.0 - t).add(to.scalar_mul(t)).normalize();
+        }
+
+        let theta = cos.acos();
+        let sin = theta.sin();
+        from.scalar_mul(((1.0 - t) * theta).sin() / sin)
+            .add(to.scalar_mul((t * theta).sin() / sin))
+    }
+
+    fn scalar_mul(self, rhs: f64) -> Self {
+        Self::new(self.w * rhs, self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(
+            self.w + rhs.w,
+            self.x + rhs.x,
+            self.y + rhs.y,
+            self.z + rhs.z,
+        )
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::new_identity()
+    }
+}
+
+// same order as matrices, a * b rotates by b first and then by a
+impl ops::Mul for Quaternion {
+    type Output = Self;
+
+    fn mul(self, _rhs: Self) -> Self {
+        Self::new(
+            self.w * _rhs.w - self.x * _rhs.x - self.y * _rhs.y - self.z * _rhs.z,
+            self.w * _rhs.x + self.x * _rhs.w + self.y * _rhs.z - self.z * _rhs.y,
+            self.w * _rhs.y - self.x * _rhs.z + self.y * _rhs.w + self.z * _rhs.x,
+            self.w * _rhs.z + self.x * _rhs.y - self.y * _rhs.x + self.z * _rhs.w,
+        )
+    }
+}
+
+#[cfg(test)]
+impl Quaternion {
+    // q and -q describe the same rotation
+    pub fn same_rotation(self, rhs: Self) -> bool {
+        (self.normalize().dot(rhs.normalize()).abs() - 1.0).abs() < crate::utils::FLOAT_DIFF
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base_types::Point;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn axis_angle_matches_matrix() {
+        let axis = Vector::new_vector(1.0, 2.0, -3.0);
+        let quaternion = Quaternion::from_axis_angle(axis, 1.2);
+
+        assert!(quaternion
+            .to_matrix()
+            .equal(&Matrix::rotation_axis(axis, 1.2)));
+        assert!(
+            Quaternion::from_axis_angle(Vector::new_vector(0.0, 1.0, 0.0), PI / 2.0)
+                .to_matrix()
+                .equal(&Matrix::rotation_y(PI / 2.0))
+        );
+    }
+
+    #[test]
+    fn to_axis_angle() {
+        let axis = Vector::new_vector(0.0, 3.0, 4.0);
+        let (result_axis, theta) = Quaternion::from_axis_angle(axis, 0.75).to_axis_angle();
+
+        assert!(result_axis.equal(axis.normalize()));
+        assert!(crate::utils::float_equal(theta, 0.75));
+
+        let (_, theta) = Quaternion::new_identity().to_axis_angle();
+        assert_eq!(theta, 0.0);
+    }
+
+    #[test]
+    fn from_matrix() {
+        // one rotation for every branch of from_matrix
+        let rotations = [
+            Matrix::rotation_x(0.3),
+            Matrix::rotation_x(3.0),
+            Matrix::rotation_y(3.0),
+            Matrix::rotation_z(3.0),
+            Matrix::rotation_axis(Vector::new_vector(1.0, 1.0, 1.0), 2.5),
+        ];
+
+        for rotation in rotations {
+            assert!(Quaternion::from_matrix(&rotation)
+                .to_matrix()
+                .equal(&rotation));
+        }
+    }
+
+    #[test]
+    fn mul_composes_like_matrices() {
+        let a = Quaternion::from_axis_angle(Vector::new_vector(1.0, 0.0, 0.0), 0.5);
+        let b = Quaternion::from_axis_angle(Vector::new_vector(0.0, 1.0, 1.0), -1.5);
+
+        assert!((a * b)
+            .to_matrix()
+            .equal(&a.to_matrix().mul(&b.to_matrix())));
+        assert!((a * a.conjugate()).same_rotation(Quaternion::new_identity()));
+    }
+
+    #[test]
+    fn slerp() {
+        let axis = Vector::new_vector(0.0, 0.0, 1.0);
+        let from = Quaternion::from_axis_angle(axis, 0.0);
+        let to = Quaternion::from_axis_angle(axis, PI / 2.0);
+
+        assert!(from.slerp(to, 0.0).same_rotation(from));
+        assert!(from.slerp(to, 1.0).same_rotation(to));
+        assert!(from
+            .slerp(to, 0.5)
+            .same_rotation(Quaternion::from_axis_angle(axis, PI / 4.0)));
+
+        // constant speed, a third of the way is a third of the angle
+        let point = from.slerp(to, 1.0 / 3.0).to_matrix() * Point::new_point(1.0, 0.0, 0.0);
+        assert!(point.equal(Point::new_point((PI / 6.0).cos(), (PI / 6.0).sin(), 0.0)));
+    }
+
+    #[test]
+    fn slerp_takes_shortest_way() {
+        let axis = Vector::new_vector(0.0, 1.0, 0.0);
+        let from = Quaternion::from_axis_angle(axis, 0.1);
+        let to = Quaternion::from_axis_angle(axis, 2.0 * PI - 0.1);
+
+        // the short way round goes through no rotation at all
+        assert!(from
+            .slerp(to, 0.5)
+            .same_rotation(Quaternion::new_identity()));
+    }
+
+    #[test]
+    fn slerp_nearly_equal() {
+        let axis = Vector::new_vector(1.0, 0.0, 0.0);
+        let from = Quaternion::from_axis_angle(axis, 1.0);
+        let to = Quaternion::from_axis_angle(axis, 1.0 + 1e-6);
+
+        assert!(from
+            .slerp(to, 0.5)
+            .same_rotation(Quaternion::from_axis_angle(axis, 1.0 + 5e-7)));
+    }
+}
@@ -0,0 +1,136 @@
+use std::borrow::Cow;
+
+use super::m_4d::Matrix;
+
+// a transformation matrix along with its inverse and the transpose of the
+// inverse. Every ray needs those, so they are worked out once whenever the
+// matrix changes instead of on every intersection
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Clone)]
+pub struct Transform {
+    matrix: Matrix,
+    inverse: Option<Matrix>,
+    inverse_transpose: Option<Matrix>,
+    // where a moving transform ends up at time 1, the matrix above is
+    // where it starts at time 0
+    end: Option<Box<Transform>>,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix) -> Self {
+        let inverse = matrix.inverse();
+        let inverse_transpose = inverse.as_ref().map(Matrix::transpose);
+
+        Self {
+            matrix,
+            inverse,
+            inverse_transpose,
+            end: None,
+        }
+    }
+
+    // moves from start at time 0 to end at time 1, for a velocity use
+    // start.translate(...) as the end
+    pub fn new_moving(start: Matrix, end: Matrix) -> Self {
+        Self {
+            end: Some(Box::new(Self::new(end))),
+            ..Self::new(start)
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    // None when the matrix can not be inverted, e.g., scaling by zero
+    pub fn inverse(&self) -> Option<&Matrix> {
+        self.inverse.as_ref()
+    }
+
+    // used to move normals from object space into world space
+    pub fn inverse_transpose(&self) -> Option<&Matrix> {
+        self.inverse_transpose.as_ref()
+    }
+
+    pub fn end(&self) -> Option<&Transform> {
+        self.end.as_deref()
+    }
+
+    // the transform at the given time, between 0 and 1. Only moving
+    // transforms have to work anything out
+    pub fn at(&self, time: f64) -> Cow<'_, Transform> {
+        match &self.end {
+            None => Cow::Borrowed(self),
+            Some(_) if time <= 0.0 => Cow::Borrowed(self),
+            Some(end) if time >= 1.0 => Cow::Borrowed(end),
+            Some(end) => Cow::Owned(Self::new(self.matrix.interpolate(end.matrix(), time))),
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new(Matrix::new_identity())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn caches_inverse_and_inverse_transpose() {
+        let matrix = Matrix::translation(1.0, 2.0, 3.0).mul(&Matrix::rotation_x(0.5));
+        let transform = Transform::new(matrix.clone());
+
+        assert_eq!(transform.matrix(), &matrix);
+        assert_eq!(transform.inverse(), matrix.inverse().as_ref());
+        assert_eq!(
+            transform.inverse_transpose(),
+            Some(&matrix.inverse().unwrap().transpose())
+        );
+    }
+
+    #[test]
+    fn not_invertible() {
+        let transform = Transform::new(Matrix::scaling(0.0, 1.0, 1.0));
+
+        assert!(transform.inverse().is_none());
+        assert!(transform.inverse_transpose().is_none());
+    }
+
+    #[test]
+    fn moving() {
+        let start = Matrix::translation(1.0, 0.0, 0.0);
+        let end = Matrix::translation(3.0, 0.0, 0.0).rotate_z(1.0);
+        let transform = Transform::new_moving(start.clone(), end.clone());
+
+        assert_eq!(transform.matrix(), &start);
+        assert_eq!(transform.end().unwrap().matrix(), &end);
+        assert_eq!(transform.at(0.0).matrix(), &start);
+        assert_eq!(transform.at(1.0).matrix(), &end);
+
+        let halfway = transform.at(0.5);
+        assert!(halfway.matrix().equal(&start.interpolate(&end, 0.5)));
+        assert!(halfway
+            .inverse()
+            .unwrap()
+            .equal(&start.interpolate(&end, 0.5).inverse().unwrap()));
+    }
+
+    #[test]
+    fn not_moving() {
+        let transform = Transform::new(Matrix::scaling(2.0, 2.0, 2.0));
+
+        assert!(transform.end().is_none());
+        assert!(matches!(transform.at(0.5), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn default_is_identity() {
+        let transform = Transform::default();
+
+        assert_eq!(transform.matrix(), &Matrix::new_identity());
+        assert_eq!(transform.inverse(), Some(&Matrix::new_identity()));
+    }
+}
@@ -0,0 +1,103 @@
+use super::m_4d::Matrix;
+use crate::base_types::{Point, Vector};
+
+// chainable versions of the transformations, applied in the order they are
+// written, e.g.,
+//   Matrix::new_identity().rotate_x(PI / 2.0).scale(5.0, 5.0, 5.0)
+// rotates first and then scales, same as
+//   Matrix::scaling(5.0, 5.0, 5.0).mul(&Matrix::rotation_x(PI / 2.0))
+impl Matrix {
+    // applies the other transform after this one
+    pub fn then(&self, transform: &Matrix) -> Self {
+        transform.mul(self)
+    }
+
+    pub fn translate(self, x: f64, y: f64, z: f64) -> Self {
+        self.then(&Self::translation(x, y, z))
+    }
+
+    pub fn scale(self, x: f64, y: f64, z: f64) -> Self {
+        self.then(&Self::scaling(x, y, z))
+    }
+
+    pub fn rotate_x(self, theta: f64) -> Self {
+        self.then(&Self::rotation_x(theta))
+    }
+
+    pub fn rotate_y(self, theta: f64) -> Self {
+        self.then(&Self::rotation_y(theta))
+    }
+
+    pub fn rotate_z(self, theta: f64) -> Self {
+        self.then(&Self::rotation_z(theta))
+    }
+
+    pub fn rotate_axis(self, axis: Vector, theta: f64) -> Self {
+        self.then(&Self::rotation_axis(axis, theta))
+    }
+
+    pub fn shear(self, xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Self {
+        self.then(&Self::shearing(xy, xz, yx, yz, zx, zy))
+    }
+
+    pub fn look_towards(self, from: Point, to: Point, up: Vector) -> Self {
+        self.then(&Self::look_at(from, to, up))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn applies_in_reading_order() {
+        let transform = Matrix::new_identity()
+            .rotate_x(PI / 2.0)
+            .scale(5.0, 5.0, 5.0)
+            .translate(10.0, 5.0, 7.0);
+        let point = Point::new_point(1.0, 0.0, 1.0);
+
+        assert!(transform
+            .coords_mul(point)
+            .equal(Point::new_point(15.0, 0.0, 7.0)));
+        assert!(transform.equal(
+            &Matrix::translation(10.0, 5.0, 7.0)
+                .mul(&Matrix::scaling(5.0, 5.0, 5.0))
+                .mul(&Matrix::rotation_x(PI / 2.0))
+        ));
+    }
+
+    #[test]
+    fn every_operation() {
+        let axis = Vector::new_vector(1.0, 2.0, 3.0);
+        let from = Point::new_point(1.0, 3.0, 2.0);
+        let to = Point::new_point(4.0, -2.0, 8.0);
+        let up = Vector::new_vector(1.0, 1.0, 0.0);
+
+        let transform = Matrix::new_identity()
+            .shear(1.0, 0.0, 0.0, 0.0, 0.0, 0.5)
+            .rotate_y(0.5)
+            .rotate_z(-0.25)
+            .rotate_axis(axis, 1.5)
+            .look_towards(from, to, up);
+        let expected = Matrix::look_at(from, to, up)
+            .mul(&Matrix::rotation_axis(axis, 1.5))
+            .mul(&Matrix::rotation_z(-0.25))
+            .mul(&Matrix::rotation_y(0.5))
+            .mul(&Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.5));
+
+        assert!(transform.equal(&expected));
+    }
+
+    #[test]
+    fn then() {
+        let first = Matrix::scaling(2.0, 2.0, 2.0);
+        let second = Matrix::translation(1.0, 0.0, 0.0);
+
+        assert!(first
+            .then(&second)
+            .coords_mul(Point::new_point(1.0, 1.0, 1.0))
+            .equal(Point::new_point(3.0, 2.0, 2.0)));
+    }
+}
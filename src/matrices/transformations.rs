@@ -59,6 +59,29 @@ impl Matrix {
         matrix
     }
 
+    // rotates around the given axis, turning the same way as rotation_x,
+    // rotation_y and rotation_z do around theirs
+    pub fn rotation_axis(axis: Vector, theta: f64) -> Self {
+        let axis = axis.normalize();
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+        let sin = theta.sin();
+        let cos = theta.cos();
+        let one_minus_cos = 1.0 - cos;
+
+        let mut matrix = Self::new_identity();
+        matrix.rows[0][0] = one_minus_cos * x * x + cos;
+        matrix.rows[0][1] = one_minus_cos * x * y - sin * z;
+        matrix.rows[0][2] = one_minus_cos * x * z + sin * y;
+        matrix.rows[1][0] = one_minus_cos * x * y + sin * z;
+        matrix.rows[1][1] = one_minus_cos * y * y + cos;
+        matrix.rows[1][2] = one_minus_cos * y * z - sin * x;
+        matrix.rows[2][0] = one_minus_cos * x * z - sin * y;
+        matrix.rows[2][1] = one_minus_cos * y * z + sin * x;
+        matrix.rows[2][2] = one_minus_cos * z * z + cos;
+
+        matrix
+    }
+
     pub fn shearing(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Self {
         let mut matrix = Self::new_identity();
         matrix.rows[0][1] = xy;
@@ -84,6 +107,22 @@ impl Matrix {
         Matrix::new_with_rows([row1, row2, row3, row4])
             .mul(&Matrix::translation(-from.x, -from.y, -from.z))
     }
+
+    // moves an object to from and turns it so its -z axis faces to, the
+    // inverse of view_transform. Unlike view_transform the axes are kept
+    // at unit length, so up does not need to be at a right angle to to
+    pub fn look_at(from: Point, to: Point, up: Vector) -> Self {
+        let forward = (to - from).normalize();
+        let left = forward.cross(up.normalize()).normalize();
+        let true_up = left.cross(forward);
+
+        let row1 = [left.x, true_up.x, -forward.x, from.x];
+        let row2 = [left.y, true_up.y, -forward.y, from.y];
+        let row3 = [left.z, true_up.z, -forward.z, from.z];
+        let row4 = [0.0, 0.0, 0.0, 1.0];
+
+        Matrix::new_with_rows([row1, row2, row3, row4])
+    }
 }
 
 #[cfg(test)]
@@ -214,6 +253,37 @@ mod test {
         assert!((full_quarter.coords_mul(point)).equal(Point::new_point(-1.0, 0.0, 0.0)));
     }
 
+    #[test]
+    fn rotation_axis_matches_rotation_around_main_axes() {
+        let theta = PI / 3.0;
+
+        assert!(
+            Matrix::rotation_axis(Vector::new_vector(2.0, 0.0, 0.0), theta)
+                .equal(&Matrix::rotation_x(theta))
+        );
+        assert!(
+            Matrix::rotation_axis(Vector::new_vector(0.0, 1.0, 0.0), theta)
+                .equal(&Matrix::rotation_y(theta))
+        );
+        assert!(
+            Matrix::rotation_axis(Vector::new_vector(0.0, 0.0, 0.5), theta)
+                .equal(&Matrix::rotation_z(theta))
+        );
+    }
+
+    #[test]
+    fn rotation_axis_diagonal() {
+        // a third of a turn around the diagonal cycles the axes
+        let matrix = Matrix::rotation_axis(Vector::new_vector(1.0, 1.0, 1.0), 2.0 * PI / 3.0);
+
+        assert!(matrix
+            .coords_mul(Point::new_point(1.0, 0.0, 0.0))
+            .equal(Point::new_point(0.0, 1.0, 0.0)));
+        assert!(matrix
+            .coords_mul(Vector::new_vector(0.0, 0.0, 1.0))
+            .equal(Vector::new_vector(1.0, 0.0, 0.0)));
+    }
+
     #[test]
     fn shearing() {
         let mut matrix = Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
@@ -327,4 +397,36 @@ mod test {
         let t = Matrix::view_transform(from, to, up);
         assert!(t.equal(&Matrix::new_with_rows(rows)));
     }
+
+    #[test]
+    fn look_at_is_inverse_of_view_transform() {
+        let from = Point::new_point(1.0, 3.0, 2.0);
+        let to = Point::new_point(4.0, 3.0, 6.0);
+        let up = Vector::new_vector(0.0, 1.0, 0.0);
+
+        assert!(Matrix::look_at(from, to, up)
+            .equal(&Matrix::view_transform(from, to, up).inverse().unwrap()));
+    }
+
+    #[test]
+    fn look_at() {
+        let from = Point::new_point(1.0, 3.0, 2.0);
+        let to = Point::new_point(4.0, -2.0, 8.0);
+        let up = Vector::new_vector(1.0, 1.0, 0.0);
+        let look_at = Matrix::look_at(from, to, up);
+
+        assert!(look_at
+            .coords_mul(Point::new_point(0.0, 0.0, 0.0))
+            .equal(from));
+        assert!(look_at
+            .coords_mul(Vector::new_vector(0.0, 0.0, -1.0))
+            .equal((to - from).normalize()));
+        // no stretching
+        assert!(crate::utils::float_equal(
+            look_at
+                .coords_mul(Vector::new_vector(1.0, 1.0, 0.0).normalize())
+                .magnitude(),
+            1.0
+        ));
+    }
 }
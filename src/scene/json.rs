@@ -0,0 +1,321 @@
+use std::fs;
+use std::io::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::animation::Animation;
+use crate::world::{Camera, World};
+
+#[derive(Serialize)]
+struct SceneRef<'a> {
+    camera: &'a Camera,
+    world: &'a World,
+}
+
+#[derive(Deserialize)]
+struct Scene {
+    camera: Camera,
+    world: World,
+}
+
+// colors are [r, g, b], points and vectors are {x, y, z}, matrices are
+// lists of rows, and patterns and objects are tagged with their "type"
+pub fn scene_to_json(world: &World, camera: &Camera) -> Result<String, Error> {
+    Ok(serde_json::to_string_pretty(&SceneRef { camera, world })?)
+}
+
+pub fn parse_json_scene(source: &str) -> Result<(World, Camera), Error> {
+    let scene: Scene = serde_json::from_str(source)?;
+    Ok((scene.world, scene.camera))
+}
+
+pub fn save_json_scene(path: &str, world: &World, camera: &Camera) -> Result<(), Error> {
+    fs::write(path, scene_to_json(world, camera)?)
+}
+
+pub fn load_json_scene(path: &str) -> Result<(World, Camera), Error> {
+    fs::read_to_string(path)
+        .and_then(|source| parse_json_scene(&source))
+        .map_err(|error| Error::new(error.kind(), format!("{}: {}", path, error)))
+}
+
+// animations are kept in their own file, so the same scene can be rendered
+// with different ones
+pub fn parse_json_animation(source: &str) -> Result<Animation, Error> {
+    Ok(serde_json::from_str(source)?)
+}
+
+pub fn load_json_animation(path: &str) -> Result<Animation, Error> {
+    fs::read_to_string(path)
+        .and_then(|source| parse_json_animation(&source))
+        .map_err(|error| Error::new(error.kind(), format!("{}: {}", path, error)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base_types::Point;
+    use crate::examples::{perturbed_scene, plane_scene, world_scene};
+    use crate::matrices::Matrix;
+    use crate::visuals::{Color, Material, Microfacet, Pattern, StripePattern};
+    use crate::world::{AmbientOcclusion, Integrator, Object, Plane, Quad, Sphere};
+    use std::io::ErrorKind;
+
+    fn assert_round_trip(world: World, camera: Camera) {
+        let json = scene_to_json(&world, &camera).unwrap();
+        let (loaded_world, loaded_camera) = parse_json_scene(&json).unwrap();
+
+        // saving what was loaded gives back the same file
+        assert_eq!(scene_to_json(&loaded_world, &loaded_camera).unwrap(), json);
+        assert!(loaded_camera
+            .render(&loaded_world)
+            .approx_equal(&camera.render(&world), 0.0));
+    }
+
+    #[test]
+    fn example_scenes_round_trip() {
+        // between them these use every pattern and object kind
+        let (world, camera) = plane_scene(32, 22);
+        assert_round_trip(world, camera);
+        let (world, camera) = world_scene(32, 22);
+        assert_round_trip(world, camera);
+        let (world, camera) = perturbed_scene(32, 16);
+        assert_round_trip(world, camera);
+    }
+
+    #[test]
+    fn camera_settings() {
+        let (world, mut camera) = plane_scene(40, 20);
+        camera.samples_per_pixel = 4;
+        let json = scene_to_json(&world, &camera).unwrap();
+        let (_, loaded_camera) = parse_json_scene(&json).unwrap();
+
+        assert_eq!(loaded_camera.hsize(), 40);
+        assert_eq!(loaded_camera.vsize(), 20);
+        assert_eq!(loaded_camera.field_of_view, camera.field_of_view);
+        assert_eq!(loaded_camera.samples_per_pixel, 4);
+        assert!(loaded_camera
+            .get_transform()
+            .matrix()
+            .equal(camera.get_transform().matrix()));
+    }
+
+    #[test]
+    fn path_tracer_settings() {
+        let (mut world, camera) = plane_scene(8, 6);
+        world.integrator = Integrator::PathTracer { seed: 12 };
+        world.objects[0].set_material(Material {
+            emission: Color::new(0.5, 0.25, 0.0),
+            ..Material::default()
+        });
+        let json = scene_to_json(&world, &camera).unwrap();
+        let (loaded_world, _) = parse_json_scene(&json).unwrap();
+
+        assert_eq!(loaded_world.integrator, Integrator::PathTracer { seed: 12 });
+        assert_eq!(
+            loaded_world.objects[0].get_material().emission,
+            Color::new(0.5, 0.25, 0.0)
+        );
+        // the default integrator is left out
+        let (world, camera) = plane_scene(8, 6);
+        assert!(!scene_to_json(&world, &camera)
+            .unwrap()
+            .contains("integrator"));
+    }
+
+    #[test]
+    fn ambient_occlusion() {
+        let (mut world, camera) = plane_scene(8, 6);
+        let json = scene_to_json(&world, &camera).unwrap();
+        assert!(!json.contains("ambient_occlusion"));
+
+        world.ambient_occlusion = Some(AmbientOcclusion::new(12, 0.5));
+        let json = scene_to_json(&world, &camera).unwrap();
+        let (loaded_world, _) = parse_json_scene(&json).unwrap();
+        assert_eq!(
+            loaded_world.ambient_occlusion,
+            Some(AmbientOcclusion::new(12, 0.5))
+        );
+    }
+
+    #[test]
+    fn microfacet_materials() {
+        let (mut world, camera) = plane_scene(8, 6);
+        let json = scene_to_json(&world, &camera).unwrap();
+        assert!(!json.contains("microfacet"));
+
+        world.objects[0].get_material_mut().microfacet = Some(Microfacet::new(0.3, 1.0));
+        let json = scene_to_json(&world, &camera).unwrap();
+        let (loaded_world, _) = parse_json_scene(&json).unwrap();
+        assert_eq!(
+            loaded_world.objects[0].get_material().microfacet,
+            Some(Microfacet::new(0.3, 1.0))
+        );
+    }
+
+    #[test]
+    fn emissive_quads() {
+        let (mut world, camera) = plane_scene(8, 6);
+        let lamp = Quad::new(
+            Matrix::translation(0.0, 4.0, 0.0),
+            Material {
+                emission: Color::new(1.0, 0.9, 0.8),
+                emission_strength: 3.0,
+                ..Material::default()
+            },
+        );
+        world.add_object(Box::new(lamp));
+        world.emissive_samples = 4;
+        let json = scene_to_json(&world, &camera).unwrap();
+        let (loaded_world, _) = parse_json_scene(&json).unwrap();
+
+        assert!(loaded_world.objects.last().unwrap().equal(&Quad::new(
+            Matrix::translation(0.0, 4.0, 0.0),
+            Material {
+                emission: Color::new(1.0, 0.9, 0.8),
+                emission_strength: 3.0,
+                ..Material::default()
+            },
+        )));
+        assert_eq!(loaded_world.emissive_samples, 4);
+    }
+
+    #[test]
+    fn moving_objects() {
+        let (mut world, mut camera) = plane_scene(8, 6);
+        world.objects[0]
+            .set_moving_transform(Matrix::new_identity(), Matrix::translation(0.0, 1.0, 0.0));
+        camera.shutter_close = 0.5;
+        camera.samples_per_pixel = 2;
+        let json = scene_to_json(&world, &camera).unwrap();
+        let (loaded_world, loaded_camera) = parse_json_scene(&json).unwrap();
+
+        assert!(loaded_world.objects[0]
+            .get_transform()
+            .end()
+            .unwrap()
+            .matrix()
+            .equal(&Matrix::translation(0.0, 1.0, 0.0)));
+        assert!(loaded_world.objects[1].get_transform().end().is_none());
+        assert!(loaded_camera
+            .render(&loaded_world)
+            .approx_equal(&camera.render(&world), 0.0));
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let (world, camera) = parse_json_scene(
+            r#"{
+                "camera": { "width": 10, "height": 5, "field_of_view": 1.0 },
+                "world": {
+                    "objects": [
+                        { "type": "sphere", "material": { "color": [1, 0, 0] } },
+                        { "type": "plane" }
+                    ],
+                    "lights": [
+                        {
+                            "position": { "x": 0, "y": 5, "z": 0 },
+                            "color": [1, 1, 1]
+                        }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(camera
+            .get_transform()
+            .matrix()
+            .equal(&crate::matrices::Matrix::new_identity()));
+        assert_eq!(camera.samples_per_pixel, 1);
+        assert_eq!(
+            world.max_reflect_depth,
+            crate::world::DEFAULT_MAX_REFLECT_DEPTH
+        );
+        let mut red_sphere = Sphere::default();
+        red_sphere.set_material(Material {
+            color: Color::new(1.0, 0.0, 0.0),
+            ..Material::default()
+        });
+        assert!(world.objects[0].equal(&red_sphere));
+        assert!(world.objects[1].equal(&Plane::default()));
+    }
+
+    #[test]
+    fn invalid_scenes() {
+        let unknown_object = parse_json_scene(
+            r#"{
+                "camera": { "width": 10, "height": 5, "field_of_view": 1.0 },
+                "world": { "objects": [{ "type": "cube" }], "lights": [] }
+            }"#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(unknown_object.kind(), ErrorKind::InvalidData);
+        assert!(unknown_object
+            .to_string()
+            .contains("unknown variant `cube`"));
+
+        let empty_camera = parse_json_scene(
+            r#"{
+                "camera": { "width": 0, "height": 5, "field_of_view": 1.0 },
+                "world": { "objects": [], "lights": [] }
+            }"#,
+        );
+        assert!(empty_camera.is_err());
+
+        assert!(parse_json_scene("{").is_err());
+    }
+
+    #[test]
+    fn singular_transforms() {
+        let load_error = |world: &World, camera: &Camera| {
+            let json = scene_to_json(world, camera).unwrap();
+            let error = parse_json_scene(&json).err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+            error.to_string()
+        };
+        let flat = Matrix::scaling(0.0, 1.0, 1.0);
+
+        let (world, mut camera) = plane_scene(8, 6);
+        camera.set_transform(flat.clone());
+        assert!(load_error(&world, &camera).contains("camera transform can not be inverted"));
+
+        let (mut world, camera) = plane_scene(8, 6);
+        world.objects[0].set_transform(flat.clone());
+        assert!(load_error(&world, &camera).contains("object transform can not be inverted"));
+
+        let (mut world, camera) = plane_scene(8, 6);
+        world.objects[0].set_moving_transform(Matrix::new_identity(), flat.clone());
+        assert!(load_error(&world, &camera).contains("object end_transform can not be inverted"));
+
+        let (mut world, camera) = plane_scene(8, 6);
+        let mut stripes =
+            StripePattern::new(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0), None);
+        stripes.set_transform(flat);
+        world.objects[0].get_material_mut().patterns = vec![Box::new(stripes)];
+        assert!(load_error(&world, &camera).contains("pattern transform can not be inverted"));
+    }
+
+    #[test]
+    fn turntable_animation() {
+        let animation =
+            parse_json_animation(include_str!("../../scenes/plane_turntable.json")).unwrap();
+        let (mut world, mut camera) = plane_scene(32, 22);
+        let camera_position = |camera: &Camera| {
+            camera.get_transform().inverse().unwrap() * Point::new_point(0.0, 0.0, 0.0)
+        };
+
+        assert_eq!(animation.frame_count(), 49);
+        // halfway round the camera looks at the scene from behind
+        animation.apply(2.0, &mut world, &mut camera).unwrap();
+        assert!(camera_position(&camera).equal(Point::new_point(0.0, 1.5, 5.0)));
+        // and in between keyframes it keeps its distance
+        animation.apply(0.5, &mut world, &mut camera).unwrap();
+        let offset = camera_position(&camera) - Point::new_point(0.0, 1.0, 0.0);
+        assert!(crate::utils::float_equal(
+            offset.magnitude(),
+            25.25_f64.sqrt()
+        ));
+    }
+}
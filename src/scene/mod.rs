@@ -0,0 +1,5 @@
+mod json;
+mod yaml;
+
+pub use json::*;
+pub use yaml::*;
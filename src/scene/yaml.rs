@@ -0,0 +1,1256 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+use yaml_rust2::yaml::Hash;
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::base_types::{Point, Vector};
+use crate::matrices::Matrix;
+use crate::visuals::{
+    CheckerPattern, Color, GradientPattern, Material, Microfacet, Pattern, PerturbPattern,
+    RingPattern, StripePattern,
+};
+use crate::world::{ApertureShape, Camera, Light, Object, Plane, Projection, Quad, Sphere, World};
+
+const MATERIAL_KEYS: [&str; 14] = [
+    "color",
+    "pattern",
+    "ambient",
+    "diffuse",
+    "specular",
+    "shininess",
+    "reflective",
+    "transparency",
+    "refractive-index",
+    "patterns",
+    "emission",
+    "emission-strength",
+    "roughness",
+    "metallic",
+];
+
+// loads a scene written in the yaml format used by the ray tracer challenge
+// scene files, see parse_yaml_scene
+pub fn load_yaml_scene(path: &str) -> Result<(World, Camera), Error> {
+    std::fs::read_to_string(path)
+        .and_then(|source| parse_yaml_scene(&source))
+        .map_err(|error| Error::new(error.kind(), format!("{}: {}", path, error)))
+}
+
+// the scene is a list of items, each one of
+//   - add: camera      (width, height, field-of-view, from, to, up, and
+//                        optionally samples, shutter: [open, close] and for
+//                        depth of field aperture, focal-distance, which
+//                        defaults to how far to is, and aperture-blades.
+//                        projection is perspective, orthographic with a
+//                        view-width, fisheye or equirectangular)
+//   - add: light       (at, intensity)
+//   - add: sphere/plane/quad (material, transform, and for motion blur
+//                        either end-transform or velocity). Quads are 2 by 2
+//                        squares on the xz plane facing up)
+//   - define: <name>   (value, and optionally extend: <other define>).
+//                        Transforms can list defines from earlier items
+// transforms are lists of operations applied in the order they are listed.
+// errors name the item and the key path that could not be read
+pub fn parse_yaml_scene(source: &str) -> Result<(World, Camera), Error> {
+    let documents = YamlLoader::load_from_str(source)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error.to_string()))?;
+    let items = match documents.first() {
+        Some(Yaml::Array(items)) => items,
+        _ => return Err(invalid_data("scene", "expected a list of scene items")),
+    };
+
+    let mut scene = SceneBuilder::default();
+    for (i, item) in items.iter().enumerate() {
+        scene.add_item(item, i)?;
+    }
+
+    scene.build()
+}
+
+#[derive(Default)]
+struct SceneBuilder {
+    defines: HashMap<String, Yaml>,
+    camera: Option<Camera>,
+    lights: Vec<Light>,
+    objects: Vec<Box<dyn Object>>,
+}
+
+impl SceneBuilder {
+    fn add_item(&mut self, item: &Yaml, index: usize) -> Result<(), Error> {
+        let hash = match item {
+            Yaml::Hash(hash) => hash,
+            _ => {
+                return Err(invalid_data(
+                    &format!("scene[{}]", index),
+                    "expected a mapping",
+                ))
+            }
+        };
+
+        if let Some(name) = hash.get(&key("define")) {
+            let path = format!("scene[{}] (define: {})", index, yaml_label(name));
+            return self.add_define(hash, &path);
+        }
+
+        let kind = match hash.get(&key("add")) {
+            Some(Yaml::String(kind)) => kind.as_str(),
+            _ => {
+                return Err(invalid_data(
+                    &format!("scene[{}]", index),
+                    "expected an \"add\" or \"define\" key",
+                ))
+            }
+        };
+        let path = format!("scene[{}] (add: {})", index, kind);
+
+        match kind {
+            "camera" => {
+                if self.camera.is_some() {
+                    return Err(invalid_data(&path, "the scene can only have one camera"));
+                }
+                self.camera = Some(self.camera(hash, &path)?);
+            }
+            "light" => self.lights.push(self.light(hash, &path)?),
+            "sphere" | "plane" | "quad" => {
+                check_keys(
+                    hash,
+                    &["add", "material", "transform", "end-transform", "velocity"],
+                    &path,
+                )?;
+
+                let mut object: Box<dyn Object> = match kind {
+                    "sphere" => Box::new(Sphere::default()),
+                    "plane" => Box::new(Plane::default()),
+                    _ => Box::new(Quad::default()),
+                };
+                if let Some(material) = hash.get(&key("material")) {
+                    object.set_material(self.material(material, &join(&path, "material"))?);
+                }
+                let transform = match hash.get(&key("transform")) {
+                    Some(transform) => self.transform(transform, &join(&path, "transform"))?,
+                    None => Matrix::new_identity(),
+                };
+                match (hash.get(&key("end-transform")), hash.get(&key("velocity"))) {
+                    (Some(_), Some(_)) => {
+                        return Err(invalid_data(
+                            &path,
+                            "use either end-transform or velocity, not both",
+                        ))
+                    }
+                    (Some(end), None) => {
+                        let end = self.transform(end, &join(&path, "end-transform"))?;
+                        object.set_moving_transform(transform, end);
+                    }
+                    // how far the object moves while the shutter is open
+                    (None, Some(velocity)) => {
+                        let [x, y, z] = triple(velocity, &join(&path, "velocity"))?;
+                        let end = transform.clone().translate(x, y, z);
+                        object.set_moving_transform(transform, end);
+                    }
+                    (None, None) => object.set_transform(transform),
+                }
+
+                self.objects.push(object);
+            }
+            _ => {
+                return Err(invalid_data(
+                    &path,
+                    &format!(
+                        "unknown item \"{}\", expected camera, light, sphere, plane or quad",
+                        kind
+                    ),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_define(&mut self, hash: &Hash, path: &str) -> Result<(), Error> {
+        check_keys(hash, &["define", "extend", "value"], path)?;
+
+        let name = string(&hash[&key("define")], &join(path, "define"))?;
+        let mut value = required(hash, "value", path)?.clone();
+
+        // extending merges the values of the other define under this one
+        if let Some(extend) = hash.get(&key("extend")) {
+            let extend_path = join(path, "extend");
+            let base = self.define(string(extend, &extend_path)?, &extend_path)?;
+            value = match (base, value) {
+                (Yaml::Hash(base), Yaml::Hash(value)) => {
+                    let mut merged = base.clone();
+                    merged.extend(value);
+                    Yaml::Hash(merged)
+                }
+                _ => {
+                    return Err(invalid_data(
+                        path,
+                        "only mappings (e.g. materials) can be extended",
+                    ))
+                }
+            };
+        }
+
+        // transforms that list other transform defines get them spliced in
+        // now, so they only ever name earlier defines and never themselves
+        if let Yaml::Array(operations) = value {
+            value = Yaml::Array(self.inline_transforms(operations, &join(path, "value"))?);
+        }
+
+        self.defines.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn inline_transforms(&self, operations: Vec<Yaml>, path: &str) -> Result<Vec<Yaml>, Error> {
+        let mut inlined = Vec::new();
+
+        for (i, operation) in operations.into_iter().enumerate() {
+            let Yaml::String(name) = &operation else {
+                inlined.push(operation);
+                continue;
+            };
+
+            let operation_path = format!("{}[{}]", path, i);
+            match self.define(name, &operation_path)? {
+                Yaml::Array(define) => inlined.extend(define.iter().cloned()),
+                _ => {
+                    return Err(invalid_data(
+                        &operation_path,
+                        &format!("\"{}\" is not defined as a transform", name),
+                    ))
+                }
+            }
+        }
+
+        Ok(inlined)
+    }
+
+    fn define(&self, name: &str, path: &str) -> Result<&Yaml, Error> {
+        self.defines
+            .get(name)
+            .ok_or_else(|| invalid_data(path, &format!("nothing is defined as \"{}\"", name)))
+    }
+
+    fn camera(&self, hash: &Hash, path: &str) -> Result<Camera, Error> {
+        check_keys(
+            hash,
+            &[
+                "add",
+                "width",
+                "height",
+                "field-of-view",
+                "from",
+                "to",
+                "up",
+                "samples",
+                "shutter",
+                "aperture",
+                "focal-distance",
+                "aperture-blades",
+                "projection",
+                "view-width",
+            ],
+            path,
+        )?;
+
+        let width = positive_integer(required(hash, "width", path)?, &join(path, "width"))?;
+        let height = positive_integer(required(hash, "height", path)?, &join(path, "height"))?;
+        let field_of_view = number(
+            required(hash, "field-of-view", path)?,
+            &join(path, "field-of-view"),
+        )?;
+
+        let from = triple(required(hash, "from", path)?, &join(path, "from"))?;
+        let to = triple(required(hash, "to", path)?, &join(path, "to"))?;
+        let up = triple(required(hash, "up", path)?, &join(path, "up"))?;
+
+        if from == to {
+            return Err(invalid_data(&join(path, "to"), "the same point as from"));
+        }
+
+        let from = Point::new_point(from[0], from[1], from[2]);
+        let to = Point::new_point(to[0], to[1], to[2]);
+        let view = Matrix::view_transform(from, to, Vector::new_vector(up[0], up[1], up[2]));
+        // no way to tell which way is up when looking straight along it
+        if view.inverse().is_none() {
+            return Err(invalid_data(
+                &join(path, "up"),
+                "zero or parallel to the view direction",
+            ));
+        }
+        let mut camera = Camera::new(width, height, field_of_view);
+        camera.set_transform(view);
+
+        if let Some(samples) = hash.get(&key("samples")) {
+            camera.samples_per_pixel = positive_integer(samples, &join(path, "samples"))?;
+        }
+        if let Some(shutter) = hash.get(&key("shutter")) {
+            let shutter_path = join(path, "shutter");
+            let values = array(shutter, &shutter_path)?;
+            if values.len() != 2 {
+                return Err(invalid_data(&shutter_path, "expected [open, close]"));
+            }
+
+            let open = number(&values[0], &format!("{}[0]", shutter_path))?;
+            let close = number(&values[1], &format!("{}[1]", shutter_path))?;
+            if !(0.0 <= open && open <= close && close <= 1.0) {
+                return Err(invalid_data(
+                    &shutter_path,
+                    "expected 0 <= open <= close <= 1",
+                ));
+            }
+            camera.shutter_open = open;
+            camera.shutter_close = close;
+        }
+
+        if let Some(aperture) = hash.get(&key("aperture")) {
+            camera.aperture = number(aperture, &join(path, "aperture"))?;
+            if camera.aperture < 0.0 {
+                return Err(invalid_data(
+                    &join(path, "aperture"),
+                    "expected a number of at least 0",
+                ));
+            }
+        }
+        camera.focal_distance = (to - from).magnitude();
+        if let Some(distance) = hash.get(&key("focal-distance")) {
+            camera.focal_distance = number(distance, &join(path, "focal-distance"))?;
+            if camera.focal_distance <= 0.0 {
+                return Err(invalid_data(
+                    &join(path, "focal-distance"),
+                    "expected a number above 0",
+                ));
+            }
+        }
+        if let Some(blades) = hash.get(&key("aperture-blades")) {
+            let blades_path = join(path, "aperture-blades");
+            let blades = positive_integer(blades, &blades_path)?;
+            if blades < 3 {
+                return Err(invalid_data(&blades_path, "expected at least 3 blades"));
+            }
+            camera.aperture_shape = ApertureShape::Polygon(blades);
+        }
+
+        let projection = self.projection(hash, path)?;
+        projection
+            .validate(field_of_view)
+            .map_err(|error| invalid_data(path, &error.to_string()))?;
+        camera.set_projection(projection);
+
+        Ok(camera)
+    }
+
+    fn projection(&self, hash: &Hash, path: &str) -> Result<Projection, Error> {
+        let name = match hash.get(&key("projection")) {
+            Some(name) => string(name, &join(path, "projection"))?,
+            None => "perspective",
+        };
+        let view_width = hash.get(&key("view-width"));
+        if view_width.is_some() && name != "orthographic" {
+            return Err(invalid_data(
+                &join(path, "view-width"),
+                "only used by the orthographic projection",
+            ));
+        }
+
+        Ok(match name {
+            "perspective" => Projection::Perspective,
+            "orthographic" => Projection::Orthographic {
+                width: number(
+                    required(hash, "view-width", path)?,
+                    &join(path, "view-width"),
+                )?,
+            },
+            "fisheye" => Projection::Fisheye,
+            "equirectangular" => Projection::Equirectangular,
+            _ => {
+                return Err(invalid_data(
+                    &join(path, "projection"),
+                    &format!(
+                        "unknown projection \"{}\", expected perspective, orthographic, \
+                         fisheye or equirectangular",
+                        name
+                    ),
+                ))
+            }
+        })
+    }
+
+    fn light(&self, hash: &Hash, path: &str) -> Result<Light, Error> {
+        check_keys(hash, &["add", "at", "intensity"], path)?;
+
+        let at = triple(required(hash, "at", path)?, &join(path, "at"))?;
+        let intensity = color(required(hash, "intensity", path)?, &join(path, "intensity"))?;
+
+        Ok(Light::new(Point::new_point(at[0], at[1], at[2]), intensity))
+    }
+
+    fn material(&self, node: &Yaml, path: &str) -> Result<Material, Error> {
+        let hash = match node {
+            Yaml::String(name) => match self.define(name, path)? {
+                Yaml::Hash(hash) => hash,
+                _ => {
+                    return Err(invalid_data(
+                        path,
+                        &format!("\"{}\" is not defined as a material", name),
+                    ))
+                }
+            },
+            Yaml::Hash(hash) => hash,
+            _ => return Err(invalid_data(path, "expected a material or a define name")),
+        };
+        check_keys(hash, &MATERIAL_KEYS, path)?;
+
+        let mut material = Material::default();
+        let number_fields = [
+            ("ambient", &mut material.ambient),
+            ("diffuse", &mut material.diffuse),
+            ("specular", &mut material.specular),
+            ("shininess", &mut material.shininess),
+            ("reflective", &mut material.reflective),
+            ("transparency", &mut material.transparency),
+            ("refractive-index", &mut material.refractive_index),
+            ("emission-strength", &mut material.emission_strength),
+        ];
+        for (field, value) in number_fields {
+            if let Some(node) = hash.get(&key(field)) {
+                *value = number(node, &join(path, field))?;
+            }
+        }
+
+        if let Some(node) = hash.get(&key("color")) {
+            material.color = color(node, &join(path, "color"))?;
+        }
+        if let Some(node) = hash.get(&key("emission")) {
+            material.emission = color(node, &join(path, "emission"))?;
+        }
+        // either one switches the material to the microfacet model
+        if hash.contains_key(&key("roughness")) || hash.contains_key(&key("metallic")) {
+            let mut microfacet = Microfacet::default();
+            for (field, value) in [
+                ("roughness", &mut microfacet.roughness),
+                ("metallic", &mut microfacet.metallic),
+            ] {
+                if let Some(node) = hash.get(&key(field)) {
+                    let field_path = join(path, field);
+                    *value = number(node, &field_path)?;
+                    if !(0.0..=1.0).contains(value) {
+                        return Err(invalid_data(&field_path, "expected a value from 0 to 1"));
+                    }
+                }
+            }
+            material.microfacet = Some(microfacet);
+        }
+        if let Some(node) = hash.get(&key("pattern")) {
+            material.patterns = vec![self.pattern(node, &join(path, "pattern"))?];
+        }
+        if let Some(node) = hash.get(&key("patterns")) {
+            let patterns_path = join(path, "patterns");
+            for (i, pattern) in array(node, &patterns_path)?.iter().enumerate() {
+                let pattern_path = format!("{}[{}]", patterns_path, i);
+                material
+                    .patterns
+                    .push(self.pattern(pattern, &pattern_path)?);
+            }
+        }
+
+        Ok(material)
+    }
+
+    // pattern types are stripes, gradient, rings and checkers, which take
+    // either two colors or two nested patterns, and perturb, which wraps
+    // another pattern
+    fn pattern(&self, node: &Yaml, path: &str) -> Result<Box<dyn Pattern>, Error> {
+        let hash = match node {
+            Yaml::Hash(hash) => hash,
+            _ => return Err(invalid_data(path, "expected a pattern mapping")),
+        };
+        let kind = string(required(hash, "type", path)?, &join(path, "type"))?;
+
+        let mut pattern: Box<dyn Pattern> = if kind == "perturb" {
+            check_keys(hash, &["type", "pattern", "transform"], path)?;
+            let inner = self.pattern(required(hash, "pattern", path)?, &join(path, "pattern"))?;
+
+            Box::new(PerturbPattern::new(inner))
+        } else {
+            check_keys(hash, &["type", "colors", "patterns", "transform"], path)?;
+
+            let (color_a, color_b) = match hash.get(&key("colors")) {
+                Some(node) => {
+                    let colors_path = join(path, "colors");
+                    let colors = array(node, &colors_path)?;
+                    if colors.len() != 2 {
+                        return Err(invalid_data(&colors_path, "expected two colors"));
+                    }
+
+                    (
+                        color(&colors[0], &format!("{}[0]", colors_path))?,
+                        color(&colors[1], &format!("{}[1]", colors_path))?,
+                    )
+                }
+                None => (Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0)),
+            };
+
+            let nested_patterns = match hash.get(&key("patterns")) {
+                Some(node) => {
+                    let nested_path = join(path, "patterns");
+                    let nested = array(node, &nested_path)?;
+                    if nested.len() != 2 {
+                        return Err(invalid_data(&nested_path, "expected two patterns"));
+                    }
+
+                    Some([
+                        self.pattern(&nested[0], &format!("{}[0]", nested_path))?,
+                        self.pattern(&nested[1], &format!("{}[1]", nested_path))?,
+                    ])
+                }
+                None => None,
+            };
+
+            if !hash.contains_key(&key("colors")) && nested_patterns.is_none() {
+                return Err(invalid_data(path, "expected \"colors\" or \"patterns\""));
+            }
+
+            match kind {
+                "stripes" => Box::new(StripePattern::new(color_a, color_b, nested_patterns)),
+                "gradient" => Box::new(GradientPattern::new(color_a, color_b, nested_patterns)),
+                "rings" => Box::new(RingPattern::new(color_a, color_b, nested_patterns)),
+                "checkers" => Box::new(CheckerPattern::new(color_a, color_b, nested_patterns)),
+                _ => {
+                    return Err(invalid_data(
+                        &join(path, "type"),
+                        &format!(
+                            "unknown pattern type \"{}\", expected stripes, gradient, \
+                             rings, checkers or perturb",
+                            kind
+                        ),
+                    ))
+                }
+            }
+        };
+
+        if let Some(transform) = hash.get(&key("transform")) {
+            pattern.set_transform(self.transform(transform, &join(path, "transform"))?);
+        }
+
+        Ok(pattern)
+    }
+
+    // each entry is either an operation like [translate, 1, 2, 3] or the
+    // name of a defined list of operations
+    fn transform(&self, node: &Yaml, path: &str) -> Result<Matrix, Error> {
+        let mut transform = Matrix::new_identity();
+
+        for (i, operation) in array(node, path)?.iter().enumerate() {
+            let operation_path = format!("{}[{}]", path, i);
+            let matrix = match operation {
+                Yaml::String(name) => {
+                    self.transform(self.define(name, &operation_path)?, &operation_path)?
+                }
+                _ => transform_operation(operation, &operation_path)?,
+            };
+
+            transform = transform.then(&matrix);
+        }
+
+        // a flattened object has no inside to send rays into
+        if transform.inverse().is_none() {
+            return Err(invalid_data(path, "the transform can not be inverted"));
+        }
+
+        Ok(transform)
+    }
+
+    fn build(self) -> Result<(World, Camera), Error> {
+        let camera = self
+            .camera
+            .ok_or_else(|| invalid_data("scene", "expected an \"add: camera\" item"))?;
+
+        let mut world = World::new();
+        world.lights = self.lights;
+        world.objects = self.objects;
+
+        Ok((world, camera))
+    }
+}
+
+fn transform_operation(node: &Yaml, path: &str) -> Result<Matrix, Error> {
+    let values = array(node, path)?;
+    let operation = match values.first() {
+        Some(operation) => string(operation, &format!("{}[0]", path))?,
+        None => return Err(invalid_data(path, "expected an operation")),
+    };
+
+    let mut arguments = Vec::with_capacity(values.len() - 1);
+    for (i, value) in values.iter().enumerate().skip(1) {
+        arguments.push(number(value, &format!("{}[{}]", path, i))?);
+    }
+
+    let expected_arguments = match operation {
+        "translate" | "scale" => 3,
+        "rotate-x" | "rotate-y" | "rotate-z" => 1,
+        "shear" => 6,
+        _ => {
+            return Err(invalid_data(
+                path,
+                &format!(
+                    "unknown transform \"{}\", expected translate, scale, \
+                     rotate-x, rotate-y, rotate-z or shear",
+                    operation
+                ),
+            ))
+        }
+    };
+    if arguments.len() != expected_arguments {
+        return Err(invalid_data(
+            path,
+            &format!(
+                "{} takes {} values, got {}",
+                operation,
+                expected_arguments,
+                arguments.len()
+            ),
+        ));
+    }
+
+    let a = &arguments;
+    Ok(match operation {
+        "translate" => Matrix::translation(a[0], a[1], a[2]),
+        "scale" => Matrix::scaling(a[0], a[1], a[2]),
+        "rotate-x" => Matrix::rotation_x(a[0]),
+        "rotate-y" => Matrix::rotation_y(a[0]),
+        "rotate-z" => Matrix::rotation_z(a[0]),
+        _ => Matrix::shearing(a[0], a[1], a[2], a[3], a[4], a[5]),
+    })
+}
+
+fn key(name: &str) -> Yaml {
+    Yaml::String(name.to_string())
+}
+
+fn join(path: &str, name: &str) -> String {
+    format!("{} > {}", path, name)
+}
+
+fn invalid_data(path: &str, message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("{}: {}", path, message))
+}
+
+fn yaml_label(node: &Yaml) -> String {
+    match node {
+        Yaml::String(value) | Yaml::Real(value) => value.clone(),
+        Yaml::Integer(value) => value.to_string(),
+        _ => "?".to_string(),
+    }
+}
+
+fn check_keys(hash: &Hash, allowed: &[&str], path: &str) -> Result<(), Error> {
+    for name in hash.keys() {
+        let known = matches!(name, Yaml::String(name) if allowed.contains(&name.as_str()));
+        if !known {
+            return Err(invalid_data(
+                path,
+                &format!(
+                    "unknown key \"{}\", expected one of {}",
+                    yaml_label(name),
+                    allowed.join(", ")
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn required<'a>(hash: &'a Hash, name: &str, path: &str) -> Result<&'a Yaml, Error> {
+    hash.get(&key(name))
+        .ok_or_else(|| invalid_data(path, &format!("missing \"{}\"", name)))
+}
+
+fn string<'a>(node: &'a Yaml, path: &str) -> Result<&'a str, Error> {
+    match node {
+        Yaml::String(value) => Ok(value),
+        _ => Err(invalid_data(path, "expected a string")),
+    }
+}
+
+fn array<'a>(node: &'a Yaml, path: &str) -> Result<&'a [Yaml], Error> {
+    match node {
+        Yaml::Array(values) => Ok(values),
+        _ => Err(invalid_data(path, "expected a list")),
+    }
+}
+
+fn number(node: &Yaml, path: &str) -> Result<f64, Error> {
+    match node {
+        Yaml::Integer(value) => Ok(*value as f64),
+        Yaml::Real(_) => node
+            .as_f64()
+            .ok_or_else(|| invalid_data(path, "expected a number")),
+        _ => Err(invalid_data(path, "expected a number")),
+    }
+}
+
+fn positive_integer(node: &Yaml, path: &str) -> Result<usize, Error> {
+    match node {
+        Yaml::Integer(value) if *value > 0 => Ok(*value as usize),
+        _ => Err(invalid_data(path, "expected a positive whole number")),
+    }
+}
+
+fn triple(node: &Yaml, path: &str) -> Result<[f64; 3], Error> {
+    let values = array(node, path)?;
+    if values.len() != 3 {
+        return Err(invalid_data(path, "expected a list of three numbers"));
+    }
+
+    Ok([
+        number(&values[0], &format!("{}[0]", path))?,
+        number(&values[1], &format!("{}[1]", path))?,
+        number(&values[2], &format!("{}[2]", path))?,
+    ])
+}
+
+fn color(node: &Yaml, path: &str) -> Result<Color, Error> {
+    let values = triple(node, path)?;
+    Ok(Color::new(values[0], values[1], values[2]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base_types::Ray;
+    use std::f64::consts::PI;
+
+    const CAMERA: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 1.0471975512
+  from: [0, 1.5, -5]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+";
+
+    fn parse_with_camera(items: &str) -> Result<(World, Camera), Error> {
+        parse_yaml_scene(&format!("{}{}", CAMERA, items))
+    }
+
+    fn error_message(items: &str) -> String {
+        parse_with_camera(items).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn camera() {
+        let (_, camera) = parse_with_camera("").unwrap();
+
+        assert_eq!(camera.hsize(), 100);
+        assert_eq!(camera.vsize(), 50);
+        assert!(crate::utils::float_equal(camera.field_of_view, PI / 3.0));
+        assert!(camera
+            .get_transform()
+            .matrix()
+            .equal(&Matrix::view_transform(
+                Point::new_point(0.0, 1.5, -5.0),
+                Point::new_point(0.0, 1.0, 0.0),
+                Vector::new_vector(0.0, 1.0, 0.0),
+            )));
+    }
+
+    #[test]
+    fn motion_blur() {
+        let (world, camera) = parse_yaml_scene(
+            "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+  samples: 8
+  shutter: [0.25, 1]
+- add: sphere
+  transform:
+    - [translate, 1, 0, 0]
+  velocity: [0, 2, 0]
+- add: plane
+  end-transform:
+    - [rotate-x, 0.5]
+",
+        )
+        .unwrap();
+
+        assert_eq!(camera.samples_per_pixel, 8);
+        assert_eq!((camera.shutter_open, camera.shutter_close), (0.25, 1.0));
+        let sphere_end = world.objects[0].get_transform().end().unwrap();
+        assert!(sphere_end
+            .matrix()
+            .equal(&Matrix::translation(1.0, 2.0, 0.0)));
+        let plane_transform = world.objects[1].get_transform();
+        assert!(plane_transform.matrix().equal(&Matrix::new_identity()));
+        assert!(plane_transform
+            .end()
+            .unwrap()
+            .matrix()
+            .equal(&Matrix::rotation_x(0.5)));
+    }
+
+    #[test]
+    fn depth_of_field() {
+        let (_, camera) = parse_with_camera("").unwrap();
+        assert_eq!(camera.aperture, 0.0);
+
+        let lens = CAMERA.replace(
+            "  up: [0, 1, 0]",
+            "  up: [0, 1, 0]\n  aperture: 0.25\n  aperture-blades: 6",
+        );
+        let (_, camera) = parse_yaml_scene(&lens).unwrap();
+        assert_eq!(camera.aperture, 0.25);
+        assert_eq!(camera.aperture_shape, ApertureShape::Polygon(6));
+        // focused on what the camera looks at unless told otherwise
+        let to = Point::new_point(0.0, 1.0, 0.0);
+        let from = Point::new_point(0.0, 1.5, -5.0);
+        assert!(crate::utils::float_equal(
+            camera.focal_distance,
+            (to - from).magnitude()
+        ));
+
+        let focused = lens.replace("  aperture: 0.25", "  aperture: 0.25\n  focal-distance: 3");
+        let (_, camera) = parse_yaml_scene(&focused).unwrap();
+        assert_eq!(camera.focal_distance, 3.0);
+
+        let two_blades = lens.replace("aperture-blades: 6", "aperture-blades: 2");
+        assert_eq!(
+            parse_yaml_scene(&two_blades).err().unwrap().to_string(),
+            "scene[0] (add: camera) > aperture-blades: expected at least 3 blades"
+        );
+    }
+
+    #[test]
+    fn projections() {
+        let with = |lines: &str| {
+            parse_yaml_scene(
+                &CAMERA.replace("  up: [0, 1, 0]", &format!("  up: [0, 1, 0]\n{}", lines)),
+            )
+        };
+
+        let (_, camera) = parse_with_camera("").unwrap();
+        assert_eq!(camera.projection(), Projection::Perspective);
+        let (_, camera) = with("  projection: orthographic\n  view-width: 8").unwrap();
+        assert_eq!(camera.projection(), Projection::Orthographic { width: 8.0 });
+        let (_, camera) = with("  projection: fisheye").unwrap();
+        assert_eq!(camera.projection(), Projection::Fisheye);
+        let (_, camera) = with("  projection: equirectangular").unwrap();
+        assert_eq!(camera.projection(), Projection::Equirectangular);
+
+        let message = |lines: &str| with(lines).err().unwrap().to_string();
+        assert_eq!(
+            message("  projection: orthographic"),
+            "scene[0] (add: camera): missing \"view-width\""
+        );
+        assert_eq!(
+            message("  projection: fisheye\n  view-width: 8"),
+            "scene[0] (add: camera) > view-width: only used by the orthographic projection"
+        );
+        assert_eq!(
+            message("  projection: orthographic\n  view-width: -1"),
+            "scene[0] (add: camera): orthographic width must be above 0"
+        );
+        assert!(message("  projection: cylindrical").contains("unknown projection \"cylindrical\""));
+    }
+
+    #[test]
+    fn motion_blur_errors() {
+        assert_eq!(
+            error_message("- add: sphere\n  velocity: [1, 0, 0]\n  end-transform: []\n"),
+            "scene[1] (add: sphere): use either end-transform or velocity, not both"
+        );
+
+        let camera = CAMERA.replace("  up: [0, 1, 0]", "  up: [0, 1, 0]\n  shutter: [1, 0]");
+        assert_eq!(
+            parse_yaml_scene(&camera).err().unwrap().to_string(),
+            "scene[0] (add: camera) > shutter: expected 0 <= open <= close <= 1"
+        );
+    }
+
+    #[test]
+    fn camera_errors() {
+        let camera = CAMERA.replace("  up: [0, 1, 0]", "  up: [0, 0, 1]");
+        assert!(parse_yaml_scene(&camera).is_ok());
+
+        let camera = CAMERA.replace("  from: [0, 1.5, -5]", "  from: [0, 5, 0]");
+        let camera = camera.replace("  to: [0, 1, 0]", "  to: [0, 0, 0]");
+        assert_eq!(
+            parse_yaml_scene(&camera).err().unwrap().to_string(),
+            "scene[0] (add: camera) > up: zero or parallel to the view direction"
+        );
+
+        let camera = CAMERA.replace("  up: [0, 1, 0]", "  up: [0, 0, 0]");
+        assert_eq!(
+            parse_yaml_scene(&camera).err().unwrap().to_string(),
+            "scene[0] (add: camera) > up: zero or parallel to the view direction"
+        );
+
+        let camera = CAMERA.replace("  to: [0, 1, 0]", "  to: [0, 1.5, -5]");
+        assert_eq!(
+            parse_yaml_scene(&camera).err().unwrap().to_string(),
+            "scene[0] (add: camera) > to: the same point as from"
+        );
+    }
+
+    #[test]
+    fn lights() {
+        let (world, _) = parse_with_camera(
+            "
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 0.5, 0.25]
+- add: light
+  at: [5, 2, -10]
+  intensity: [0.5, 1, 0.25]
+",
+        )
+        .unwrap();
+
+        assert_eq!(world.lights.len(), 2);
+        assert_eq!(
+            world.lights[0].position,
+            Point::new_point(-10.0, 10.0, -10.0)
+        );
+        assert_eq!(world.lights[0].color, Color::new(1.0, 0.5, 0.25));
+    }
+
+    #[test]
+    fn shapes_with_material_and_transform() {
+        let (world, _) = parse_with_camera(
+            "
+- add: sphere
+  material:
+    color: [0.1, 1, 0.5]
+    diffuse: 0.7
+    specular: 0.3
+    reflective: 0.5
+    emission: [2, 2, 1]
+  transform:
+    - [scale, 0.5, 0.5, 0.5]
+    - [translate, 1.5, 0.5, -0.5]
+- add: plane
+- add: plane
+  material:
+    color: [1, 0.8, 0.3]
+    metallic: 1
+- add: quad
+  material:
+    emission: [1, 0.5, 1]
+    emission-strength: 4
+  transform:
+    - [translate, 0, 3, 0]
+",
+        )
+        .unwrap();
+
+        assert_eq!(world.objects.len(), 4);
+        let material = world.objects[0].get_material();
+        assert_eq!(material.color, Color::new(0.1, 1.0, 0.5));
+        assert_eq!(material.diffuse, 0.7);
+        assert_eq!(material.specular, 0.3);
+        assert_eq!(material.reflective, 0.5);
+        assert_eq!(material.emission, Color::new(2.0, 2.0, 1.0));
+        assert_eq!(material.ambient, Material::default().ambient);
+
+        // listed transforms are applied in order
+        assert!(world.objects[0]
+            .get_transform()
+            .matrix()
+            .equal(&Matrix::translation(1.5, 0.5, -0.5).mul(&Matrix::scaling(0.5, 0.5, 0.5))));
+        assert!(world.objects[1].equal(&Plane::default()));
+        assert_eq!(material.microfacet, None);
+        // either roughness or metallic switches to the microfacet model
+        assert_eq!(
+            world.objects[2].get_material().microfacet,
+            Some(Microfacet::new(0.5, 1.0))
+        );
+        let lamp_material = Material {
+            emission: Color::new(1.0, 0.5, 1.0),
+            emission_strength: 4.0,
+            ..Material::default()
+        };
+        assert!(world.objects[3].equal(&Quad::new(
+            Matrix::translation(0.0, 3.0, 0.0),
+            lamp_material
+        )));
+        assert_eq!(
+            world.objects[3].get_material().emitted(),
+            Color::new(4.0, 2.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn defines_and_extend() {
+        let (world, _) = parse_with_camera(
+            "
+- define: white-material
+  value:
+    color: [1, 1, 1]
+    diffuse: 0.7
+    ambient: 0.1
+- define: blue-material
+  extend: white-material
+  value:
+    color: [0.5, 0.8, 0.9]
+- define: standard-transform
+  value:
+    - [translate, 1, -1, 1]
+    - [scale, 0.5, 0.5, 0.5]
+- add: sphere
+  material: blue-material
+  transform:
+    - standard-transform
+    - [translate, 0, 0, 5]
+",
+        )
+        .unwrap();
+
+        let material = world.objects[0].get_material();
+        assert_eq!(material.color, Color::new(0.5, 0.8, 0.9));
+        assert_eq!(material.diffuse, 0.7);
+        assert!(world.objects[0].get_transform().matrix().equal(
+            &Matrix::translation(0.0, 0.0, 5.0)
+                .mul(&Matrix::scaling(0.5, 0.5, 0.5))
+                .mul(&Matrix::translation(1.0, -1.0, 1.0))
+        ));
+    }
+
+    #[test]
+    fn nested_transform_defines() {
+        let (world, _) = parse_with_camera(
+            "
+- define: lift
+  value:
+    - [translate, 0, 1, 0]
+- define: lift-and-grow
+  value:
+    - lift
+    - [scale, 2, 2, 2]
+- define: lift
+  value:
+    - lift
+    - lift
+- add: sphere
+  transform:
+    - lift-and-grow
+    - lift
+",
+        )
+        .unwrap();
+
+        // redefining lift in terms of itself uses the earlier lift, and
+        // leaves lift-and-grow as it was
+        assert!(world.objects[0].get_transform().matrix().equal(
+            &Matrix::translation(0.0, 2.0, 0.0)
+                .mul(&Matrix::scaling(2.0, 2.0, 2.0))
+                .mul(&Matrix::translation(0.0, 1.0, 0.0))
+        ));
+    }
+
+    #[test]
+    fn self_referencing_defines() {
+        assert_eq!(
+            error_message("- define: loop\n  value:\n    - loop\n"),
+            "scene[1] (define: loop) > value[0]: nothing is defined as \"loop\""
+        );
+        assert_eq!(
+            error_message(
+                "- define: ping\n  value:\n    - pong\n- define: pong\n  value:\n    - ping\n"
+            ),
+            "scene[1] (define: ping) > value[0]: nothing is defined as \"pong\""
+        );
+        assert_eq!(
+            error_message(
+                "- define: red\n  value:\n    color: [1, 0, 0]\n- define: move\n  value:\n    - red\n"
+            ),
+            "scene[2] (define: move) > value[0]: \"red\" is not defined as a transform"
+        );
+    }
+
+    #[test]
+    fn patterns() {
+        let (world, _) = parse_with_camera(
+            "
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      patterns:
+        - type: stripes
+          colors: [[1, 0, 0], [0, 1, 0]]
+        - type: perturb
+          pattern:
+            type: rings
+            colors: [[0, 0, 1], [1, 1, 1]]
+      transform:
+        - [scale, 2, 2, 2]
+",
+        )
+        .unwrap();
+
+        let mut expected_pattern = CheckerPattern::new(
+            Color::new(1.0, 1.0, 1.0),
+            Color::new(0.0, 0.0, 0.0),
+            Some([
+                Box::new(StripePattern::new(
+                    Color::new(1.0, 0.0, 0.0),
+                    Color::new(0.0, 1.0, 0.0),
+                    None,
+                )),
+                Box::new(PerturbPattern::new(Box::new(RingPattern::new(
+                    Color::new(0.0, 0.0, 1.0),
+                    Color::new(1.0, 1.0, 1.0),
+                    None,
+                )))),
+            ]),
+        );
+        expected_pattern.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let expected_material = Material {
+            patterns: vec![Box::new(expected_pattern)],
+            ..Material::default()
+        };
+
+        let material = world.objects[0].get_material();
+        assert!(*material == expected_material);
+        assert!(material.patterns[0]
+            .get_transform()
+            .matrix()
+            .equal(&Matrix::scaling(2.0, 2.0, 2.0)));
+    }
+
+    #[test]
+    fn renders_like_the_same_scene_built_in_code() {
+        let (world, camera) = parse_with_camera(
+            "
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+- add: sphere
+  material:
+    color: [0.8, 1.0, 0.6]
+    diffuse: 0.7
+    specular: 0.2
+- add: sphere
+  transform:
+    - [scale, 0.5, 0.5, 0.5]
+",
+        )
+        .unwrap();
+
+        let ray = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let mut default_world = World::default();
+        default_world.objects.truncate(2);
+
+        assert!(world
+            .color_at(ray, None)
+            .equal(default_world.color_at(ray, None)));
+        assert_eq!(camera.hsize(), 100);
+    }
+
+    #[test]
+    fn plane_scene_file_matches_the_example() {
+        let (world, camera) = parse_yaml_scene(include_str!("../../scenes/plane.yaml")).unwrap();
+        let (example_world, example_camera) = crate::examples::plane_scene(32, 22);
+
+        assert_eq!(camera.hsize(), 764);
+        assert_eq!(camera.vsize(), 528);
+
+        let mut small_camera = Camera::new(32, 22, camera.field_of_view);
+        small_camera.set_transform(camera.get_transform().matrix().clone());
+        assert!(small_camera
+            .render(&world)
+            .approx_equal(&example_camera.render(&example_world), 1e-9));
+    }
+
+    #[test]
+    fn errors_point_at_the_node() {
+        assert_eq!(
+            error_message("- add: cube\n"),
+            "scene[1] (add: cube): unknown item \"cube\", expected camera, light, sphere, plane or quad"
+        );
+        assert_eq!(
+            error_message("- add: sphere\n  material:\n    colour: [1, 1, 1]\n"),
+            "scene[1] (add: sphere) > material: unknown key \"colour\", expected one of \
+             color, pattern, ambient, diffuse, specular, shininess, reflective, \
+             transparency, refractive-index, patterns, emission, emission-strength, roughness, metallic"
+        );
+        assert_eq!(
+            error_message("- add: sphere\n  material:\n    pattern:\n      type: dots\n      colors: [[1, 1, 1], [0, 0, 0]]\n"),
+            "scene[1] (add: sphere) > material > pattern > type: unknown pattern type \"dots\", \
+             expected stripes, gradient, rings, checkers or perturb"
+        );
+        assert_eq!(
+            error_message("- add: plane\n  transform:\n    - [scale, 1, 2]\n"),
+            "scene[1] (add: plane) > transform[0]: scale takes 3 values, got 2"
+        );
+        assert_eq!(
+            error_message("- add: plane\n  material: shiny\n"),
+            "scene[1] (add: plane) > material: nothing is defined as \"shiny\""
+        );
+        assert_eq!(
+            error_message("- add: sphere\n  material:\n    roughness: 2\n"),
+            "scene[1] (add: sphere) > material > roughness: expected a value from 0 to 1"
+        );
+        assert_eq!(
+            error_message("- add: light\n  at: [1, 2, x]\n  intensity: [1, 1, 1]\n"),
+            "scene[1] (add: light) > at[2]: expected a number"
+        );
+    }
+
+    #[test]
+    fn singular_transforms() {
+        assert_eq!(
+            error_message("- add: sphere\n  transform:\n    - [scale, 0, 1, 1]\n"),
+            "scene[1] (add: sphere) > transform: the transform can not be inverted"
+        );
+        assert_eq!(
+            error_message("- add: sphere\n  end-transform:\n    - [scale, 1, 0, 1]\n"),
+            "scene[1] (add: sphere) > end-transform: the transform can not be inverted"
+        );
+        assert_eq!(
+            error_message(
+                "- add: plane\n  material:\n    pattern:\n      type: stripes\n      \
+                 colors: [[1, 1, 1], [0, 0, 0]]\n      transform:\n        - [scale, 0, 1, 1]\n"
+            ),
+            "scene[1] (add: plane) > material > pattern > transform: \
+             the transform can not be inverted"
+        );
+        assert_eq!(
+            error_message(
+                "- define: flat\n  value:\n    - [scale, 1, 0, 1]\n\
+                 - add: sphere\n  transform:\n    - flat\n"
+            ),
+            "scene[2] (add: sphere) > transform[0]: the transform can not be inverted"
+        );
+        // tiny objects are fine
+        assert!(parse_with_camera(
+            "- add: sphere\n  transform:\n    - [scale, 1e-4, 1e-4, 1e-4]\n"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn camera_is_required() {
+        let error = parse_yaml_scene("- add: sphere\n").err().unwrap();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "scene: expected an \"add: camera\" item");
+    }
+
+    #[test]
+    fn invalid_yaml() {
+        assert!(parse_yaml_scene("- add: [camera").is_err());
+        assert!(parse_yaml_scene("add: camera").is_err());
+    }
+}
@@ -48,7 +48,7 @@ fn merge(vec: &mut [Intersection], l: usize, mid: usize, r: usize) {
     }
 }
 
-pub fn is_sorted(vec: &Vec<Intersection>) -> bool {
+pub fn is_sorted(vec: &[Intersection]) -> bool {
     let len = vec.len();
     for i in 1..len {
         if vec[i].distance < vec[i - 1].distance {
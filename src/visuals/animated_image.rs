@@ -0,0 +1,431 @@
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, Rgba, RgbaImage};
+
+use super::{Canvas, DEFAULT_WEBP_QUALITY};
+
+// 1 is the best gif palette and very slow, 10 is what the gif crate
+// itself defaults to
+const GIF_ENCODE_SPEED: i32 = 10;
+// frame durations in webp files are 24 bit
+const WEBP_MAX_FRAME_DELAY_MS: u32 = (1 << 24) - 1;
+// webp frame chunks that hold image data, the others are only metadata
+const WEBP_IMAGE_CHUNKS: [&[u8; 4]; 3] = [b"ALPH", b"VP8 ", b"VP8L"];
+// animation flag of the VP8X chunk
+const WEBP_ANIMATION_FLAG: u8 = 0b0000_0010;
+// ANMF flag to draw each frame over the previous one without blending
+const WEBP_NO_BLEND_FLAG: u8 = 0b0000_0010;
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Clone, Copy)]
+pub enum AnimationFormat {
+    Gif,
+    WebpLossless,
+    // lossy webp, quality is between 0 and 100
+    Webp(f32),
+}
+
+impl AnimationFormat {
+    // picks the format based on the file extension of the path, .webp
+    // files are lossy with the default quality
+    pub fn from_path(path: &str) -> Result<Self, Error> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("gif") => Ok(Self::Gif),
+            Some("webp") => Ok(Self::Webp(DEFAULT_WEBP_QUALITY)),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unsupported animation extension for {}", path),
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct AnimationTiming {
+    // how long each frame is shown. Gif files round this to 10ms
+    pub frame_delay_ms: u32,
+    // how many times the animation plays, 0 loops forever
+    pub loop_count: u16,
+}
+
+impl AnimationTiming {
+    pub fn new(frame_delay_ms: u32, loop_count: u16) -> Self {
+        Self {
+            frame_delay_ms,
+            loop_count,
+        }
+    }
+}
+
+// all frames need to be the same size
+pub fn encode_animation(
+    frames: &[Canvas],
+    format: AnimationFormat,
+    timing: AnimationTiming,
+) -> Result<Vec<u8>, Error> {
+    let Some(first) = frames.first() else {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "an animation needs at least one frame",
+        ));
+    };
+    if first.width() == 0 || first.height() == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "animation frames can not be empty",
+        ));
+    }
+    if let Some((index, frame)) = frames
+        .iter()
+        .enumerate()
+        .find(|(_, frame)| frame.width() != first.width() || frame.height() != first.height())
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "frame {} is {}x{}, but the first frame is {}x{}",
+                index,
+                frame.width(),
+                frame.height(),
+                first.width(),
+                first.height()
+            ),
+        ));
+    }
+
+    match format {
+        AnimationFormat::Gif => to_gif(frames, timing),
+        AnimationFormat::WebpLossless => to_webp(frames, None, timing),
+        AnimationFormat::Webp(quality) => {
+            if !(0.0..=100.0).contains(&quality) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("webp quality must be between 0 and 100, got {}", quality),
+                ));
+            }
+
+            to_webp(frames, Some(quality), timing)
+        }
+    }
+}
+
+pub fn save_animation(
+    path: &str,
+    frames: &[Canvas],
+    format: AnimationFormat,
+    timing: AnimationTiming,
+) -> Result<(), Error> {
+    let bytes = encode_animation(frames, format, timing)?;
+
+    match std::fs::write(path, bytes) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(Error::new(ErrorKind::InvalidInput, error.to_string())),
+    }
+}
+
+fn to_gif(frames: &[Canvas], timing: AnimationTiming) -> Result<Vec<u8>, Error> {
+    let mut gif = Vec::new();
+    let mut encoder = GifEncoder::new_with_speed(&mut gif, GIF_ENCODE_SPEED);
+    // gif counts the repeats after the first time through
+    let repeat = match timing.loop_count {
+        0 => Repeat::Infinite,
+        loop_count => Repeat::Finite(loop_count - 1),
+    };
+    let delay = Delay::from_numer_denom_ms(timing.frame_delay_ms, 1);
+
+    let result = encoder.set_repeat(repeat).and_then(|_| {
+        encoder.encode_frames(frames.iter().map(|canvas| {
+            let image =
+                RgbaImage::from_fn(canvas.width() as u32, canvas.height() as u32, |x, y| {
+                    let [red, green, blue] = canvas.pixel_at(x as usize, y as usize).to_vec();
+                    Rgba([red, green, blue, u8::MAX])
+                });
+
+            Frame::from_parts(image, 0, 0, delay)
+        }))
+    });
+    drop(encoder);
+
+    match result {
+        Ok(_) => Ok(gif),
+        Err(error) => Err(Error::new(ErrorKind::InvalidData, error.to_string())),
+    }
+}
+
+// every frame is encoded as a still webp, and its image chunks are then
+// wrapped in the animation container, see
+// https://developers.google.com/speed/webp/docs/riff_container
+fn to_webp(
+    frames: &[Canvas],
+    quality: Option<f32>,
+    timing: AnimationTiming,
+) -> Result<Vec<u8>, Error> {
+    if timing.frame_delay_ms > WEBP_MAX_FRAME_DELAY_MS {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "webp frames can be shown for at most {}ms, got {}ms",
+                WEBP_MAX_FRAME_DELAY_MS, timing.frame_delay_ms
+            ),
+        ));
+    }
+
+    let width = frames[0].width() as u32;
+    let height = frames[0].height() as u32;
+
+    let mut vp8x = vec![WEBP_ANIMATION_FLAG, 0, 0, 0];
+    vp8x.extend_from_slice(&to_u24(width - 1));
+    vp8x.extend_from_slice(&to_u24(height - 1));
+
+    // black background, in bgra order
+    let mut anim = vec![0, 0, 0, u8::MAX];
+    anim.extend_from_slice(&timing.loop_count.to_le_bytes());
+
+    let mut body = b"WEBP".to_vec();
+    write_chunk(&mut body, b"VP8X", &vp8x);
+    write_chunk(&mut body, b"ANIM", &anim);
+
+    for canvas in frames {
+        // frame offset, which is always the top left corner
+        let mut anmf = vec![0; 6];
+        anmf.extend_from_slice(&to_u24(width - 1));
+        anmf.extend_from_slice(&to_u24(height - 1));
+        anmf.extend_from_slice(&to_u24(timing.frame_delay_ms));
+        anmf.push(WEBP_NO_BLEND_FLAG);
+        anmf.extend(webp_image_chunks(&canvas.to_webp(quality))?);
+
+        write_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    let mut webp = b"RIFF".to_vec();
+    webp.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    webp.extend(body);
+
+    Ok(webp)
+}
+
+// the image chunks of a still webp file, as they are laid out in it
+fn webp_image_chunks(webp: &[u8]) -> Result<Vec<u8>, Error> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "webp encoder gave an invalid file");
+
+    if webp.len() < 12 || &webp[..4] != b"RIFF" || &webp[8..12] != b"WEBP" {
+        return Err(invalid());
+    }
+
+    let mut chunks = Vec::new();
+    let mut offset = 12;
+    while offset + 8 <= webp.len() {
+        let size = u32::from_le_bytes(webp[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        // chunks are padded to an even size
+        let end = offset + 8 + size + size % 2;
+        if end > webp.len() {
+            return Err(invalid());
+        }
+
+        if WEBP_IMAGE_CHUNKS.contains(&&webp[offset..offset + 4].try_into().unwrap()) {
+            chunks.extend_from_slice(&webp[offset..end]);
+        }
+        offset = end;
+    }
+
+    if chunks.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(chunks)
+}
+
+fn write_chunk(webp: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+    webp.extend_from_slice(fourcc);
+    webp.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    webp.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        webp.push(0);
+    }
+}
+
+fn to_u24(value: u32) -> [u8; 3] {
+    let bytes = value.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::visuals::Color;
+    use image::codecs::gif::GifDecoder;
+    use image::codecs::webp::WebPDecoder;
+    use image::AnimationDecoder;
+    use std::io::Cursor;
+
+    fn frames() -> Vec<Canvas> {
+        [
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+        ]
+        .into_iter()
+        .map(|color| {
+            let mut canvas = Canvas::new(5, 3);
+            for y in 0..3 {
+                for x in 0..5 {
+                    canvas.write_pixel(x, y, color);
+                }
+            }
+            canvas
+        })
+        .collect()
+    }
+
+    fn assert_frames(decoded: Vec<Frame>, delay_ms: u32) {
+        let expected = frames();
+
+        assert_eq!(decoded.len(), expected.len());
+        for (frame, canvas) in decoded.iter().zip(expected) {
+            assert_eq!(frame.delay().numer_denom_ms(), (delay_ms, 1));
+            assert_eq!(frame.buffer().dimensions(), (5, 3));
+
+            let [red, green, blue] = canvas.pixel_at(0, 0).to_vec();
+            assert_eq!(
+                frame.buffer().get_pixel(2, 1),
+                &Rgba([red, green, blue, u8::MAX])
+            );
+        }
+    }
+
+    #[test]
+    fn from_path() {
+        assert_eq!(
+            AnimationFormat::from_path("spin.GIF").unwrap(),
+            AnimationFormat::Gif
+        );
+        assert_eq!(
+            AnimationFormat::from_path("out/spin.webp").unwrap(),
+            AnimationFormat::Webp(DEFAULT_WEBP_QUALITY)
+        );
+        assert!(AnimationFormat::from_path("spin.png").is_err());
+    }
+
+    #[test]
+    fn gif() {
+        let gif =
+            encode_animation(&frames(), AnimationFormat::Gif, AnimationTiming::new(50, 0)).unwrap();
+        // the netscape extension holds the loop count, 0 loops forever
+        let netscape = gif
+            .windows(11)
+            .position(|window| window == b"NETSCAPE2.0")
+            .unwrap();
+        assert_eq!(&gif[netscape + 13..netscape + 15], &[0, 0]);
+
+        let decoded = GifDecoder::new(Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_frames(decoded, 50);
+    }
+
+    #[test]
+    fn gif_loop_count() {
+        let gif =
+            encode_animation(&frames(), AnimationFormat::Gif, AnimationTiming::new(50, 3)).unwrap();
+        let netscape = gif
+            .windows(11)
+            .position(|window| window == b"NETSCAPE2.0")
+            .unwrap();
+
+        // played once and then repeated twice
+        assert_eq!(&gif[netscape + 13..netscape + 15], &[2, 0]);
+    }
+
+    #[test]
+    fn webp_lossless() {
+        let webp = encode_animation(
+            &frames(),
+            AnimationFormat::WebpLossless,
+            AnimationTiming::new(40, 2),
+        )
+        .unwrap();
+
+        assert_eq!(&webp[..4], b"RIFF");
+        assert_eq!(
+            u32::from_le_bytes(webp[4..8].try_into().unwrap()) as usize,
+            webp.len() - 8
+        );
+        // loop count is the last field of the ANIM chunk
+        let anim = webp
+            .windows(4)
+            .position(|window| window == b"ANIM")
+            .unwrap();
+        assert_eq!(&webp[anim + 12..anim + 14], &[2, 0]);
+
+        let decoded = WebPDecoder::new(Cursor::new(webp))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_frames(decoded, 40);
+    }
+
+    #[test]
+    fn webp_lossy() {
+        let webp = encode_animation(
+            &frames(),
+            AnimationFormat::Webp(90.0),
+            AnimationTiming::new(100, 0),
+        )
+        .unwrap();
+
+        // the image crate can't decode lossy animations, so each frame is
+        // unwrapped into a still webp and decoded on its own instead
+        let mut offset = 12;
+        let mut green_values = Vec::new();
+        while offset < webp.len() {
+            let size =
+                u32::from_le_bytes(webp[offset + 4..offset + 8].try_into().unwrap()) as usize;
+            if &webp[offset..offset + 4] == b"ANMF" {
+                let frame_chunks = &webp[offset + 24..offset + 8 + size];
+                let mut still = b"RIFF".to_vec();
+                still.extend_from_slice(&(frame_chunks.len() as u32 + 4).to_le_bytes());
+                still.extend_from_slice(b"WEBP");
+                still.extend_from_slice(frame_chunks);
+
+                let image = webp::Decoder::new(&still).decode().unwrap();
+                assert_eq!((image.width(), image.height()), (5, 3));
+                green_values.push(image[(5 + 2) * 3 + 1]);
+            }
+            offset += 8 + size + size % 2;
+        }
+
+        // lossy, so only close to the original colors
+        assert_eq!(green_values.len(), 3);
+        assert!(green_values[0] < 20 && green_values[1] > 235 && green_values[2] < 20);
+    }
+
+    #[test]
+    fn invalid_animations() {
+        let timing = AnimationTiming::new(40, 0);
+
+        assert!(encode_animation(&[], AnimationFormat::Gif, timing).is_err());
+        assert!(encode_animation(
+            &[Canvas::new(5, 3), Canvas::new(3, 5)],
+            AnimationFormat::Gif,
+            timing
+        )
+        .is_err());
+        assert!(encode_animation(&frames(), AnimationFormat::Webp(101.0), timing).is_err());
+        assert!(encode_animation(
+            &frames(),
+            AnimationFormat::WebpLossless,
+            AnimationTiming::new(WEBP_MAX_FRAME_DELAY_MS + 1, 0)
+        )
+        .is_err());
+    }
+}
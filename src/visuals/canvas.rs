@@ -1,9 +1,18 @@
-use std::io::{Error, ErrorKind};
+use std::io::{Cursor, Error, ErrorKind};
 
-use image::ImageBuffer;
-use webp::{Encoder, WebPMemory};
+use image::codecs::hdr::HdrEncoder;
+use image::codecs::openexr::OpenExrEncoder;
+use image::codecs::png::PngEncoder;
+use image::{ColorType, DynamicImage, ImageEncoder, Rgb};
+use webp::{Decoder, Encoder, WebPMemory};
 
-use crate::visuals::Color;
+use crate::visuals::{Color, ImageFormat, OutputTransform};
+
+// the ppm spec asks that lines in plain ppm files not exceed 70 characters
+const PPM_MAX_LINE_LENGTH: usize = 70;
+const PPM_MAX_COLOR_VALUE: usize = 255;
+// negative scale marks the pfm pixel data as little endian
+const PFM_LITTLE_ENDIAN_SCALE: &str = "-1.0";
 
 pub struct Canvas {
     grid: Vec<Vec<Color>>,
@@ -25,7 +34,7 @@ impl Canvas {
 
     pub fn write_pixel(&mut self, x: usize, y: usize, color: Color) {
         // do bounds check
-        if (self.height - y) > self.height || (self.width - x) > self.width {
+        if y > self.height || x > self.width {
             return;
         }
 
@@ -37,30 +46,284 @@ impl Canvas {
         self.grid[y][x]
     }
 
-    // saves canvas as webp file
-    pub fn save_canvas(&self, path: &str) -> Result<(), Error> {
-        let mut image_buff = ImageBuffer::new(self.width as u32 + 1, self.height as u32 + 1);
+    pub fn width(&self) -> usize {
+        self.width + 1
+    }
+
+    pub fn height(&self) -> usize {
+        self.height + 1
+    }
+
+    // loads a canvas from a png, ppm or webp file
+    pub fn load_canvas(path: &str) -> Result<Self, Error> {
+        let format = ImageFormat::from_path(path)?;
+        let bytes = std::fs::read(path)?;
+
+        Self::decode(&bytes, format)
+    }
+
+    pub fn decode(bytes: &[u8], format: ImageFormat) -> Result<Self, Error> {
+        let image = match format {
+            ImageFormat::Png | ImageFormat::PpmPlain | ImageFormat::PpmBinary => {
+                let image_format = match format {
+                    ImageFormat::Png => image::ImageFormat::Png,
+                    _ => image::ImageFormat::Pnm,
+                };
 
-        for (y, _) in self.grid.iter().enumerate() {
-            for (x, color) in self.grid[y].iter().enumerate() {
-                image_buff.put_pixel(x as u32, y as u32, image::Rgb(color.to_vec()));
+                match image::load_from_memory_with_format(bytes, image_format) {
+                    Ok(image) => image,
+                    Err(error) => {
+                        return Err(Error::new(ErrorKind::InvalidData, error.to_string()))
+                    }
+                }
             }
+            ImageFormat::WebpLossless | ImageFormat::Webp(_) => {
+                match Decoder::new(bytes).decode() {
+                    Some(webp) => webp.to_image(),
+                    None => {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            "could not decode webp image",
+                        ))
+                    }
+                }
+            }
+            ImageFormat::RadianceHdr | ImageFormat::OpenExr | ImageFormat::Pfm => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "only png, ppm and webp images can be loaded",
+                ))
+            }
+        };
+
+        Self::from_image(image)
+    }
+
+    fn from_image(image: DynamicImage) -> Result<Self, Error> {
+        let image = image.into_rgb8();
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "image has no pixels"));
         }
 
-        let encoder: Encoder =
-            Encoder::from_rgb(&image_buff, self.width as u32 + 1, self.height as u32 + 1);
-        let webp: WebPMemory = encoder.encode(65f32);
+        let mut canvas = Self::new(width as usize, height as usize);
+        for (x, y, pixel) in image.enumerate_pixels() {
+            canvas.write_pixel(x as usize, y as usize, Color::from_vec(pixel.0));
+        }
+
+        Ok(canvas)
+    }
 
-        match std::fs::write(path, webp.as_ref()) {
+    // saves canvas in the format matching the extension of the path
+    pub fn save_canvas(&self, path: &str) -> Result<(), Error> {
+        self.save_canvas_as(path, ImageFormat::from_path(path)?)
+    }
+
+    // runs the colors through the output transform (exposure, tone mapping
+    // and srgb encoding) before saving
+    pub fn save_canvas_with_transform(
+        &self,
+        path: &str,
+        format: ImageFormat,
+        transform: &OutputTransform,
+    ) -> Result<(), Error> {
+        self.with_output_transform(transform)
+            .save_canvas_as(path, format)
+    }
+
+    pub fn with_output_transform(&self, transform: &OutputTransform) -> Self {
+        self.map(|color| transform.apply(color))
+    }
+
+    pub fn save_canvas_as(&self, path: &str, format: ImageFormat) -> Result<(), Error> {
+        let bytes = self.encode(format)?;
+
+        match std::fs::write(path, bytes) {
             Ok(_) => Ok(()),
             Err(error) => Err(Error::new(ErrorKind::InvalidInput, error.to_string())),
         }
     }
+
+    pub fn encode(&self, format: ImageFormat) -> Result<Vec<u8>, Error> {
+        match format {
+            ImageFormat::Png => self.to_png(),
+            ImageFormat::PpmPlain => Ok(self.to_plain_ppm()),
+            ImageFormat::PpmBinary => Ok(self.to_binary_ppm()),
+            ImageFormat::WebpLossless => Ok(self.to_webp(None)),
+            ImageFormat::Webp(quality) => {
+                if !(0.0..=100.0).contains(&quality) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("webp quality must be between 0 and 100, got {}", quality),
+                    ));
+                }
+
+                Ok(self.to_webp(Some(quality)))
+            }
+            ImageFormat::RadianceHdr => self.to_hdr(),
+            ImageFormat::OpenExr => self.to_exr(),
+            ImageFormat::Pfm => Ok(self.to_pfm()),
+        }
+    }
+
+    // row-major, unclamped rgb values, 3 per pixel
+    pub fn to_rgb_f32(&self) -> Vec<f32> {
+        let mut values = Vec::with_capacity(self.width() * self.height() * 3);
+        for row in self.grid.iter() {
+            for color in row.iter() {
+                values.extend_from_slice(&color.to_f32_vec());
+            }
+        }
+
+        values
+    }
+
+    // row-major rgb bytes, 3 per pixel
+    fn to_rgb_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.width() * self.height() * 3);
+        for row in self.grid.iter() {
+            for color in row.iter() {
+                bytes.extend_from_slice(&color.to_vec());
+            }
+        }
+
+        bytes
+    }
+
+    fn to_png(&self) -> Result<Vec<u8>, Error> {
+        let mut png = Vec::new();
+        let encoder = PngEncoder::new(&mut png);
+
+        match encoder.write_image(
+            &self.to_rgb_bytes(),
+            self.width() as u32,
+            self.height() as u32,
+            ColorType::Rgb8,
+        ) {
+            Ok(_) => Ok(png),
+            Err(error) => Err(Error::new(ErrorKind::InvalidData, error.to_string())),
+        }
+    }
+
+    fn ppm_header(&self, magic_number: &str) -> String {
+        format!(
+            "{}\n{} {}\n{}\n",
+            magic_number,
+            self.width(),
+            self.height(),
+            PPM_MAX_COLOR_VALUE
+        )
+    }
+
+    fn to_plain_ppm(&self) -> Vec<u8> {
+        let mut ppm = self.ppm_header("P3");
+
+        for row in self.grid.iter() {
+            let mut line = String::new();
+            for color in row.iter() {
+                for value in color.to_vec() {
+                    let value = value.to_string();
+                    // +1 for the separating space
+                    if !line.is_empty() && line.len() + value.len() + 1 > PPM_MAX_LINE_LENGTH {
+                        ppm.push_str(&line);
+                        ppm.push('\n');
+                        line.clear();
+                    }
+
+                    if !line.is_empty() {
+                        line.push(' ');
+                    }
+                    line.push_str(&value);
+                }
+            }
+
+            ppm.push_str(&line);
+            ppm.push('\n');
+        }
+
+        ppm.into_bytes()
+    }
+
+    fn to_binary_ppm(&self) -> Vec<u8> {
+        let mut ppm = self.ppm_header("P6").into_bytes();
+        ppm.extend(self.to_rgb_bytes());
+
+        ppm
+    }
+
+    fn to_hdr(&self) -> Result<Vec<u8>, Error> {
+        let mut hdr = Vec::new();
+        // radiance hdr has no way to represent negative values
+        let pixels: Vec<Rgb<f32>> = self
+            .to_rgb_f32()
+            .chunks(3)
+            .map(|rgb| Rgb([rgb[0].max(0.0), rgb[1].max(0.0), rgb[2].max(0.0)]))
+            .collect();
+
+        match HdrEncoder::new(&mut hdr).encode(&pixels, self.width(), self.height()) {
+            Ok(_) => Ok(hdr),
+            Err(error) => Err(Error::new(ErrorKind::InvalidData, error.to_string())),
+        }
+    }
+
+    fn to_exr(&self) -> Result<Vec<u8>, Error> {
+        let mut exr = Cursor::new(Vec::new());
+        let bytes: Vec<u8> = self
+            .to_rgb_f32()
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+
+        match OpenExrEncoder::new(&mut exr).write_image(
+            &bytes,
+            self.width() as u32,
+            self.height() as u32,
+            ColorType::Rgb32F,
+        ) {
+            Ok(_) => Ok(exr.into_inner()),
+            Err(error) => Err(Error::new(ErrorKind::InvalidData, error.to_string())),
+        }
+    }
+
+    fn to_pfm(&self) -> Vec<u8> {
+        let mut pfm = format!(
+            "PF\n{} {}\n{}\n",
+            self.width(),
+            self.height(),
+            PFM_LITTLE_ENDIAN_SCALE
+        )
+        .into_bytes();
+
+        // pfm scanlines go from the bottom of the image to the top
+        for row in self.grid.iter().rev() {
+            for color in row.iter() {
+                for value in color.to_f32_vec() {
+                    pfm.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+
+        pfm
+    }
+
+    // lossless when no quality is given
+    pub(super) fn to_webp(&self, quality: Option<f32>) -> Vec<u8> {
+        let rgb_bytes = self.to_rgb_bytes();
+        let encoder: Encoder =
+            Encoder::from_rgb(&rgb_bytes, self.width() as u32, self.height() as u32);
+        let webp: WebPMemory = match quality {
+            Some(quality) => encoder.encode(quality),
+            None => encoder.encode_lossless(),
+        };
+
+        webp.to_vec()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::visuals::ToneMapping;
 
     #[test]
     fn new() {
@@ -89,4 +352,196 @@ mod test {
         assert_eq!(canvas.pixel_at(9, 21), Color::new(8.0, 42.5, 200.0));
         assert_eq!(canvas.pixel_at(0, 0), Color::new(24.0, 42.5, 244.0));
     }
+
+    #[test]
+    fn plain_ppm_header() {
+        let canvas = Canvas::new(5, 3);
+        let ppm = String::from_utf8(canvas.encode(ImageFormat::PpmPlain).unwrap()).unwrap();
+        let lines: Vec<&str> = ppm.lines().collect();
+
+        assert_eq!(lines[0], "P3");
+        assert_eq!(lines[1], "5 3");
+        assert_eq!(lines[2], "255");
+    }
+
+    #[test]
+    fn plain_ppm_pixel_data() {
+        let mut canvas = Canvas::new(5, 3);
+        canvas.write_pixel(0, 0, Color::new(1.5, 0.0, 0.0));
+        canvas.write_pixel(2, 1, Color::new(0.0, 0.5, 0.0));
+        canvas.write_pixel(4, 2, Color::new(-0.5, 0.0, 1.0));
+        let ppm = String::from_utf8(canvas.encode(ImageFormat::PpmPlain).unwrap()).unwrap();
+        let lines: Vec<&str> = ppm.lines().collect();
+
+        assert_eq!(lines[3], "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0");
+        assert_eq!(lines[4], "0 0 0 0 0 0 0 128 0 0 0 0 0 0 0");
+        assert_eq!(lines[5], "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255");
+        assert!(ppm.ends_with('\n'));
+    }
+
+    #[test]
+    fn plain_ppm_splits_long_lines() {
+        let mut canvas = Canvas::new(10, 2);
+        for y in 0..2 {
+            for x in 0..10 {
+                canvas.write_pixel(x, y, Color::new(1.0, 0.8, 0.6));
+            }
+        }
+        let ppm = String::from_utf8(canvas.encode(ImageFormat::PpmPlain).unwrap()).unwrap();
+        let lines: Vec<&str> = ppm.lines().collect();
+
+        assert_eq!(
+            lines[3],
+            "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204"
+        );
+        assert_eq!(
+            lines[4],
+            "153 255 204 153 255 204 153 255 204 153 255 204 153"
+        );
+        assert!(lines.iter().all(|line| line.len() <= PPM_MAX_LINE_LENGTH));
+    }
+
+    #[test]
+    fn binary_ppm() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(1, 0, Color::new(1.0, 0.5, 0.0));
+        let ppm = canvas.encode(ImageFormat::PpmBinary).unwrap();
+
+        assert_eq!(&ppm[..11], b"P6\n2 1\n255\n");
+        assert_eq!(&ppm[11..], &[0, 0, 0, 255, 128, 0]);
+    }
+
+    #[test]
+    fn png_signature() {
+        let canvas = Canvas::new(4, 4);
+        let png = canvas.encode(ImageFormat::Png).unwrap();
+
+        assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+    }
+
+    #[test]
+    fn webp_quality_out_of_range() {
+        let canvas = Canvas::new(4, 4);
+
+        assert!(canvas.encode(ImageFormat::Webp(101.0)).is_err());
+        assert!(canvas.encode(ImageFormat::Webp(-1.0)).is_err());
+        assert!(canvas.encode(ImageFormat::Webp(80.0)).is_ok());
+        assert!(canvas.encode(ImageFormat::WebpLossless).is_ok());
+    }
+
+    #[test]
+    fn save_canvas_unsupported_extension() {
+        let canvas = Canvas::new(4, 4);
+        let error = canvas.save_canvas("canvas.tga").unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn to_rgb_f32_keeps_high_dynamic_range() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(1, 0, Color::new(4.5, -0.5, 0.25));
+
+        assert_eq!(canvas.to_rgb_f32(), vec![0.0, 0.0, 0.0, 4.5, -0.5, 0.25]);
+    }
+
+    #[test]
+    fn pfm() {
+        let mut canvas = Canvas::new(1, 2);
+        canvas.write_pixel(0, 0, Color::new(2.0, 0.5, 0.0));
+        let pfm = canvas.encode(ImageFormat::Pfm).unwrap();
+
+        let header = b"PF\n1 2\n-1.0\n";
+        assert_eq!(&pfm[..header.len()], header);
+
+        // bottom row first, so the written pixel is the second one
+        let pixels: Vec<f32> = pfm[header.len()..]
+            .chunks(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        assert_eq!(pixels, vec![0.0, 0.0, 0.0, 2.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn radiance_hdr_round_trip() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(2, 1, Color::new(8.0, 1.0, 0.5));
+        let hdr = canvas.encode(ImageFormat::RadianceHdr).unwrap();
+
+        let decoder = image::codecs::hdr::HdrDecoder::new(hdr.as_slice()).unwrap();
+        assert_eq!(
+            (decoder.metadata().width, decoder.metadata().height),
+            (3, 2)
+        );
+
+        let pixels = decoder.read_image_hdr().unwrap();
+        assert_eq!(pixels[5].0, [8.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn open_exr_round_trip() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 1, Color::new(12.25, 0.125, 3.0));
+        let exr = canvas.encode(ImageFormat::OpenExr).unwrap();
+
+        let image = image::load_from_memory(&exr).unwrap().into_rgb32f();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(0, 1).0, [12.25, 0.125, 3.0]);
+    }
+
+    #[test]
+    fn with_output_transform() {
+        let mut canvas = Canvas::new(2, 3);
+        canvas.write_pixel(1, 2, Color::new(3.0, 1.0, 0.0));
+        let transform = OutputTransform::new(0.0, ToneMapping::Reinhard, false);
+        let mapped = canvas.with_output_transform(&transform);
+
+        assert_eq!(mapped.width(), 2);
+        assert_eq!(mapped.height(), 3);
+        assert_eq!(mapped.pixel_at(1, 2), Color::new(0.75, 0.5, 0.0));
+        assert_eq!(mapped.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn decode_round_trip() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        canvas.write_pixel(2, 1, Color::new(0.2, 0.4, 0.6));
+
+        for format in [
+            ImageFormat::Png,
+            ImageFormat::PpmPlain,
+            ImageFormat::PpmBinary,
+            ImageFormat::WebpLossless,
+        ] {
+            let bytes = canvas.encode(format).unwrap();
+            let decoded = Canvas::decode(&bytes, format).unwrap();
+
+            assert_eq!(decoded.width(), 3);
+            assert_eq!(decoded.height(), 2);
+            for y in 0..2 {
+                for x in 0..3 {
+                    assert_eq!(
+                        decoded.pixel_at(x, y).to_vec(),
+                        canvas.pixel_at(x, y).to_vec()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decode_invalid_data() {
+        assert!(Canvas::decode(b"not an image", ImageFormat::Png).is_err());
+        assert!(Canvas::decode(b"not an image", ImageFormat::WebpLossless).is_err());
+    }
+
+    #[test]
+    fn decode_unsupported_format() {
+        let canvas = Canvas::new(2, 2);
+        let bytes = canvas.encode(ImageFormat::Pfm).unwrap();
+        let error = Canvas::decode(&bytes, ImageFormat::Pfm).err().unwrap();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
 }
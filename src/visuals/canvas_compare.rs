@@ -0,0 +1,289 @@
+use std::io::{Error, ErrorKind};
+
+use super::{Canvas, Color};
+
+// side length of the square windows ssim is computed over
+const SSIM_WINDOW_SIZE: usize = 8;
+// stabilizing constants from the ssim paper, for a dynamic range of 1.0
+const SSIM_C1: f64 = 0.01 * 0.01;
+const SSIM_C2: f64 = 0.03 * 0.03;
+
+// rec. 709 luma weights
+const LUMA_WEIGHTS: [f64; 3] = [0.2126, 0.7152, 0.0722];
+
+impl Canvas {
+    // mean squared error over all color channels. Colors are clamped to
+    // [0, 1] first so the comparison matches what ends up in an image file
+    pub fn mse(&self, other: &Canvas) -> Result<f64, Error> {
+        self.check_same_size(other)?;
+
+        let mut squared_error = 0.0;
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let (a, b) = (self.pixel_at(x, y), other.pixel_at(x, y));
+                for (a, b) in clamped_channels(a).iter().zip(clamped_channels(b)) {
+                    squared_error += (a - b).powi(2);
+                }
+            }
+        }
+
+        Ok(squared_error / (self.width() * self.height() * 3) as f64)
+    }
+
+    // peak signal to noise ratio in decibels, infinite for identical canvases
+    pub fn psnr(&self, other: &Canvas) -> Result<f64, Error> {
+        let mse = self.mse(other)?;
+        if mse == 0.0 {
+            return Ok(f64::INFINITY);
+        }
+
+        // peak value is 1.0, so 10 * log10(1.0^2 / mse)
+        Ok(-10.0 * mse.log10())
+    }
+
+    // mean structural similarity of the luma of both canvases, 1.0 when
+    // they are identical
+    pub fn ssim(&self, other: &Canvas) -> Result<f64, Error> {
+        self.check_same_size(other)?;
+
+        let mut total = 0.0;
+        let mut windows = 0;
+        for window_y in (0..self.height()).step_by(SSIM_WINDOW_SIZE) {
+            for window_x in (0..self.width()).step_by(SSIM_WINDOW_SIZE) {
+                let window_width = SSIM_WINDOW_SIZE.min(self.width() - window_x);
+                let window_height = SSIM_WINDOW_SIZE.min(self.height() - window_y);
+
+                let mut a_values = Vec::with_capacity(window_width * window_height);
+                let mut b_values = Vec::with_capacity(window_width * window_height);
+                for y in window_y..(window_y + window_height) {
+                    for x in window_x..(window_x + window_width) {
+                        a_values.push(luma(self.pixel_at(x, y)));
+                        b_values.push(luma(other.pixel_at(x, y)));
+                    }
+                }
+
+                total += window_ssim(&a_values, &b_values);
+                windows += 1;
+            }
+        }
+
+        Ok(total / windows as f64)
+    }
+
+    // true when the canvases are the same size and no color channel
+    // differs by more than the tolerance
+    pub fn approx_equal(&self, other: &Canvas, tolerance: f64) -> bool {
+        if self.check_same_size(other).is_err() {
+            return false;
+        }
+
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if pixel_difference(self.pixel_at(x, y), other.pixel_at(x, y)) > tolerance {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    // heat map of where the canvases differ. Matching pixels are black and
+    // differing ones go from blue for small differences through red to
+    // yellow for the largest ones
+    pub fn diff_canvas(&self, other: &Canvas) -> Result<Canvas, Error> {
+        self.check_same_size(other)?;
+
+        let mut diff = Canvas::new(self.width(), self.height());
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let difference = pixel_difference(self.pixel_at(x, y), other.pixel_at(x, y));
+                diff.write_pixel(x, y, heat_color(difference));
+            }
+        }
+
+        Ok(diff)
+    }
+
+    fn check_same_size(&self, other: &Canvas) -> Result<(), Error> {
+        if self.width() != other.width() || self.height() != other.height() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "cannot compare a {}x{} canvas with a {}x{} canvas",
+                    self.width(),
+                    self.height(),
+                    other.width(),
+                    other.height()
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn clamped_channels(color: Color) -> [f64; 3] {
+    [
+        color.0.clamp(0.0, 1.0),
+        color.1.clamp(0.0, 1.0),
+        color.2.clamp(0.0, 1.0),
+    ]
+}
+
+fn luma(color: Color) -> f64 {
+    let channels = clamped_channels(color);
+    channels[0] * LUMA_WEIGHTS[0] + channels[1] * LUMA_WEIGHTS[1] + channels[2] * LUMA_WEIGHTS[2]
+}
+
+// largest difference of any color channel
+fn pixel_difference(a: Color, b: Color) -> f64 {
+    clamped_channels(a)
+        .iter()
+        .zip(clamped_channels(b))
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max)
+}
+
+fn window_ssim(a_values: &[f64], b_values: &[f64]) -> f64 {
+    let count = a_values.len() as f64;
+    let a_mean = a_values.iter().sum::<f64>() / count;
+    let b_mean = b_values.iter().sum::<f64>() / count;
+
+    let mut a_variance = 0.0;
+    let mut b_variance = 0.0;
+    let mut covariance = 0.0;
+    for (a, b) in a_values.iter().zip(b_values) {
+        a_variance += (a - a_mean).powi(2);
+        b_variance += (b - b_mean).powi(2);
+        covariance += (a - a_mean) * (b - b_mean);
+    }
+    a_variance /= count;
+    b_variance /= count;
+    covariance /= count;
+
+    ((2.0 * a_mean * b_mean + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+        / ((a_mean.powi(2) + b_mean.powi(2) + SSIM_C1) * (a_variance + b_variance + SSIM_C2))
+}
+
+fn heat_color(difference: f64) -> Color {
+    if difference <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let difference = difference.min(1.0);
+    if difference < 0.5 {
+        // blue to red
+        let fraction = difference / 0.5;
+        return Color::new(fraction, 0.0, 1.0 - fraction);
+    }
+
+    // red to yellow
+    let fraction = (difference - 0.5) / 0.5;
+    Color::new(1.0, fraction, 0.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::float_equal;
+
+    fn gradient_canvas() -> Canvas {
+        let mut canvas = Canvas::new(16, 12);
+        for y in 0..12 {
+            for x in 0..16 {
+                let value = (x + y) as f64 / 26.0;
+                canvas.write_pixel(x, y, Color::new(value, 1.0 - value, 0.5));
+            }
+        }
+
+        canvas
+    }
+
+    #[test]
+    fn identical_canvases() {
+        let canvas = gradient_canvas();
+
+        assert_eq!(canvas.mse(&canvas).unwrap(), 0.0);
+        assert_eq!(canvas.psnr(&canvas).unwrap(), f64::INFINITY);
+        assert!(float_equal(canvas.ssim(&canvas).unwrap(), 1.0));
+        assert!(canvas.approx_equal(&canvas, 0.0));
+    }
+
+    #[test]
+    fn mse_and_psnr() {
+        let canvas_a = Canvas::new(2, 2);
+        let mut canvas_b = Canvas::new(2, 2);
+        canvas_b.write_pixel(0, 0, Color::new(1.0, 1.0, 1.0));
+
+        // one of four pixels is fully off in every channel
+        assert!(float_equal(canvas_a.mse(&canvas_b).unwrap(), 0.25));
+        assert!(float_equal(
+            canvas_a.psnr(&canvas_b).unwrap(),
+            -10.0 * 0.25_f64.log10()
+        ));
+    }
+
+    #[test]
+    fn mse_clamps_colors() {
+        let mut canvas_a = Canvas::new(1, 1);
+        let mut canvas_b = Canvas::new(1, 1);
+        canvas_a.write_pixel(0, 0, Color::new(5.0, -1.0, 1.0));
+        canvas_b.write_pixel(0, 0, Color::new(1.0, 0.0, 2.0));
+
+        assert_eq!(canvas_a.mse(&canvas_b).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn ssim_drops_with_noise() {
+        let canvas = gradient_canvas();
+        let mut noisy = gradient_canvas();
+        for y in (0..12).step_by(2) {
+            for x in (0..16).step_by(3) {
+                noisy.write_pixel(x, y, Color::new(1.0, 0.0, 0.0));
+            }
+        }
+
+        let ssim = canvas.ssim(&noisy).unwrap();
+        assert!(ssim < 0.95);
+        assert!(ssim > 0.0);
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let canvas_a = Canvas::new(2, 2);
+        let canvas_b = Canvas::new(3, 2);
+
+        assert_eq!(
+            canvas_a.mse(&canvas_b).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert!(canvas_a.psnr(&canvas_b).is_err());
+        assert!(canvas_a.ssim(&canvas_b).is_err());
+        assert!(canvas_a.diff_canvas(&canvas_b).is_err());
+        assert!(!canvas_a.approx_equal(&canvas_b, 1.0));
+    }
+
+    #[test]
+    fn approx_equal_tolerance() {
+        let canvas_a = Canvas::new(2, 2);
+        let mut canvas_b = Canvas::new(2, 2);
+        canvas_b.write_pixel(1, 1, Color::new(0.0, 0.01, 0.0));
+
+        assert!(canvas_a.approx_equal(&canvas_b, 0.02));
+        assert!(!canvas_a.approx_equal(&canvas_b, 0.005));
+    }
+
+    #[test]
+    fn diff_canvas() {
+        let canvas_a = Canvas::new(3, 1);
+        let mut canvas_b = Canvas::new(3, 1);
+        canvas_b.write_pixel(1, 0, Color::new(0.25, 0.0, 0.0));
+        canvas_b.write_pixel(2, 0, Color::new(1.0, 1.0, 1.0));
+        let diff = canvas_a.diff_canvas(&canvas_b).unwrap();
+
+        assert_eq!(diff.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(diff.pixel_at(1, 0), Color::new(0.5, 0.0, 0.5));
+        assert_eq!(diff.pixel_at(2, 0), Color::new(1.0, 1.0, 0.0));
+    }
+}
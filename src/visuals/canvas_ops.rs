@@ -0,0 +1,262 @@
+use std::io::{Error, ErrorKind};
+
+use super::{Canvas, Color};
+
+#[derive(Clone, Copy)]
+pub enum ResizeFilter {
+    Nearest,
+    Bilinear,
+}
+
+impl Canvas {
+    // new canvas with every pixel run through the given function
+    pub fn map<F>(&self, func: F) -> Self
+    where
+        F: Fn(Color) -> Color,
+    {
+        let mut canvas = Self::new(self.width(), self.height());
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                canvas.write_pixel(x, y, func(self.pixel_at(x, y)));
+            }
+        }
+
+        canvas
+    }
+
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Result<Self, Error> {
+        // subtracting, as adding to a huge offset could overflow
+        if width == 0
+            || height == 0
+            || x > self.width()
+            || width > self.width() - x
+            || y > self.height()
+            || height > self.height() - y
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "cannot crop {}x{} at ({}, {}) out of a {}x{} canvas",
+                    width,
+                    height,
+                    x,
+                    y,
+                    self.width(),
+                    self.height()
+                ),
+            ));
+        }
+
+        let mut canvas = Self::new(width, height);
+        for crop_y in 0..height {
+            for crop_x in 0..width {
+                canvas.write_pixel(crop_x, crop_y, self.pixel_at(x + crop_x, y + crop_y));
+            }
+        }
+
+        Ok(canvas)
+    }
+
+    pub fn resize(&self, width: usize, height: usize, filter: ResizeFilter) -> Result<Self, Error> {
+        if width == 0 || height == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("cannot resize a canvas to {}x{}", width, height),
+            ));
+        }
+
+        let mut canvas = Self::new(width, height);
+        let x_scale = self.width() as f64 / width as f64;
+        let y_scale = self.height() as f64 / height as f64;
+
+        for y in 0..height {
+            for x in 0..width {
+                // sample at the pixel's center
+                let source_x = (x as f64 + 0.5) * x_scale;
+                let source_y = (y as f64 + 0.5) * y_scale;
+
+                let color = match filter {
+                    ResizeFilter::Nearest => self.pixel_at(
+                        (source_x as usize).min(self.width() - 1),
+                        (source_y as usize).min(self.height() - 1),
+                    ),
+                    ResizeFilter::Bilinear => self.bilinear_sample(source_x - 0.5, source_y - 0.5),
+                };
+                canvas.write_pixel(x, y, color);
+            }
+        }
+
+        Ok(canvas)
+    }
+
+    // x and y are in pixel space, with whole numbers at the pixel centers
+    fn bilinear_sample(&self, x: f64, y: f64) -> Color {
+        let x = x.clamp(0.0, (self.width() - 1) as f64);
+        let y = y.clamp(0.0, (self.height() - 1) as f64);
+
+        let left = x.floor() as usize;
+        let top = y.floor() as usize;
+        let right = (left + 1).min(self.width() - 1);
+        let bottom = (top + 1).min(self.height() - 1);
+        let x_fraction = x - left as f64;
+        let y_fraction = y - top as f64;
+
+        let top_color =
+            self.pixel_at(left, top) * (1.0 - x_fraction) + self.pixel_at(right, top) * x_fraction;
+        let bottom_color = self.pixel_at(left, bottom) * (1.0 - x_fraction)
+            + self.pixel_at(right, bottom) * x_fraction;
+
+        top_color * (1.0 - y_fraction) + bottom_color * y_fraction
+    }
+
+    // copies the other canvas in with its top left corner at (x, y).
+    // anything hanging off the edge of this canvas is dropped
+    pub fn paste(&mut self, other: &Canvas, x: usize, y: usize) {
+        // only the part of other that lands on this canvas
+        let width = other.width().min(self.width().saturating_sub(x));
+        let height = other.height().min(self.height().saturating_sub(y));
+        for other_y in 0..height {
+            for other_x in 0..width {
+                self.write_pixel(x + other_x, y + other_y, other.pixel_at(other_x, other_y));
+            }
+        }
+    }
+
+    pub fn flip_horizontal(&self) -> Self {
+        let mut canvas = Self::new(self.width(), self.height());
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                canvas.write_pixel(self.width() - 1 - x, y, self.pixel_at(x, y));
+            }
+        }
+
+        canvas
+    }
+
+    pub fn flip_vertical(&self) -> Self {
+        let mut canvas = Self::new(self.width(), self.height());
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                canvas.write_pixel(x, self.height() - 1 - y, self.pixel_at(x, y));
+            }
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 3x2 canvas where each pixel has a distinct color
+    fn numbered_canvas() -> Canvas {
+        let mut canvas = Canvas::new(3, 2);
+        for y in 0..2 {
+            for x in 0..3 {
+                canvas.write_pixel(x, y, Color::new(x as f64, y as f64, 0.0));
+            }
+        }
+
+        canvas
+    }
+
+    #[test]
+    fn map() {
+        let canvas = numbered_canvas().map(|color| color * 2.0);
+
+        assert_eq!(canvas.pixel_at(2, 1), Color::new(4.0, 2.0, 0.0));
+        assert_eq!(canvas.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn crop() {
+        let canvas = numbered_canvas().crop(1, 1, 2, 1).unwrap();
+
+        assert_eq!(canvas.width(), 2);
+        assert_eq!(canvas.height(), 1);
+        assert_eq!(canvas.pixel_at(0, 0), Color::new(1.0, 1.0, 0.0));
+        assert_eq!(canvas.pixel_at(1, 0), Color::new(2.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn crop_out_of_bounds() {
+        let canvas = numbered_canvas();
+
+        assert!(canvas.crop(2, 0, 2, 1).is_err());
+        assert!(canvas.crop(0, 0, 0, 1).is_err());
+        assert!(canvas.crop(0, 0, 3, 2).is_ok());
+        assert!(canvas.crop(usize::MAX, 0, 2, 1).is_err());
+        assert!(canvas.crop(1, usize::MAX, 1, 1).is_err());
+        assert!(canvas.crop(1, 0, usize::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn resize_nearest() {
+        let canvas = numbered_canvas()
+            .resize(6, 4, ResizeFilter::Nearest)
+            .unwrap();
+
+        assert_eq!(canvas.width(), 6);
+        assert_eq!(canvas.height(), 4);
+        assert_eq!(canvas.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(canvas.pixel_at(1, 1), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(canvas.pixel_at(5, 3), Color::new(2.0, 1.0, 0.0));
+        assert_eq!(canvas.pixel_at(3, 2), Color::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn resize_bilinear() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(1, 0, Color::new(1.0, 1.0, 1.0));
+        let resized = canvas.resize(4, 1, ResizeFilter::Bilinear).unwrap();
+
+        assert_eq!(resized.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(resized.pixel_at(1, 0), Color::new(0.25, 0.25, 0.25));
+        assert_eq!(resized.pixel_at(2, 0), Color::new(0.75, 0.75, 0.75));
+        assert_eq!(resized.pixel_at(3, 0), Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn resize_to_nothing() {
+        let canvas = numbered_canvas();
+
+        assert!(canvas.resize(0, 4, ResizeFilter::Nearest).is_err());
+        assert!(canvas.resize(6, 0, ResizeFilter::Bilinear).is_err());
+    }
+
+    #[test]
+    fn paste() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas.paste(&numbered_canvas(), 2, 3);
+
+        assert_eq!(canvas.pixel_at(2, 3), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(canvas.pixel_at(3, 3), Color::new(1.0, 0.0, 0.0));
+        // everything else was clipped or left alone
+        assert_eq!(canvas.pixel_at(1, 3), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(canvas.pixel_at(3, 2), Color::new(0.0, 0.0, 0.0));
+
+        // pasting far off the edge changes nothing
+        let mut canvas = Canvas::new(4, 4);
+        canvas.paste(&numbered_canvas(), usize::MAX, 0);
+        canvas.paste(&numbered_canvas(), 0, usize::MAX);
+        canvas.paste(&numbered_canvas(), usize::MAX, usize::MAX);
+        assert!(canvas.approx_equal(&Canvas::new(4, 4), 0.0));
+    }
+
+    #[test]
+    fn flip_horizontal() {
+        let canvas = numbered_canvas().flip_horizontal();
+
+        assert_eq!(canvas.pixel_at(0, 0), Color::new(2.0, 0.0, 0.0));
+        assert_eq!(canvas.pixel_at(2, 1), Color::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn flip_vertical() {
+        let canvas = numbered_canvas().flip_vertical();
+
+        assert_eq!(canvas.pixel_at(0, 0), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(canvas.pixel_at(2, 1), Color::new(2.0, 0.0, 0.0));
+    }
+}
@@ -1,10 +1,12 @@
 use std::ops;
 
+use serde::{Deserialize, Serialize};
+
 const MIN_PX_VAL: f64 = 0.0;
 const MAX_PX_VAL: f64 = 255.0;
 
 #[cfg_attr(test, derive(Debug, PartialEq))]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Color(pub f64, pub f64, pub f64);
 
 impl Color {
@@ -15,6 +17,20 @@ impl Color {
     pub fn to_vec(self) -> [u8; 3] {
         normalize_color_for_pixel([self.0, self.1, self.2])
     }
+
+    // inverse of to_vec
+    pub fn from_vec(values: [u8; 3]) -> Self {
+        Color(
+            values[0] as f64 / MAX_PX_VAL,
+            values[1] as f64 / MAX_PX_VAL,
+            values[2] as f64 / MAX_PX_VAL,
+        )
+    }
+
+    // unclamped color values, for formats that can store high dynamic range
+    pub fn to_f32_vec(self) -> [f32; 3] {
+        [self.0 as f32, self.1 as f32, self.2 as f32]
+    }
 }
 
 fn normalize_color_for_pixel(values: [f64; 3]) -> [u8; 3] {
@@ -0,0 +1,83 @@
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+// quality the canvas has always been saved with when writing .webp files
+pub const DEFAULT_WEBP_QUALITY: f32 = 65.0;
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Clone, Copy)]
+pub enum ImageFormat {
+    Png,
+    // plain text (P3) ppm
+    PpmPlain,
+    // binary (P6) ppm
+    PpmBinary,
+    WebpLossless,
+    // lossy webp, quality is between 0 and 100
+    Webp(f32),
+    // the formats below keep the full floating point color values
+    // instead of clamping them to 8 bits
+    RadianceHdr,
+    OpenExr,
+    Pfm,
+}
+
+impl ImageFormat {
+    // picks the format based on the file extension of the path.
+    // .ppm files are written as binary ppm and .webp files are written
+    // lossy with the default quality, use an explicit format for the others
+    pub fn from_path(path: &str) -> Result<Self, Error> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("png") => Ok(Self::Png),
+            Some("ppm") => Ok(Self::PpmBinary),
+            Some("webp") => Ok(Self::Webp(DEFAULT_WEBP_QUALITY)),
+            Some("hdr") => Ok(Self::RadianceHdr),
+            Some("exr") => Ok(Self::OpenExr),
+            Some("pfm") => Ok(Self::Pfm),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unsupported image extension for {}", path),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_path() {
+        assert_eq!(ImageFormat::from_path("out.png").unwrap(), ImageFormat::Png);
+        assert_eq!(
+            ImageFormat::from_path("./dir/out.PPM").unwrap(),
+            ImageFormat::PpmBinary
+        );
+        assert_eq!(
+            ImageFormat::from_path("out.webp").unwrap(),
+            ImageFormat::Webp(DEFAULT_WEBP_QUALITY)
+        );
+        assert_eq!(
+            ImageFormat::from_path("out.hdr").unwrap(),
+            ImageFormat::RadianceHdr
+        );
+        assert_eq!(
+            ImageFormat::from_path("out.exr").unwrap(),
+            ImageFormat::OpenExr
+        );
+        assert_eq!(ImageFormat::from_path("out.pfm").unwrap(), ImageFormat::Pfm);
+    }
+
+    #[test]
+    fn from_path_unsupported() {
+        let error = ImageFormat::from_path("out.bmp").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        assert!(ImageFormat::from_path("out").is_err());
+    }
+}
@@ -1,9 +1,14 @@
 #[cfg(test)]
 use std::cmp::PartialEq;
 
-use super::{Color, Pattern};
+use serde::{Deserialize, Serialize};
+
+use super::{Color, Microfacet, Pattern};
 use crate::base_types::Point;
 
+#[derive(Serialize, Deserialize)]
+// fields left out when loading keep their default values
+#[serde(default)]
 pub struct Material {
     pub color: Color,
     pub patterns: Vec<Box<dyn Pattern>>,
@@ -14,9 +19,19 @@ pub struct Material {
     pub reflective: f64,
     pub transparency: f64,
     pub refractive_index: f64,
+    // light given off by the surface itself, scaled by the strength. It
+    // makes the object glow and, for shapes with a surface that can be
+    // sampled, lights up the objects around it
+    pub emission: Color,
+    pub emission_strength: f64,
+    // shades the surface with the physically based microfacet model in
+    // place of Phong, left out for Phong
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub microfacet: Option<Microfacet>,
 }
 
 impl Material {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         color: Color,
         patterns: Vec<Box<dyn Pattern>>,
@@ -38,9 +53,21 @@ impl Material {
             reflective,
             transparency,
             refractive_index,
+            emission: Color::new(0.0, 0.0, 0.0),
+            emission_strength: 1.0,
+            microfacet: None,
         }
     }
 
+    pub fn emitted(&self) -> Color {
+        self.emission * self.emission_strength
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        let emitted = self.emitted();
+        emitted.0 > 0.0 || emitted.1 > 0.0 || emitted.2 > 0.0
+    }
+
     pub fn color_at(&self, point: Point) -> Color {
         let num_of_patterns = self.patterns.len();
         if num_of_patterns > 0 {
@@ -98,6 +125,9 @@ impl PartialEq for Material {
             && self.diffuse == other.diffuse
             && self.specular == other.specular
             && self.shininess == other.shininess
+            && self.emission == other.emission
+            && self.emission_strength == other.emission_strength
+            && self.microfacet == other.microfacet
     }
 }
 
@@ -118,6 +148,9 @@ impl Clone for Material {
             reflective: self.reflective,
             transparency: self.transparency,
             refractive_index: self.refractive_index,
+            emission: self.emission,
+            emission_strength: self.emission_strength,
+            microfacet: self.microfacet,
         }
     }
 }
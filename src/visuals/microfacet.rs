@@ -0,0 +1,229 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use super::Color;
+use crate::base_types::Vector;
+
+// how much light non-metals reflect straight back, about 4%
+const DIELECTRIC_REFLECTANCE: f64 = 0.04;
+// perfectly smooth surfaces reflect light in just one direction, which a
+// point light never lines up with
+const MIN_ROUGHNESS: f64 = 0.02;
+
+// a physically based surface made of tiny mirrors (Cook-Torrance), with the
+// GGX spread of mirror directions, Smith shadowing between them and Schlick
+// Fresnel, over a diffuse base that gets what the shine leaves. It uses
+// the material's color as the base color in place of the Phong ambient,
+// diffuse, specular and shininess, and takes the same roughness and
+// metallic values as other PBR tools
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Microfacet {
+    // from mirror smooth at 0 to chalky at 1
+    pub roughness: f64,
+    // 0 for plastics, wood, stone and so on, which have a diffuse base
+    // color under a white shine. 1 for metals, which only reflect, tinted
+    // by the base color
+    pub metallic: f64,
+}
+
+impl Microfacet {
+    pub fn new(roughness: f64, metallic: f64) -> Self {
+        Self {
+            roughness,
+            metallic,
+        }
+    }
+
+    // light reflected toward the eye for each unit of light arriving from
+    // the light direction, before the cosine of the angle it arrives at.
+    // All unit vectors pointing away from the surface. Like the Phong
+    // diffuse term it is pi times the BRDF, so a white rough dielectric is
+    // about as bright as a Phong surface with a diffuse of 1
+    pub fn reflectance(
+        &self,
+        base_color: Color,
+        normal: Vector,
+        eye: Vector,
+        light: Vector,
+    ) -> Color {
+        let normal_dot_light = normal.dot(light);
+        let normal_dot_eye = normal.dot(eye);
+        if normal_dot_light <= 0.0 || normal_dot_eye <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let metallic = self.metallic.clamp(0.0, 1.0);
+        let roughness = self.roughness.clamp(MIN_ROUGHNESS, 1.0);
+        let alpha = roughness * roughness;
+        let halfway = (eye + light).normalize();
+
+        let distribution = ggx(normal.dot(halfway), alpha);
+        let geometry =
+            smith_shadowing(normal_dot_light, alpha) * smith_shadowing(normal_dot_eye, alpha);
+        let dielectric = Color::new(
+            DIELECTRIC_REFLECTANCE,
+            DIELECTRIC_REFLECTANCE,
+            DIELECTRIC_REFLECTANCE,
+        );
+        let reflectance = dielectric * (1.0 - metallic) + base_color * metallic;
+        let fresnel = schlick(reflectance, eye.dot(halfway));
+        // light that gets under the surface, is scattered and gets out
+        // again, less the more of it the shine reflects (Ashikhmin-Shirley)
+        let diffuse_share = 28.0 / 23.0
+            * (1.0 - (1.0 - normal_dot_light / 2.0).powi(5))
+            * (1.0 - (1.0 - normal_dot_eye / 2.0).powi(5));
+
+        let white = Color::new(1.0, 1.0, 1.0);
+        let diffuse = (white - reflectance) * base_color * (1.0 - metallic) * diffuse_share;
+        let specular =
+            fresnel * (PI * distribution * geometry / (4.0 * normal_dot_light * normal_dot_eye));
+
+        diffuse + specular
+    }
+}
+
+impl Default for Microfacet {
+    fn default() -> Self {
+        Self::new(0.5, 0.0)
+    }
+}
+
+// share of the tiny mirrors lined up with the halfway vector
+fn ggx(normal_dot_halfway: f64, alpha: f64) -> f64 {
+    let alpha_squared = alpha * alpha;
+    let denominator = normal_dot_halfway * normal_dot_halfway * (alpha_squared - 1.0) + 1.0;
+
+    alpha_squared / (PI * denominator * denominator)
+}
+
+// share of the tiny mirrors seen from a direction that are not hidden
+// behind others
+fn smith_shadowing(normal_dot_direction: f64, alpha: f64) -> f64 {
+    let alpha_squared = alpha * alpha;
+    let cosine_squared = normal_dot_direction * normal_dot_direction;
+
+    2.0 * normal_dot_direction
+        / (normal_dot_direction + (alpha_squared + (1.0 - alpha_squared) * cosine_squared).sqrt())
+}
+
+// surfaces reflect more at grazing angles, reaching white edge on
+fn schlick(reflectance: Color, cosine: f64) -> Color {
+    let white = Color::new(1.0, 1.0, 1.0);
+    reflectance + (white - reflectance) * (1.0 - cosine.clamp(0.0, 1.0)).powi(5)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::cosine_weighted_direction;
+
+    const WHITE: Color = Color(1.0, 1.0, 1.0);
+
+    fn up() -> Vector {
+        Vector::new_vector(0.0, 1.0, 0.0)
+    }
+
+    #[test]
+    fn nothing_from_behind() {
+        let surface = Microfacet::default();
+        let below = Vector::new_vector(0.0, -1.0, 0.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+
+        assert_eq!(surface.reflectance(WHITE, up(), up(), below), black);
+        assert_eq!(surface.reflectance(WHITE, up(), below, up()), black);
+    }
+
+    #[test]
+    fn dielectrics() {
+        // head on, 4% is reflected as shine and the rest is diffuse
+        let surface = Microfacet::new(1.0, 0.0);
+        let base = Color::new(0.5, 0.2, 1.0);
+        let shine = 0.04 / 4.0;
+        let diffuse = 0.96 * 28.0 / 23.0 * (1.0 - 0.5_f64.powi(5)).powi(2);
+
+        assert!(surface
+            .reflectance(base, up(), up(), up())
+            .equal(base * diffuse + Color::new(shine, shine, shine)));
+    }
+
+    #[test]
+    fn metals_are_tinted_by_the_base_color() {
+        // head on, the tiny mirrors facing up reflect the base color, and
+        // at roughness 0.5 their share is 1 / (pi * 0.25^2)
+        let surface = Microfacet::new(0.5, 1.0);
+        let base = Color::new(1.0, 0.5, 0.2);
+
+        assert!(surface
+            .reflectance(base, up(), up(), up())
+            .equal(base * 4.0));
+    }
+
+    #[test]
+    fn grazing_light_shines_more() {
+        let surface = Microfacet::new(0.3, 0.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let head_on = surface.reflectance(black, up(), up(), up());
+        let eye = Vector::new_vector(0.0, 0.2, 1.0).normalize();
+        let light = Vector::new_vector(0.0, 0.2, -1.0).normalize();
+        let grazing = surface.reflectance(black, up(), eye, light);
+
+        assert!(grazing.0 > head_on.0);
+    }
+
+    #[test]
+    fn roughness_spreads_the_highlight() {
+        let smooth = Microfacet::new(0.2, 1.0);
+        let rough = Microfacet::new(0.8, 1.0);
+        let eye = Vector::new_vector(0.0, 1.0, 1.0).normalize();
+        let mirrored = Vector::new_vector(0.0, 1.0, -1.0).normalize();
+        let off = Vector::new_vector(1.0, 1.0, 0.0).normalize();
+
+        let smooth_peak = smooth.reflectance(WHITE, up(), eye, mirrored);
+        let rough_peak = rough.reflectance(WHITE, up(), eye, mirrored);
+        assert!(smooth_peak.0 > rough_peak.0);
+        assert!(
+            rough.reflectance(WHITE, up(), eye, off).0
+                > smooth.reflectance(WHITE, up(), eye, off).0
+        );
+    }
+
+    #[test]
+    fn no_more_light_out_than_in() {
+        // averaging over cosine weighted light directions gives the share
+        // of light from all around that is reflected toward the eye
+        let count = 200;
+        for surface in [Microfacet::new(1.0, 1.0), Microfacet::new(0.3, 0.0)] {
+            for eye in [up(), Vector::new_vector(1.0, 0.3, 0.0).normalize()] {
+                let mut total = 0.0;
+                for i in 0..count {
+                    for j in 0..count {
+                        let light = cosine_weighted_direction(
+                            up(),
+                            (i as f64 + 0.5) / count as f64,
+                            (j as f64 + 0.5) / count as f64,
+                        );
+                        total += surface.reflectance(WHITE, up(), eye, light).0;
+                    }
+                }
+                let reflected = total / (count * count) as f64;
+
+                assert!(reflected < 1.0 && reflected > 0.25, "{}", reflected);
+            }
+        }
+    }
+
+    #[test]
+    fn serialize() {
+        assert_eq!(
+            serde_json::to_string(&Microfacet::new(0.25, 1.0)).unwrap(),
+            r#"{"roughness":0.25,"metallic":1.0}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Microfacet>(r#"{"metallic":1.0}"#).unwrap(),
+            Microfacet::new(0.5, 1.0)
+        );
+    }
+}
@@ -1,9 +1,22 @@
+mod animated_image;
 mod canvas;
+mod canvas_compare;
+mod canvas_ops;
 mod color;
+mod image_format;
 mod material;
+mod microfacet;
 mod patterns;
+mod stereo_layout;
+mod tone_mapping;
 
+pub use animated_image::*;
 pub use canvas::*;
+pub use canvas_ops::*;
 pub use color::*;
+pub use image_format::*;
 pub use material::*;
+pub use microfacet::*;
 pub use patterns::*;
+pub use stereo_layout::*;
+pub use tone_mapping::*;
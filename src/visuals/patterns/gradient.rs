@@ -1,11 +1,11 @@
-use super::{Color, Pattern};
+use super::{clone_nested_patterns, Color, Pattern, PatternData};
 use crate::base_types::Point;
-use crate::matrices::Matrix;
+use crate::matrices::{Matrix, Transform};
 
 pub struct GradientPattern {
     color_a: Color,
     color_b: Color,
-    transform: Matrix,
+    transform: Transform,
     nested_patterns: Option<[Box<dyn Pattern>; 2]>,
 }
 
@@ -18,7 +18,7 @@ impl GradientPattern {
         Self {
             color_a,
             color_b,
-            transform: Matrix::new_identity(),
+            transform: Transform::default(),
             nested_patterns,
         }
     }
@@ -58,11 +58,20 @@ impl Pattern for GradientPattern {
     }
 
     fn set_transform(&mut self, transform: Matrix) {
-        self.transform = transform;
+        self.transform = Transform::new(transform);
     }
 
-    fn get_transform(&self) -> Matrix {
-        self.transform.clone()
+    fn get_transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn to_data(&self) -> PatternData {
+        PatternData::Gradient {
+            color_a: self.color_a,
+            color_b: self.color_b,
+            transform: self.transform.matrix().clone(),
+            nested_patterns: clone_nested_patterns(&self.nested_patterns),
+        }
     }
 }
 
@@ -1,6 +1,11 @@
+use std::io::{Error, ErrorKind};
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use super::Color;
 use crate::base_types::Point;
-use crate::matrices::Matrix;
+use crate::matrices::{Matrix, Transform};
 
 mod checker;
 mod gradient;
@@ -14,9 +19,130 @@ pub use perturb::*;
 pub use ring::*;
 pub use striped::*;
 
-pub trait Pattern {
+// patterns and objects are shared between the render threads
+pub trait Pattern: Send + Sync {
     fn color_at(&self, point: Point) -> Color;
     fn clone_pattern(&self) -> Box<dyn Pattern>;
-    fn get_transform(&self) -> Matrix;
+    fn get_transform(&self) -> &Transform;
     fn set_transform(&mut self, transform: Matrix);
+    fn to_data(&self) -> PatternData;
+}
+
+// every kind of pattern in a form that can be saved and loaded, a perturb
+// pattern keeps its transform on the pattern it wraps
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PatternData {
+    Stripes {
+        color_a: Color,
+        color_b: Color,
+        #[serde(default = "Matrix::new_identity")]
+        transform: Matrix,
+        #[serde(default)]
+        nested_patterns: Option<[Box<dyn Pattern>; 2]>,
+    },
+    Gradient {
+        color_a: Color,
+        color_b: Color,
+        #[serde(default = "Matrix::new_identity")]
+        transform: Matrix,
+        #[serde(default)]
+        nested_patterns: Option<[Box<dyn Pattern>; 2]>,
+    },
+    Rings {
+        color_a: Color,
+        color_b: Color,
+        #[serde(default = "Matrix::new_identity")]
+        transform: Matrix,
+        #[serde(default)]
+        nested_patterns: Option<[Box<dyn Pattern>; 2]>,
+    },
+    Checkers {
+        color_a: Color,
+        color_b: Color,
+        #[serde(default = "Matrix::new_identity")]
+        transform: Matrix,
+        #[serde(default)]
+        nested_patterns: Option<[Box<dyn Pattern>; 2]>,
+    },
+    Perturb {
+        pattern: Box<dyn Pattern>,
+    },
+}
+
+impl PatternData {
+    // fails for transforms that can not be inverted, as points could never
+    // be moved into pattern space
+    pub fn into_pattern(self) -> Result<Box<dyn Pattern>, Error> {
+        let (mut pattern, transform): (Box<dyn Pattern>, Matrix) = match self {
+            Self::Stripes {
+                color_a,
+                color_b,
+                transform,
+                nested_patterns,
+            } => (
+                Box::new(StripePattern::new(color_a, color_b, nested_patterns)),
+                transform,
+            ),
+            Self::Gradient {
+                color_a,
+                color_b,
+                transform,
+                nested_patterns,
+            } => (
+                Box::new(GradientPattern::new(color_a, color_b, nested_patterns)),
+                transform,
+            ),
+            Self::Rings {
+                color_a,
+                color_b,
+                transform,
+                nested_patterns,
+            } => (
+                Box::new(RingPattern::new(color_a, color_b, nested_patterns)),
+                transform,
+            ),
+            Self::Checkers {
+                color_a,
+                color_b,
+                transform,
+                nested_patterns,
+            } => (
+                Box::new(CheckerPattern::new(color_a, color_b, nested_patterns)),
+                transform,
+            ),
+            Self::Perturb { pattern } => return Ok(Box::new(PerturbPattern::new(pattern))),
+        };
+
+        if transform.inverse().is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "pattern transform can not be inverted",
+            ));
+        }
+        pattern.set_transform(transform);
+        Ok(pattern)
+    }
+}
+
+impl Serialize for dyn Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_data().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Pattern> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PatternData::deserialize(deserializer)?
+            .into_pattern()
+            .map_err(D::Error::custom)
+    }
+}
+
+fn clone_nested_patterns(
+    nested_patterns: &Option<[Box<dyn Pattern>; 2]>,
+) -> Option<[Box<dyn Pattern>; 2]> {
+    nested_patterns
+        .as_ref()
+        .map(|patterns| [patterns[0].clone_pattern(), patterns[1].clone_pattern()])
 }
@@ -1,6 +1,6 @@
-use super::{Color, Pattern};
+use super::{Color, Pattern, PatternData};
 use crate::base_types::Point;
-use crate::matrices::Matrix;
+use crate::matrices::{Matrix, Transform};
 
 pub struct PerturbPattern {
     pattern: Box<dyn Pattern>,
@@ -34,7 +34,13 @@ impl Pattern for PerturbPattern {
         self.pattern.set_transform(transform);
     }
 
-    fn get_transform(&self) -> Matrix {
+    fn get_transform(&self) -> &Transform {
         self.pattern.get_transform()
     }
+
+    fn to_data(&self) -> PatternData {
+        PatternData::Perturb {
+            pattern: self.pattern.clone_pattern(),
+        }
+    }
 }
@@ -1,11 +1,11 @@
-use super::{Color, Pattern};
+use super::{clone_nested_patterns, Color, Pattern, PatternData};
 use crate::base_types::Point;
-use crate::matrices::Matrix;
+use crate::matrices::{Matrix, Transform};
 
 pub struct RingPattern {
     color_a: Color,
     color_b: Color,
-    transform: Matrix,
+    transform: Transform,
     nested_patterns: Option<[Box<dyn Pattern>; 2]>,
 }
 
@@ -18,7 +18,7 @@ impl RingPattern {
         Self {
             color_a,
             color_b,
-            transform: Matrix::new_identity(),
+            transform: Transform::default(),
             nested_patterns,
         }
     }
@@ -27,15 +27,15 @@ impl RingPattern {
 impl Pattern for RingPattern {
     fn color_at(&self, point: Point) -> Color {
         if ((point.x.powi(2) + point.z.powi(2)).sqrt().floor() as isize) % 2 == 0 {
-            if self.nested_patterns.is_some() {
-                return self.nested_patterns.as_ref().unwrap()[0].color_at(point);
+            if let Some(nested_patterns) = self.nested_patterns.as_ref() {
+                return nested_patterns[0].color_at(point);
             }
 
             return self.color_a;
         }
 
-        if self.nested_patterns.is_some() {
-            return self.nested_patterns.as_ref().unwrap()[1].color_at(point);
+        if let Some(nested_patterns) = self.nested_patterns.as_ref() {
+            return nested_patterns[1].color_at(point);
         }
 
         self.color_b
@@ -59,11 +59,20 @@ impl Pattern for RingPattern {
     }
 
     fn set_transform(&mut self, transform: Matrix) {
-        self.transform = transform;
+        self.transform = Transform::new(transform);
     }
 
-    fn get_transform(&self) -> Matrix {
-        self.transform.clone()
+    fn get_transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn to_data(&self) -> PatternData {
+        PatternData::Rings {
+            color_a: self.color_a,
+            color_b: self.color_b,
+            transform: self.transform.matrix().clone(),
+            nested_patterns: clone_nested_patterns(&self.nested_patterns),
+        }
     }
 }
 
@@ -0,0 +1,120 @@
+use std::io::{Error, ErrorKind};
+
+use super::{Canvas, Color};
+
+// how the left and right eye images of a stereo pair are put together into
+// a single image
+#[derive(Clone, Copy)]
+pub enum StereoLayout {
+    // left eye on the left, for headsets and parallel viewing
+    SideBySide,
+    // left eye on top
+    OverUnder,
+    // red from the left eye and green and blue from the right, for red/cyan
+    // glasses
+    Anaglyph,
+}
+
+impl StereoLayout {
+    pub fn compose(&self, left: &Canvas, right: &Canvas) -> Result<Canvas, Error> {
+        if left.width() != right.width() || left.height() != right.height() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "cannot pair a {}x{} left eye with a {}x{} right eye",
+                    left.width(),
+                    left.height(),
+                    right.width(),
+                    right.height()
+                ),
+            ));
+        }
+
+        let (width, height) = (left.width(), left.height());
+        let canvas = match self {
+            Self::SideBySide => {
+                let mut canvas = Canvas::new(width * 2, height);
+                canvas.paste(left, 0, 0);
+                canvas.paste(right, width, 0);
+                canvas
+            }
+            Self::OverUnder => {
+                let mut canvas = Canvas::new(width, height * 2);
+                canvas.paste(left, 0, 0);
+                canvas.paste(right, 0, height);
+                canvas
+            }
+            Self::Anaglyph => {
+                let mut canvas = Canvas::new(width, height);
+                for y in 0..height {
+                    for x in 0..width {
+                        let (left_color, right_color) = (left.pixel_at(x, y), right.pixel_at(x, y));
+                        canvas.write_pixel(
+                            x,
+                            y,
+                            Color::new(left_color.0, right_color.1, right_color.2),
+                        );
+                    }
+                }
+                canvas
+            }
+        };
+
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn filled(width: usize, height: usize, color: Color) -> Canvas {
+        Canvas::new(width, height).map(|_| color)
+    }
+
+    #[test]
+    fn side_by_side() {
+        let left = filled(3, 2, Color::new(1.0, 0.0, 0.0));
+        let right = filled(3, 2, Color::new(0.0, 0.0, 1.0));
+        let pair = StereoLayout::SideBySide.compose(&left, &right).unwrap();
+
+        assert_eq!((pair.width(), pair.height()), (6, 2));
+        assert!(pair.pixel_at(2, 1).equal(Color::new(1.0, 0.0, 0.0)));
+        assert!(pair.pixel_at(3, 0).equal(Color::new(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn over_under() {
+        let left = filled(3, 2, Color::new(1.0, 0.0, 0.0));
+        let right = filled(3, 2, Color::new(0.0, 0.0, 1.0));
+        let pair = StereoLayout::OverUnder.compose(&left, &right).unwrap();
+
+        assert_eq!((pair.width(), pair.height()), (3, 4));
+        assert!(pair.pixel_at(2, 1).equal(Color::new(1.0, 0.0, 0.0)));
+        assert!(pair.pixel_at(0, 2).equal(Color::new(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn anaglyph() {
+        let left = filled(3, 2, Color::new(0.2, 0.4, 0.6));
+        let right = filled(3, 2, Color::new(0.7, 0.5, 0.3));
+        let pair = StereoLayout::Anaglyph.compose(&left, &right).unwrap();
+
+        assert_eq!((pair.width(), pair.height()), (3, 2));
+        assert!(pair.pixel_at(1, 1).equal(Color::new(0.2, 0.5, 0.3)));
+    }
+
+    #[test]
+    fn different_sizes() {
+        let error = StereoLayout::SideBySide
+            .compose(&Canvas::new(3, 2), &Canvas::new(2, 3))
+            .err()
+            .unwrap();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            error.to_string(),
+            "cannot pair a 3x2 left eye with a 2x3 right eye"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use super::Color;
+
+// constants for the uncharted 2 filmic curve, see
+// http://filmicworlds.com/blog/filmic-tonemapping-operators/
+const FILMIC_SHOULDER_STRENGTH: f64 = 0.15;
+const FILMIC_LINEAR_STRENGTH: f64 = 0.50;
+const FILMIC_LINEAR_ANGLE: f64 = 0.10;
+const FILMIC_TOE_STRENGTH: f64 = 0.20;
+const FILMIC_TOE_NUMERATOR: f64 = 0.02;
+const FILMIC_TOE_DENOMINATOR: f64 = 0.30;
+const FILMIC_WHITE_POINT: f64 = 11.2;
+const FILMIC_EXPOSURE_BIAS: f64 = 2.0;
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Clone, Copy)]
+pub enum ToneMapping {
+    // values are left alone and get clamped when written out
+    Clamp,
+    Reinhard,
+    // Krzysztof Narkowicz's fit of the ACES filmic curve
+    Aces,
+    // John Hable's uncharted 2 curve
+    Filmic,
+}
+
+impl ToneMapping {
+    // maps a linear value in [0, inf) into [0, 1]
+    pub fn map(self, value: f64) -> f64 {
+        let value = value.max(0.0);
+
+        match self {
+            Self::Clamp => value.min(1.0),
+            Self::Reinhard => value / (1.0 + value),
+            Self::Aces => {
+                let mapped =
+                    (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14);
+                mapped.clamp(0.0, 1.0)
+            }
+            Self::Filmic => {
+                let mapped =
+                    filmic_curve(value * FILMIC_EXPOSURE_BIAS) / filmic_curve(FILMIC_WHITE_POINT);
+                mapped.clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+fn filmic_curve(value: f64) -> f64 {
+    let a = FILMIC_SHOULDER_STRENGTH;
+    let b = FILMIC_LINEAR_STRENGTH;
+    let c = FILMIC_LINEAR_ANGLE;
+    let d = FILMIC_TOE_STRENGTH;
+    let e = FILMIC_TOE_NUMERATOR;
+    let f = FILMIC_TOE_DENOMINATOR;
+
+    ((value * (a * value + c * b) + d * e) / (value * (a * value + b) + d * f)) - e / f
+}
+
+// encodes a linear value in [0, 1] with the srgb transfer function
+pub fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        return value * 12.92;
+    }
+
+    1.055 * value.powf(1.0 / 2.4) - 0.055
+}
+
+// the stage colors go through between the render and the 8 bit output
+#[derive(Clone, Copy)]
+pub struct OutputTransform {
+    // in stops, i.e., each +1.0 doubles the brightness
+    pub exposure: f64,
+    pub tone_mapping: ToneMapping,
+    pub srgb: bool,
+}
+
+impl OutputTransform {
+    pub fn new(exposure: f64, tone_mapping: ToneMapping, srgb: bool) -> Self {
+        Self {
+            exposure,
+            tone_mapping,
+            srgb,
+        }
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        let exposed = color * 2.0_f64.powf(self.exposure);
+
+        Color::new(
+            self.apply_to_channel(exposed.0),
+            self.apply_to_channel(exposed.1),
+            self.apply_to_channel(exposed.2),
+        )
+    }
+
+    fn apply_to_channel(&self, value: f64) -> f64 {
+        let mapped = self.tone_mapping.map(value);
+        if self.srgb {
+            return linear_to_srgb(mapped);
+        }
+
+        mapped
+    }
+}
+
+impl Default for OutputTransform {
+    // same output as writing the linear colors straight out
+    fn default() -> Self {
+        Self::new(0.0, ToneMapping::Clamp, false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::float_equal;
+
+    #[test]
+    fn clamp() {
+        assert_eq!(ToneMapping::Clamp.map(-0.5), 0.0);
+        assert_eq!(ToneMapping::Clamp.map(0.25), 0.25);
+        assert_eq!(ToneMapping::Clamp.map(7.0), 1.0);
+    }
+
+    #[test]
+    fn reinhard() {
+        assert_eq!(ToneMapping::Reinhard.map(0.0), 0.0);
+        assert_eq!(ToneMapping::Reinhard.map(1.0), 0.5);
+        assert_eq!(ToneMapping::Reinhard.map(3.0), 0.75);
+    }
+
+    #[test]
+    fn aces() {
+        assert_eq!(ToneMapping::Aces.map(0.0), 0.0);
+        assert!(float_equal(ToneMapping::Aces.map(1.0), 0.80380));
+        assert_eq!(ToneMapping::Aces.map(1000.0), 1.0);
+    }
+
+    #[test]
+    fn filmic() {
+        assert!(float_equal(ToneMapping::Filmic.map(0.0), 0.0));
+        assert!(float_equal(
+            ToneMapping::Filmic.map(FILMIC_WHITE_POINT / FILMIC_EXPOSURE_BIAS),
+            1.0
+        ));
+    }
+
+    #[test]
+    fn operators_are_monotonic() {
+        for tone_mapping in [
+            ToneMapping::Clamp,
+            ToneMapping::Reinhard,
+            ToneMapping::Aces,
+            ToneMapping::Filmic,
+        ] {
+            let mut previous = tone_mapping.map(0.0);
+            for i in 1..100 {
+                let mapped = tone_mapping.map(i as f64 * 0.1);
+                assert!(mapped >= previous);
+                assert!(mapped <= 1.0);
+                previous = mapped;
+            }
+        }
+    }
+
+    #[test]
+    fn srgb_encoding() {
+        assert_eq!(linear_to_srgb(0.0), 0.0);
+        assert!(float_equal(linear_to_srgb(0.002), 0.02584));
+        assert!(float_equal(linear_to_srgb(0.5), 0.73536));
+        assert!(float_equal(linear_to_srgb(1.0), 1.0));
+    }
+
+    #[test]
+    fn default_transform_leaves_color() {
+        let color = Color::new(0.25, 0.5, 0.75);
+
+        assert_eq!(OutputTransform::default().apply(color), color);
+    }
+
+    #[test]
+    fn exposure() {
+        let transform = OutputTransform::new(1.0, ToneMapping::Clamp, false);
+
+        assert_eq!(
+            transform.apply(Color::new(0.25, 0.5, 0.75)),
+            Color::new(0.5, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn reinhard_with_srgb() {
+        let transform = OutputTransform::new(0.0, ToneMapping::Reinhard, true);
+
+        assert!(transform
+            .apply(Color::new(1.0, 1.0, 1.0))
+            .equal(Color::new(0.73536, 0.73536, 0.73536)));
+    }
+}
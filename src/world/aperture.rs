@@ -0,0 +1,130 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+// shape of the lens opening, out of focus highlights are blurred into it
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApertureShape {
+    #[default]
+    Disk,
+    // a regular polygon with this many blades, pointing up
+    Polygon(usize),
+}
+
+impl ApertureShape {
+    // maps u and v, both in [0, 1), to a point spread evenly over the
+    // aperture, with a radius of 1
+    pub fn sample(&self, u: f64, v: f64) -> (f64, f64) {
+        match *self {
+            Self::Polygon(blades) if blades >= 3 => sample_polygon(blades, u, v),
+            // fewer than 3 blades can not make a polygon
+            _ => sample_disk(u, v),
+        }
+    }
+}
+
+// concentric mapping, squares around the center of the unit square go to
+// circles so evenly spread samples stay evenly spread
+fn sample_disk(u: f64, v: f64) -> (f64, f64) {
+    let a = 2.0 * u - 1.0;
+    let b = 2.0 * v - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let (radius, angle) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+
+    (radius * angle.cos(), radius * angle.sin())
+}
+
+// the polygon is split into triangles around the center, u picks the
+// triangle and what is left of it along with v picks the point in it
+fn sample_polygon(blades: usize, u: f64, v: f64) -> (f64, f64) {
+    let scaled = u * blades as f64;
+    let triangle = (scaled as usize).min(blades - 1);
+    let along = (scaled - triangle as f64).sqrt();
+
+    let corner = |index: usize| {
+        let angle = PI / 2.0 + 2.0 * PI * index as f64 / blades as f64;
+        (angle.cos(), angle.sin())
+    };
+    let (x1, y1) = corner(triangle);
+    let (x2, y2) = corner(triangle + 1);
+
+    (
+        along * ((1.0 - v) * x1 + v * x2),
+        along * ((1.0 - v) * y1 + v * y2),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::float_equal;
+
+    fn samples(shape: ApertureShape) -> Vec<(f64, f64)> {
+        (0..20)
+            .flat_map(|i| (0..20).map(move |j| (i as f64 / 20.0, j as f64 / 20.0)))
+            .map(|(u, v)| shape.sample(u, v))
+            .collect()
+    }
+
+    #[test]
+    fn disk() {
+        let disk = ApertureShape::Disk;
+        let points = samples(disk);
+
+        assert_eq!(disk.sample(0.5, 0.5), (0.0, 0.0));
+        assert!(points.iter().all(|(x, y)| x.hypot(*y) <= 1.0));
+        // the edge of the square lands on the edge of the disk
+        let (x, y) = disk.sample(0.0, 0.3);
+        assert!(float_equal(x.hypot(y), 1.0));
+        // evenly spread, so a quarter of them are within half the radius
+        let inner = points.iter().filter(|(x, y)| x.hypot(*y) < 0.5).count();
+        assert!((80..=120).contains(&inner));
+    }
+
+    #[test]
+    fn polygon() {
+        let hexagon = ApertureShape::Polygon(6);
+        let points = samples(hexagon);
+
+        let (x, y) = hexagon.sample(1.0 / 6.0 - 1e-12, 0.0);
+        assert!(float_equal(x.hypot(y), 1.0));
+        // inside the hexagon, which is no wider than its inner circle
+        // across the flat sides on the left and right
+        let inner_radius = (PI / 6.0).cos();
+        assert!(points
+            .iter()
+            .all(|(x, y)| x.hypot(*y) <= 1.0 && x.abs() <= inner_radius + 1e-9));
+        // every triangle gets the same share
+        let right = points.iter().filter(|(x, _)| *x > 0.0).count();
+        assert!((180..=220).contains(&right));
+    }
+
+    #[test]
+    fn too_few_blades() {
+        assert_eq!(
+            ApertureShape::Polygon(2).sample(0.2, 0.7),
+            ApertureShape::Disk.sample(0.2, 0.7)
+        );
+    }
+
+    #[test]
+    fn serialize() {
+        assert_eq!(
+            serde_json::to_string(&ApertureShape::Polygon(5)).unwrap(),
+            r#"{"polygon":5}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ApertureShape>(r#""disk""#).unwrap(),
+            ApertureShape::Disk
+        );
+    }
+}
@@ -1,16 +1,44 @@
+use serde::{Deserialize, Serialize};
+
 use crate::base_types::{
     combine_intersections, hit_index, prepare_computations, Intersection, IntersectionDetails,
     Point, Ray,
 };
 use crate::matrices::Matrix;
 use crate::visuals::{Color, Material};
-use crate::world::{Light, Object, Plane, Sphere};
+use crate::world::{
+    halton, AmbientOcclusion, Integrator, Light, Object, Plane, Sphere, SurfacePoint,
+};
 
-const MAX_REFLECT_DEPTH: usize = 5;
+pub const DEFAULT_MAX_REFLECT_DEPTH: usize = 5;
+pub const DEFAULT_EMISSIVE_SAMPLES: usize = 16;
 
+#[derive(Serialize, Deserialize)]
 pub struct World {
     pub objects: Vec<Box<dyn Object>>,
     pub lights: Vec<Light>,
+    // how many times a ray can bounce off reflective surfaces
+    #[serde(default = "default_max_reflect_depth")]
+    pub max_reflect_depth: usize,
+    // points picked on each emissive object to light a Whitted hit, more
+    // give smoother soft shadows
+    #[serde(default = "default_emissive_samples")]
+    pub emissive_samples: usize,
+    // when set, the Whitted integrator scales the ambient term by how open
+    // each hit is, left out when it is not
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+    // left out when it is the default Whitted integrator
+    #[serde(default, skip_serializing_if = "Integrator::is_whitted")]
+    pub integrator: Integrator,
+}
+
+fn default_max_reflect_depth() -> usize {
+    DEFAULT_MAX_REFLECT_DEPTH
+}
+
+fn default_emissive_samples() -> usize {
+    DEFAULT_EMISSIVE_SAMPLES
 }
 
 impl World {
@@ -21,6 +49,10 @@ impl World {
                 Point::new_point(-10.0, 10.0, -10.0),
                 Color::new(1.0, 1.0, 1.0),
             )],
+            max_reflect_depth: DEFAULT_MAX_REFLECT_DEPTH,
+            emissive_samples: DEFAULT_EMISSIVE_SAMPLES,
+            ambient_occlusion: None,
+            integrator: Integrator::Whitted,
         }
     }
 
@@ -28,7 +60,7 @@ impl World {
         self.objects.push(object);
     }
 
-    pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+    pub fn intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
         let mut intersections = Vec::new();
 
         for object in self.objects.iter() {
@@ -45,7 +77,7 @@ impl World {
         if !intersections.is_empty() {
             if let Some(hit_index) = hit_index(&intersections) {
                 let comps = prepare_computations(hit_index, ray, intersections);
-                let reflect_depth = reflect_depth.unwrap_or(MAX_REFLECT_DEPTH);
+                let reflect_depth = reflect_depth.unwrap_or(self.max_reflect_depth);
 
                 return self.shade_hit(comps, reflect_depth);
             }
@@ -55,28 +87,80 @@ impl World {
     }
 
     fn shade_hit(&self, intersection_details: IntersectionDetails, reflect_depth: usize) -> Color {
+        let ambient_visibility = match &self.ambient_occlusion {
+            Some(settings) => {
+                1.0 - self.occlusion(
+                    intersection_details.over_point,
+                    intersection_details.surface_normal,
+                    intersection_details.time,
+                    settings,
+                )
+            }
+            None => 1.0,
+        };
+
+        let surface = SurfacePoint::new(
+            intersection_details.point,
+            intersection_details.eye_normal,
+            intersection_details.surface_normal,
+        )
+        .with_time(intersection_details.time)
+        .with_ambient_visibility(ambient_visibility);
+
         let mut color = Color::new(0.0, 0.0, 0.0);
         for (i, light) in self.lights.iter().enumerate() {
             color = color
                 + light.lighting(
                     intersection_details.intersection.object,
-                    intersection_details.point,
-                    intersection_details.eye_normal,
-                    intersection_details.surface_normal,
-                    self.is_shadowed(intersection_details.over_point, i),
+                    &surface,
+                    self.is_shadowed(
+                        intersection_details.over_point,
+                        i,
+                        intersection_details.time,
+                    ),
+                );
+        }
+
+        color
+            + self.emissive_color(&intersection_details)
+            + self.reflected_color(&intersection_details, reflect_depth)
+    }
+
+    // the glow of an emissive hit and the light it gets from emissive
+    // objects, averaged over evenly spread points on them
+    fn emissive_color(&self, intersection_details: &IntersectionDetails) -> Color {
+        let object = intersection_details.intersection.object;
+        let material = object.get_material();
+        let mut color = material.emitted();
+        if !self.has_emitters() || self.emissive_samples == 0 {
+            return color;
+        }
+
+        let albedo = object.color_at(intersection_details.point, intersection_details.time)
+            * material.diffuse;
+        let mut light = Color::new(0.0, 0.0, 0.0);
+        for sample in 0..self.emissive_samples {
+            light = light
+                + self.emissive_light(
+                    intersection_details,
+                    albedo,
+                    halton(sample + 1, 2),
+                    halton(sample + 1, 3),
                 );
         }
+        color = color + light / self.emissive_samples as f64;
 
-        color + self.reflected_color(&intersection_details, reflect_depth)
+        color
     }
 
-    fn is_shadowed(&self, point: Point, light_index: usize) -> bool {
+    pub(super) fn is_shadowed(&self, point: Point, light_index: usize, time: f64) -> bool {
         let point_to_light = point - self.lights[light_index].position;
         let distance = point_to_light.magnitude();
-        let ray_to_light = Ray {
-            origin: self.lights[light_index].position,
-            direction: point_to_light.normalize(),
-        };
+        let ray_to_light = Ray::new(
+            self.lights[light_index].position,
+            point_to_light.normalize(),
+        )
+        .with_time(time);
 
         let intersections = self.intersect(ray_to_light);
         if !intersections.is_empty() {
@@ -107,7 +191,8 @@ impl World {
         let reflect_ray = Ray::new(
             intersection_details.over_point,
             intersection_details.reflect_vector,
-        );
+        )
+        .with_time(intersection_details.time);
 
         self.color_at(reflect_ray, Some(reflect_depth - 1)) * reflective_value
     }
@@ -147,6 +232,10 @@ impl Default for World {
         Self {
             objects: vec![Box::new(sphere_1), Box::new(sphere_2), Box::new(plane)],
             lights,
+            max_reflect_depth: DEFAULT_MAX_REFLECT_DEPTH,
+            emissive_samples: DEFAULT_EMISSIVE_SAMPLES,
+            ambient_occlusion: None,
+            integrator: Integrator::Whitted,
         }
     }
 }
@@ -165,7 +254,7 @@ mod test {
         );
         let intersections = world.intersect(ray);
 
-        assert!(intersections.len() > 0);
+        assert!(!intersections.is_empty());
 
         assert_eq!(intersections[0].distance, 4.0);
         assert_eq!(intersections[1].distance, 4.5);
@@ -185,17 +274,19 @@ mod test {
         let intersection_details = prepare_computations(0, ray, intersections);
 
         assert!(world
-            .shade_hit(intersection_details, MAX_REFLECT_DEPTH)
+            .shade_hit(intersection_details, DEFAULT_MAX_REFLECT_DEPTH)
             .equal(Color::new(0.38066, 0.47583, 0.2855)));
     }
 
     #[test]
     fn shading_an_inside_intersection() {
-        let mut world = World::default();
-        world.lights = vec![Light::new(
-            Point::new_point(0.0, 0.25, 0.0),
-            Color::new(1.0, 1.0, 1.0),
-        )];
+        let world = World {
+            lights: vec![Light::new(
+                Point::new_point(0.0, 0.25, 0.0),
+                Color::new(1.0, 1.0, 1.0),
+            )],
+            ..World::default()
+        };
         let ray = Ray::new(
             Point::new_point(0.0, 0.0, 0.0),
             Vector::new_vector(0.0, 0.0, 1.0),
@@ -207,17 +298,19 @@ mod test {
         assert!(intersection_details.is_inside);
 
         assert!(world
-            .shade_hit(intersection_details, MAX_REFLECT_DEPTH)
+            .shade_hit(intersection_details, DEFAULT_MAX_REFLECT_DEPTH)
             .equal(Color::new(0.90498, 0.90498, 0.90498)));
     }
 
     #[test]
     fn shading_in_shadow() {
-        let mut world = World::default();
-        world.lights = vec![Light::new(
-            Point::new_point(0.0, 0.0, -10.0),
-            Color::new(1.0, 1.0, 1.0),
-        )];
+        let mut world = World {
+            lights: vec![Light::new(
+                Point::new_point(0.0, 0.0, -10.0),
+                Color::new(1.0, 1.0, 1.0),
+            )],
+            ..World::default()
+        };
         world.objects[1].set_transform(Matrix::translation(0.0, 0.0, 10.0));
         let ray = Ray::new(
             Point::new_point(0.0, 0.0, 5.0),
@@ -227,7 +320,7 @@ mod test {
         let intersections = world.objects[1].intersect(ray).unwrap();
         let intersection_details = prepare_computations(0, ray, intersections);
         assert!(world
-            .shade_hit(intersection_details, MAX_REFLECT_DEPTH)
+            .shade_hit(intersection_details, DEFAULT_MAX_REFLECT_DEPTH)
             .equal(Color::new(0.1, 0.1, 0.1)));
     }
 
@@ -259,15 +352,8 @@ mod test {
     fn color_ray_hits_behind() {
         let mut world = World::default();
 
-        let mut material_1 = world.objects[1].get_material();
-        material_1.ambient = 1.0;
-        world.objects[1].set_material(material_1);
-
-        let mut material_0 = world.objects[0].get_material();
-        material_0.ambient = 1.0;
-        world.objects[0].set_material(material_0);
-
-        world.objects[0].get_material().ambient = 1.0;
+        world.objects[1].get_material_mut().ambient = 1.0;
+        world.objects[0].get_material_mut().ambient = 1.0;
         let ray = Ray::new(
             Point::new_point(0.0, 0.0, 0.75),
             Vector::new_vector(0.0, 0.0, -1.0),
@@ -282,28 +368,28 @@ mod test {
     fn is_shadowed_false() {
         let world = World::default();
 
-        assert!(!world.is_shadowed(Point::new_point(0.0, 10.0, 0.0), 0));
+        assert!(!world.is_shadowed(Point::new_point(0.0, 10.0, 0.0), 0, 0.0));
     }
 
     #[test]
     fn behind_sphere_is_shadowed() {
         let world = World::default();
 
-        assert!(world.is_shadowed(Point::new_point(10.0, -10.0, 10.0), 0));
+        assert!(world.is_shadowed(Point::new_point(10.0, -10.0, 10.0), 0, 0.0));
     }
 
     #[test]
     fn sphere_not_between_light_and_point() {
         let world = World::default();
 
-        assert!(!world.is_shadowed(Point::new_point(-20.0, 20.0, -20.0), 0));
+        assert!(!world.is_shadowed(Point::new_point(-20.0, 20.0, -20.0), 0, 0.0));
     }
 
     #[test]
     fn point_in_between_object_and_light() {
         let world = World::default();
 
-        assert!(!world.is_shadowed(Point::new_point(-2.0, 2.0, -2.0), 0));
+        assert!(!world.is_shadowed(Point::new_point(-2.0, 2.0, -2.0), 0, 0.0));
     }
 
     #[test]
@@ -314,15 +400,13 @@ mod test {
             Vector::new_vector(0.0, 0.0, 1.0),
         );
 
-        let mut object_material = world.objects[1].get_material();
-        object_material.ambient = 1.0;
-        world.objects[1].set_material(object_material);
+        world.objects[1].get_material_mut().ambient = 1.0;
 
         let intersections = world.intersect(ray);
         let intersection_details = prepare_computations(0, ray, intersections);
 
         assert_eq!(
-            world.reflected_color(&intersection_details, MAX_REFLECT_DEPTH),
+            world.reflected_color(&intersection_details, DEFAULT_MAX_REFLECT_DEPTH),
             Color::new(0.0, 0.0, 0.0)
         );
     }
@@ -343,7 +427,7 @@ mod test {
         let intersection_details = prepare_computations(0, ray, intersections);
 
         assert!(world
-            .reflected_color(&intersection_details, MAX_REFLECT_DEPTH)
+            .reflected_color(&intersection_details, DEFAULT_MAX_REFLECT_DEPTH)
             .equal(Color::new(0.1903323, 0.2379154, 0.14274924)));
     }
 
@@ -362,7 +446,7 @@ mod test {
         let intersection_details = prepare_computations(0, ray, vec![intersection]);
 
         assert!(world
-            .shade_hit(intersection_details, MAX_REFLECT_DEPTH)
+            .shade_hit(intersection_details, DEFAULT_MAX_REFLECT_DEPTH)
             .equal(Color::new(0.8767577, 0.9243407, 0.8291746)));
     }
 
@@ -407,7 +491,7 @@ mod test {
             Vector::new_vector(0.0, 1.0, 0.0),
         );
 
+        // would overflow the stack if reflections recursed forever
         world.color_at(ray, None);
-        assert!(true);
     }
 }
@@ -1,7 +1,12 @@
-use super::World;
-use crate::base_types::{Point, Ray};
-use crate::matrices::Matrix;
-use crate::visuals::Canvas;
+use std::thread;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{halton, AmbientOcclusion, ApertureShape, Integrator, Projection, Random, World};
+use crate::base_types::{Point, Ray, Vector};
+use crate::matrices::{Matrix, Transform};
+use crate::visuals::{Canvas, Color};
 
 pub struct Camera {
     hsize: usize,
@@ -10,84 +15,376 @@ pub struct Camera {
     half_height: f64,
     pixel_size: f64,
     pub field_of_view: f64,
-    pub transform: Matrix,
+    projection: Projection,
+    transform: Transform,
+    // rays averaged per pixel, spread across the pixel to smooth edges
+    pub samples_per_pixel: usize,
+    // rows of the image are split between this many threads
+    pub threads: usize,
+    // part of the motion of moving objects the shutter is open for, 0 is
+    // where they start and 1 where they end. Each sample of a pixel sees
+    // them at a different time in between, which blurs them
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    // radius of the lens. At 0 the camera is a pinhole and everything is in
+    // focus, otherwise only things focal_distance away are sharp and the
+    // samples of a pixel are spread over the aperture
+    pub aperture: f64,
+    pub focal_distance: f64,
+    pub aperture_shape: ApertureShape,
 }
 
 impl Camera {
     pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
-        // basically split full triangle view into right triangle
-        // by cutting field of view in half. Tangent is equal to
-        // the width of half the canvas, since the camera is one unit away
-        let half_view = (field_of_view / 2.0).tan();
-        let aspect = (hsize as f64) / (vsize as f64);
+        let mut camera = Self {
+            hsize,
+            vsize,
+            half_width: 0.0,
+            half_height: 0.0,
+            pixel_size: 0.0,
+            field_of_view,
+            projection: Projection::Perspective,
+            transform: Transform::default(),
+            samples_per_pixel: 1,
+            threads: 1,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            aperture: 0.0,
+            focal_distance: 1.0,
+            aperture_shape: ApertureShape::Disk,
+        };
+        camera.update_view();
 
-        let half_width: f64;
-        let half_height: f64;
+        camera
+    }
+
+    // the same camera rendering at another size
+    pub fn resized(&self, hsize: usize, vsize: usize) -> Self {
+        let mut camera = Self::new(hsize, vsize, self.field_of_view);
+        camera.set_projection(self.projection);
+        camera.transform = self.transform.clone();
+        camera.samples_per_pixel = self.samples_per_pixel;
+        camera.threads = self.threads;
+        camera.shutter_open = self.shutter_open;
+        camera.shutter_close = self.shutter_close;
+        camera.aperture = self.aperture;
+        camera.focal_distance = self.focal_distance;
+        camera.aperture_shape = self.aperture_shape;
+
+        camera
+    }
+
+    fn update_view(&mut self) {
+        // for a perspective camera, basically split full triangle view into
+        // right triangle by cutting field of view in half. Tangent is equal
+        // to the width of half the canvas, since the camera is one unit away
+        let half_view = self.projection.half_view(self.field_of_view);
+        let aspect = (self.hsize as f64) / (self.vsize as f64);
 
         if aspect >= 1.0 {
-            half_width = half_view;
-            half_height = half_view / aspect;
+            self.half_width = half_view;
+            self.half_height = half_view / aspect;
         } else {
-            half_width = half_view * aspect;
-            half_height = half_view;
+            self.half_width = half_view * aspect;
+            self.half_height = half_view;
         }
 
-        let pixel_size = (half_width * 2.0) / (hsize as f64);
+        self.pixel_size = (self.half_width * 2.0) / (self.hsize as f64);
+    }
 
-        Self {
-            hsize,
-            vsize,
-            half_width,
-            half_height,
-            pixel_size,
-            field_of_view,
-            transform: Matrix::new_identity(),
-        }
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.update_view();
+    }
+
+    pub fn get_transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix) {
+        self.transform = Transform::new(transform);
+    }
+
+    // sets the focal distance so the given point is in focus
+    pub fn focus_on(&mut self, point: Point) {
+        let camera_point = self.transform.matrix().coords_mul(point);
+        self.focal_distance = -camera_point.z;
     }
 
     pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
+        self.ray_for_pixel_offset(x, y, 0.5, 0.5)
+    }
+
+    // pixel_x_offset and pixel_y_offset pick where in the pixel the ray
+    // goes through, from 0.0 to 1.0 with 0.5 being the center
+    pub fn ray_for_pixel_offset(
+        &self,
+        x: usize,
+        y: usize,
+        pixel_x_offset: f64,
+        pixel_y_offset: f64,
+    ) -> Ray {
+        self.ray_through_lens(x, y, pixel_x_offset, pixel_y_offset, (0.0, 0.0))
+    }
+
+    // lens is where on the aperture the ray starts, with a radius of 1
+    fn ray_through_lens(
+        &self,
+        x: usize,
+        y: usize,
+        pixel_x_offset: f64,
+        pixel_y_offset: f64,
+        lens: (f64, f64),
+    ) -> Ray {
         // the offset from the edge of the canvas
-        // to the pixel's center
-        let x_offset = (x as f64 + 0.5) * self.pixel_size;
-        let y_offset = (y as f64 + 0.5) * self.pixel_size;
+        // to the sample point in the pixel
+        let x_offset = (x as f64 + pixel_x_offset) * self.pixel_size;
+        let y_offset = (y as f64 + pixel_y_offset) * self.pixel_size;
 
-        // the untransform coordinates of the pixel in world space
+        // the untransform coordinates of the pixel on the screen
         // (camera looks toward -z, so +x is to the left)
-        let world_x = self.half_width - x_offset;
-        let world_y = self.half_height - y_offset;
+        let screen_x = self.half_width - x_offset;
+        let screen_y = self.half_height - y_offset;
+        let (center, toward) = self.projection.camera_ray(
+            screen_x,
+            screen_y,
+            self.field_of_view,
+            (self.half_width, self.half_height),
+        );
 
         let camera_transform_inv = self.transform.inverse().unwrap();
-        let origin = camera_transform_inv.coords_mul(Point::new_point(0.0, 0.0, 0.0));
+        let origin = camera_transform_inv.coords_mul(
+            center + Vector::new_vector(lens.0 * self.aperture, lens.1 * self.aperture, 0.0),
+        );
 
-        // canvas is at z = -1.0
-        let pixel = camera_transform_inv.coords_mul(Point::new_point(world_x, world_y, -1.0));
-        let direction = (pixel - origin).normalize();
+        // rays from anywhere on the lens meet where the ray through the
+        // center of the lens crosses the focal plane
+        let focus = center + toward * self.focal_distance;
+        let direction = (camera_transform_inv.coords_mul(focus) - origin).normalize();
 
-        Ray { origin, direction }
+        Ray::new(origin, direction)
     }
 
     pub fn render(&self, world: &World) -> Canvas {
+        self.render_with(|ray, x, y, sample| match world.integrator {
+            Integrator::Whitted => world.color_at(ray, None),
+            Integrator::PathTracer { seed } => {
+                world.trace_path(ray, &mut Random::for_sample(seed, x, y, sample))
+            }
+        })
+    }
+
+    // a grayscale image of how open each visible point is to ambient light,
+    // white where nothing is near and darker in creases. Misses are white
+    pub fn render_occlusion(&self, world: &World, settings: &AmbientOcclusion) -> Canvas {
+        self.render_with(|ray, _, _, _| {
+            let open = 1.0 - world.occlusion_at(ray, settings);
+            Color::new(open, open, open)
+        })
+    }
+
+    // shade gives the color seen along a ray through pixel x, y for the
+    // given sample
+    fn render_with<F>(&self, shade: F) -> Canvas
+    where
+        F: Fn(Ray, usize, usize, usize) -> Color + Sync,
+    {
+        let shade = &shade;
         let mut image = Canvas::new(self.hsize, self.vsize);
+        let threads = self.threads.clamp(1, self.vsize);
 
-        for y in 0..self.vsize {
-            for x in 0..self.hsize {
-                let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at(ray, None);
+        let rendered_rows: Vec<(usize, Vec<Color>)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|thread_index| {
+                    // interleave the rows so every thread gets a similar mix
+                    // of busy and empty parts of the scene
+                    scope.spawn(move || {
+                        (thread_index..self.vsize)
+                            .step_by(threads)
+                            .map(|y| (y, self.render_row(shade, y)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        for (y, row) in rendered_rows {
+            for (x, color) in row.into_iter().enumerate() {
                 image.write_pixel(x, y, color);
             }
         }
 
         image
     }
+
+    fn render_row<F>(&self, shade: &F, y: usize) -> Vec<Color>
+    where
+        F: Fn(Ray, usize, usize, usize) -> Color,
+    {
+        (0..self.hsize)
+            .map(|x| self.pixel_color(shade, x, y))
+            .collect()
+    }
+
+    fn pixel_color<F>(&self, shade: &F, x: usize, y: usize) -> Color
+    where
+        F: Fn(Ray, usize, usize, usize) -> Color,
+    {
+        let samples = self.samples_per_pixel.max(1);
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        for sample in 1..=samples {
+            color = color + shade(self.sample_ray(x, y, sample), x, y, sample);
+        }
+
+        color / samples as f64
+    }
+
+    // sample counts from 1
+    fn sample_ray(&self, x: usize, y: usize, sample: usize) -> Ray {
+        if self.samples_per_pixel <= 1 {
+            return self.ray_for_pixel(x, y).with_time(self.shutter_time(0.5));
+        }
+
+        // halton sequence spreads the samples evenly over the pixel, the
+        // lens and the shutter interval while keeping renders deterministic
+        let lens = self
+            .aperture_shape
+            .sample(halton(sample, 7), halton(sample, 11));
+        self.ray_through_lens(x, y, halton(sample, 2), halton(sample, 3), lens)
+            .with_time(self.shutter_time(halton(sample, 5)))
+    }
+
+    // fraction goes from 0 when the shutter opens to 1 when it closes
+    fn shutter_time(&self, fraction: f64) -> f64 {
+        self.shutter_open + (self.shutter_close - self.shutter_open) * fraction
+    }
+}
+
+// what a camera is saved as, the threads are left to whoever renders it
+#[derive(Serialize, Deserialize)]
+struct CameraData {
+    width: usize,
+    height: usize,
+    field_of_view: f64,
+    #[serde(default = "Matrix::new_identity")]
+    transform: Matrix,
+    #[serde(default = "default_samples_per_pixel")]
+    samples_per_pixel: usize,
+    // [open, close], left out when there is no motion blur
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shutter: Option<[f64; 2]>,
+    // left out for a pinhole camera
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lens: Option<LensData>,
+    // left out for a perspective camera
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    projection: Option<Projection>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LensData {
+    aperture: f64,
+    focal_distance: f64,
+    #[serde(default)]
+    shape: ApertureShape,
+}
+
+fn default_samples_per_pixel() -> usize {
+    1
+}
+
+impl Serialize for Camera {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CameraData {
+            width: self.hsize,
+            height: self.vsize,
+            field_of_view: self.field_of_view,
+            transform: self.transform.matrix().clone(),
+            samples_per_pixel: self.samples_per_pixel,
+            shutter: (self.shutter_open != 0.0 || self.shutter_close != 0.0)
+                .then_some([self.shutter_open, self.shutter_close]),
+            lens: (self.aperture != 0.0).then_some(LensData {
+                aperture: self.aperture,
+                focal_distance: self.focal_distance,
+                shape: self.aperture_shape,
+            }),
+            projection: (!matches!(self.projection, Projection::Perspective))
+                .then_some(self.projection),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Camera {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = CameraData::deserialize(deserializer)?;
+        if data.width == 0 || data.height == 0 {
+            return Err(D::Error::custom(
+                "camera width and height must be at least 1",
+            ));
+        }
+
+        let mut camera = Camera::new(data.width, data.height, data.field_of_view);
+        if let Some(projection) = data.projection {
+            projection
+                .validate(data.field_of_view)
+                .map_err(D::Error::custom)?;
+            camera.set_projection(projection);
+        }
+        if data.transform.inverse().is_none() {
+            return Err(D::Error::custom("camera transform can not be inverted"));
+        }
+        camera.set_transform(data.transform);
+        camera.samples_per_pixel = data.samples_per_pixel;
+        if let Some([open, close]) = data.shutter {
+            if !(0.0 <= open && open <= close && close <= 1.0) {
+                return Err(D::Error::custom(
+                    "shutter must be [open, close] with 0 <= open <= close <= 1",
+                ));
+            }
+            camera.shutter_open = open;
+            camera.shutter_close = close;
+        }
+        if let Some(lens) = data.lens {
+            if lens.aperture < 0.0 || lens.focal_distance <= 0.0 {
+                return Err(D::Error::custom(
+                    "lens aperture can not be negative and focal distance must be positive",
+                ));
+            }
+            camera.aperture = lens.aperture;
+            camera.focal_distance = lens.focal_distance;
+            camera.aperture_shape = lens.shape;
+        }
+
+        Ok(camera)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{Camera, World};
+    use super::{halton, AmbientOcclusion, ApertureShape, Camera, Projection, World};
     use crate::base_types::{Point, Vector};
     use crate::matrices::Matrix;
     use crate::utils::float_equal;
     use crate::visuals::Color;
+    use crate::world::{Object, Sphere};
     use std::f64::consts::PI;
 
     #[test]
@@ -124,7 +421,8 @@ mod test {
     #[test]
     fn ray_for_pixel_with_transform() {
         let mut camera = Camera::new(201, 101, PI / 2.0);
-        camera.transform = Matrix::rotation_y(PI / 4.0).mul(&Matrix::translation(0.0, -2.0, 5.0));
+        camera
+            .set_transform(Matrix::rotation_y(PI / 4.0).mul(&Matrix::translation(0.0, -2.0, 5.0)));
         let ray = camera.ray_for_pixel(100, 50);
 
         assert_eq!(ray.origin, Point::new_point(0.0, 2.0, -5.0));
@@ -141,12 +439,350 @@ mod test {
         let mut camera = Camera::new(11, 11, PI / 2.0);
         let from = Point::new_point(0.0, 0.0, -5.0);
         let to = Point::new_point(0.0, 0.0, 0.0);
-        let up = Point::new_point(0.0, 1.0, 0.0);
-        camera.transform = Matrix::view_transform(from, to, up);
+        let up = Vector::new_vector(0.0, 1.0, 0.0);
+        camera.set_transform(Matrix::view_transform(from, to, up));
 
         let image = camera.render(&world);
         assert!(image
             .pixel_at(5, 5)
             .equal(Color::new(0.38066, 0.47583, 0.2855)));
     }
+
+    #[test]
+    fn ray_for_pixel_offset_corner() {
+        let camera = Camera::new(201, 101, PI / 2.0);
+        let center = camera.ray_for_pixel_offset(100, 50, 0.5, 0.5);
+        let corner = camera.ray_for_pixel_offset(100, 50, 0.0, 0.0);
+
+        assert_eq!(center, camera.ray_for_pixel(100, 50));
+        assert!(corner.direction.x > center.direction.x);
+        assert!(corner.direction.y > center.direction.y);
+    }
+
+    #[test]
+    fn render_with_threads_matches_single_thread() {
+        let world = World::default();
+        let mut camera = Camera::new(11, 7, PI / 2.0);
+        camera.set_transform(Matrix::view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+        let single_threaded = camera.render(&world);
+
+        camera.threads = 3;
+        let multi_threaded = camera.render(&world);
+
+        assert!(single_threaded.approx_equal(&multi_threaded, 0.0));
+    }
+
+    #[test]
+    fn render_occlusion() {
+        let world = World::default();
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+        camera.set_transform(Matrix::view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+        let settings = AmbientOcclusion::new(32, 2.0);
+        let image = camera.render_occlusion(&world, &settings);
+
+        // the sky is open
+        assert!(image.pixel_at(5, 0).equal(Color::new(1.0, 1.0, 1.0)));
+        // the underside of the sphere is tucked in against the floor, and
+        // the floor darkens toward it
+        let crease = image.pixel_at(5, 6);
+        assert!(crease.0 < 0.5);
+        assert!(image.pixel_at(4, 7).0 < image.pixel_at(0, 10).0);
+        assert!(crease.0 == crease.1 && crease.1 == crease.2);
+
+        camera.threads = 3;
+        assert!(camera
+            .render_occlusion(&world, &settings)
+            .approx_equal(&image, 0.0));
+    }
+
+    #[test]
+    fn render_with_samples_per_pixel() {
+        let world = World::default();
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+        camera.set_transform(Matrix::view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+        camera.samples_per_pixel = 4;
+        let image = camera.render(&world);
+
+        let mut expected = Color::new(0.0, 0.0, 0.0);
+        for sample in 1..=4 {
+            let ray = camera.ray_for_pixel_offset(4, 5, halton(sample, 2), halton(sample, 3));
+            expected = expected + world.color_at(ray, None);
+        }
+
+        assert!(image.pixel_at(4, 5).equal(expected / 4.0));
+        // a single sample through the center gives a different color
+        assert!(!image
+            .pixel_at(4, 5)
+            .equal(world.color_at(camera.ray_for_pixel(4, 5), None)));
+    }
+
+    #[test]
+    fn motion_blur() {
+        let mut world = World::new();
+        let mut sphere = Sphere::default();
+        sphere.set_moving_transform(
+            Matrix::translation(-1.0, 0.0, 0.0),
+            Matrix::translation(1.0, 0.0, 0.0),
+        );
+        world.add_object(Box::new(sphere));
+
+        let mut camera = Camera::new(21, 21, PI / 2.0);
+        camera.set_transform(Matrix::view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+        camera.samples_per_pixel = 16;
+        let still = camera.render(&world);
+
+        camera.shutter_close = 1.0;
+        let blurred = camera.render(&world);
+
+        // with the shutter closed the sphere is only seen where it starts,
+        // on the left of the image since the camera looks down +z
+        let background = Color::new(0.0, 0.0, 0.0);
+        assert!(!still.pixel_at(7, 10).equal(background));
+        assert!(still.pixel_at(12, 10).equal(background));
+        // with the shutter open it is smeared to the right, and covers those
+        // pixels for only part of the samples
+        assert!(!blurred.pixel_at(12, 10).equal(background));
+        assert!(blurred.pixel_at(7, 10).0 < still.pixel_at(7, 10).0);
+    }
+
+    #[test]
+    fn shutter_is_saved() {
+        let mut camera = Camera::new(4, 3, 1.0);
+        camera.shutter_open = 0.25;
+        camera.shutter_close = 0.75;
+        let json = serde_json::to_string(&camera).unwrap();
+        let loaded: Camera = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.shutter_open, 0.25);
+        assert_eq!(loaded.shutter_close, 0.75);
+        // left out when there is no motion blur
+        assert!(!serde_json::to_string(&Camera::new(4, 3, 1.0))
+            .unwrap()
+            .contains("shutter"));
+
+        for shutter in ["[0.5, 0.25]", "[-0.5, 0.5]", "[0.0, 2.0]"] {
+            let json = json.replace("[0.25,0.75]", shutter);
+            assert_ne!(json, serde_json::to_string(&camera).unwrap());
+            let error = serde_json::from_str::<Camera>(&json).err().unwrap();
+            assert!(error.to_string().contains("0 <= open <= close <= 1"));
+        }
+    }
+
+    #[test]
+    fn lens_rays_meet_on_focal_plane() {
+        let mut camera = Camera::new(201, 101, PI / 2.0);
+        camera.aperture = 0.5;
+        camera.focal_distance = 5.0;
+
+        for lens in [(1.0, 0.0), (0.0, -1.0), (-0.6, 0.6)] {
+            let ray = camera.ray_through_lens(100, 50, 0.5, 0.5, lens);
+            let origin = Point::new_point(lens.0 * 0.5, lens.1 * 0.5, 0.0);
+            assert!(ray.origin.equal(origin));
+            // the focal plane is 5 away along the view
+            let distance = 5.0 / -ray.direction.z;
+            assert!(ray
+                .position(distance)
+                .equal(Point::new_point(0.0, 0.0, -5.0)));
+        }
+        // through the center of the lens it is the same as a pinhole
+        let pinhole = Camera::new(201, 101, PI / 2.0).ray_for_pixel(0, 0);
+        let center = camera.ray_through_lens(0, 0, 0.5, 0.5, (0.0, 0.0));
+        assert!(center.direction.equal(pinhole.direction));
+    }
+
+    #[test]
+    fn focus_on() {
+        let mut camera = Camera::new(10, 10, PI / 2.0);
+        camera.set_transform(Matrix::view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+        // only the distance along the view counts
+        camera.focus_on(Point::new_point(3.0, -1.0, 2.0));
+
+        assert!(float_equal(camera.focal_distance, 7.0));
+    }
+
+    #[test]
+    fn depth_of_field() {
+        let world = World::default();
+        let mut camera = Camera::new(21, 21, PI / 4.0);
+        camera.set_transform(Matrix::view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+        camera.samples_per_pixel = 16;
+        let pinhole = camera.render(&world);
+
+        // the edges of the sphere are close to 5 away, so focused there it
+        // looks much like the pinhole render, focused in front they blur
+        camera.aperture = 0.3;
+        camera.focal_distance = 4.8;
+        let focused = camera.render(&world);
+        camera.focal_distance = 2.5;
+        let blurred = camera.render(&world);
+
+        let focused_error = focused.mse(&pinhole).unwrap();
+        let blurred_error = blurred.mse(&pinhole).unwrap();
+        assert!(focused_error > 0.0);
+        assert!(blurred_error > 4.0 * focused_error);
+
+        // bokeh takes the shape of the aperture
+        camera.aperture_shape = ApertureShape::Polygon(5);
+        assert!(camera.render(&world).mse(&blurred).unwrap() > 0.0);
+    }
+
+    #[test]
+    fn lens_is_saved() {
+        let mut camera = Camera::new(4, 3, 1.0);
+        camera.aperture = 0.1;
+        camera.focal_distance = 6.0;
+        camera.aperture_shape = ApertureShape::Polygon(6);
+        let json = serde_json::to_string(&camera).unwrap();
+        let loaded: Camera = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.aperture, 0.1);
+        assert_eq!(loaded.focal_distance, 6.0);
+        assert_eq!(loaded.aperture_shape, ApertureShape::Polygon(6));
+        assert!(!serde_json::to_string(&Camera::new(4, 3, 1.0))
+            .unwrap()
+            .contains("lens"));
+
+        let negative = r#"{"width": 4, "height": 3, "field_of_view": 1.0,
+            "lens": {"aperture": -1.0, "focal_distance": 2.0}}"#;
+        assert!(serde_json::from_str::<Camera>(negative).is_err());
+    }
+
+    #[test]
+    fn orthographic() {
+        let mut camera = Camera::new(201, 101, PI / 2.0);
+        camera.set_projection(Projection::Orthographic { width: 4.0 });
+
+        let center = camera.ray_for_pixel(100, 50);
+        assert!(center.origin.equal(Point::new_point(0.0, 0.0, 0.0)));
+        assert!(center.direction.equal(Vector::new_vector(0.0, 0.0, -1.0)));
+        // rays stay parallel, moving across the 4 units of the view
+        let edge = camera.ray_for_pixel(0, 50);
+        assert!(edge
+            .origin
+            .equal(Point::new_point(2.0 * 200.0 / 201.0, 0.0, 0.0)));
+        assert!(edge.direction.equal(Vector::new_vector(0.0, 0.0, -1.0)));
+
+        // so the sphere is 2 across out of 4 however far away it is
+        let world = World::default();
+        camera = Camera::new(20, 20, PI / 2.0);
+        camera.set_projection(Projection::Orthographic { width: 4.0 });
+        camera.set_transform(Matrix::view_transform(
+            Point::new_point(0.0, 0.0, -50.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+        let image = camera.render(&world);
+        let background = Color::new(0.0, 0.0, 0.0);
+        assert!(!image.pixel_at(5, 10).equal(background));
+        assert!(image.pixel_at(4, 10).equal(background));
+    }
+
+    #[test]
+    fn fisheye() {
+        let mut camera = Camera::new(201, 101, PI);
+        camera.set_projection(Projection::Fisheye);
+        camera.set_transform(Matrix::translation(0.0, 0.0, 5.0));
+
+        assert!(camera
+            .ray_for_pixel(100, 50)
+            .direction
+            .equal(Vector::new_vector(0.0, 0.0, -1.0)));
+        let edge = camera.ray_for_pixel_offset(0, 50, 0.0, 0.5);
+        assert!(edge.origin.equal(Point::new_point(0.0, 0.0, -5.0)));
+        assert!(edge.direction.equal(Vector::new_vector(1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn equirectangular() {
+        let mut world = World::new();
+        world.add_object(Box::new(Sphere::default()));
+        let mut camera = Camera::new(40, 20, 1.0);
+        camera.set_projection(Projection::Equirectangular);
+        camera.set_transform(Matrix::view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+
+        // the left edge looks behind the camera, the bottom straight down
+        let behind = camera.ray_for_pixel_offset(0, 10, 0.0, 0.0);
+        assert!(behind.direction.equal(Vector::new_vector(0.0, 0.0, -1.0)));
+        let down = camera.ray_for_pixel_offset(7, 20, 0.5, 0.0);
+        assert!(down.direction.equal(Vector::new_vector(0.0, -1.0, 0.0)));
+
+        // the sphere only covers the middle of the panorama
+        let image = camera.render(&world);
+        let background = Color::new(0.0, 0.0, 0.0);
+        assert!(!image.pixel_at(20, 10).equal(background));
+        assert!(image.pixel_at(10, 10).equal(background));
+        assert!(image.pixel_at(20, 2).equal(background));
+    }
+
+    #[test]
+    fn resized_keeps_settings() {
+        let mut camera = Camera::new(20, 10, 1.0);
+        camera.set_projection(Projection::Orthographic { width: 3.0 });
+        camera.set_transform(Matrix::translation(1.0, 2.0, 3.0));
+        camera.samples_per_pixel = 4;
+        camera.shutter_close = 0.5;
+        camera.aperture = 0.1;
+        camera.focal_distance = 3.0;
+        let resized = camera.resized(40, 20);
+
+        assert_eq!((resized.hsize(), resized.vsize()), (40, 20));
+        assert_eq!(resized.projection(), camera.projection());
+        assert_eq!(resized.get_transform(), camera.get_transform());
+        assert_eq!(resized.samples_per_pixel, 4);
+        assert_eq!(resized.shutter_close, 0.5);
+        assert_eq!((resized.aperture, resized.focal_distance), (0.1, 3.0));
+        // the same view, just with more pixels
+        assert!(resized
+            .ray_for_pixel_offset(0, 0, 0.0, 0.0)
+            .origin
+            .equal(camera.ray_for_pixel_offset(0, 0, 0.0, 0.0).origin));
+    }
+
+    #[test]
+    fn projection_is_saved() {
+        let mut camera = Camera::new(4, 3, 2.0);
+        camera.set_projection(Projection::Fisheye);
+        let json = serde_json::to_string(&camera).unwrap();
+        let loaded: Camera = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.projection(), Projection::Fisheye);
+        assert!(!serde_json::to_string(&Camera::new(4, 3, 1.0))
+            .unwrap()
+            .contains("projection"));
+
+        let zero_width = r#"{"width": 4, "height": 3, "field_of_view": 1.0,
+            "projection": {"type": "orthographic", "width": 0.0}}"#;
+        let error = serde_json::from_str::<Camera>(zero_width).err().unwrap();
+        assert!(error
+            .to_string()
+            .contains("orthographic width must be above 0"));
+    }
 }
@@ -0,0 +1,206 @@
+use std::f64::consts::PI;
+
+use super::{surface_reflectance, World};
+use crate::base_types::{hit_index, IntersectionDetails, Point, Ray};
+use crate::utils::FLOAT_DIFF;
+use crate::visuals::Color;
+
+impl World {
+    // diffuse light reaching the hit from every emissive object that can
+    // see it, estimated from one point on each, picked by u and v. Averaging
+    // over many points gives soft shadows. Objects light from both sides
+    // of their surface
+    pub(super) fn emissive_light(
+        &self,
+        comps: &IntersectionDetails,
+        albedo: Color,
+        u: f64,
+        v: f64,
+    ) -> Color {
+        let mut color = Color::new(0.0, 0.0, 0.0);
+
+        for object in self.objects.iter() {
+            let material = object.get_material();
+            if !material.is_emissive() {
+                continue;
+            }
+            let Some(sample) = object.sample_surface(u, v, comps.time) else {
+                continue;
+            };
+
+            let to_light = sample.point - comps.point;
+            let distance = to_light.magnitude();
+            let direction = to_light / distance;
+            let surface_cosine = direction.dot(comps.surface_normal);
+            let light_cosine = direction.dot(sample.normal).abs();
+            if surface_cosine <= 0.0
+                || light_cosine == 0.0
+                || self.is_blocked(comps.over_point, sample.point, comps.time)
+            {
+                continue;
+            }
+
+            // the surface reflects its reflectance / pi of the light, which
+            // comes from a patch of the emitter 1 / pdf in size that looks
+            // smaller the further away and more turned away it is
+            color = color
+                + surface_reflectance(comps, albedo, direction)
+                    * material.emitted()
+                    * (surface_cosine * light_cosine)
+                    / (PI * distance * distance * sample.pdf);
+        }
+
+        color
+    }
+
+    // true when something is in between the two points
+    fn is_blocked(&self, from: Point, to: Point, time: f64) -> bool {
+        let path = to - from;
+        let distance = path.magnitude();
+        let intersections = self.intersect(Ray::new(from, path / distance).with_time(time));
+
+        // the point on the emitter is hit itself, give or take
+        hit_index(&intersections)
+            .is_some_and(|index| intersections[index].distance < distance - FLOAT_DIFF)
+    }
+
+    pub(super) fn has_emitters(&self) -> bool {
+        self.objects
+            .iter()
+            .any(|object| object.get_material().is_emissive())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base_types::{prepare_computations, Vector};
+    use crate::matrices::Matrix;
+    use crate::visuals::Material;
+    use crate::world::{Object, Plane, Quad, Sphere};
+
+    fn lamp(strength: f64) -> Material {
+        Material {
+            emission: Color::new(1.0, 1.0, 1.0),
+            emission_strength: strength,
+            ..Material::default()
+        }
+    }
+
+    // a white floor under a small square lamp, with no point lights
+    fn lit_floor() -> World {
+        let mut floor = Plane::default();
+        floor.set_material(Material {
+            ambient: 0.0,
+            diffuse: 1.0,
+            specular: 0.0,
+            ..Material::default()
+        });
+        let lamp = Quad::new(
+            Matrix::translation(0.0, 2.0, 0.0).mul(&Matrix::scaling(0.1, 1.0, 0.1)),
+            lamp(100.0),
+        );
+
+        World {
+            objects: vec![Box::new(floor), Box::new(lamp)],
+            lights: Vec::new(),
+            ..World::new()
+        }
+    }
+
+    fn looking_down_at(x: f64) -> Ray {
+        Ray::new(
+            Point::new_point(x, 0.5, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn glow() {
+        let mut sphere = Sphere::default();
+        sphere.set_material(Material {
+            emission: Color::new(0.5, 0.2, 0.1),
+            emission_strength: 2.0,
+            ..Material::default()
+        });
+        sphere.set_transform(Matrix::translation(0.0, 0.0, 5.0));
+        let world = World {
+            objects: vec![Box::new(sphere)],
+            ..World::new()
+        };
+        let ray = Ray::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let lit = world.color_at(ray, None);
+
+        // on top of the phong lighting from the point light
+        let mut dark = World::new();
+        dark.add_object(Box::new(Sphere::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            1.0,
+            Matrix::translation(0.0, 0.0, 5.0),
+            Material::default(),
+        )));
+        assert!((lit - dark.color_at(ray, None)).equal(Color::new(1.0, 0.4, 0.2)));
+    }
+
+    #[test]
+    fn lights_surfaces() {
+        let world = lit_floor();
+        let color = world.color_at(looking_down_at(0.0), None);
+
+        // a lamp 0.2 wide and 100 strong 2 above is about a point light of
+        // 4 / pi, and cos = 1 and 1 / d^2 = 1 / 4 right under it
+        let expected = 4.0 / (PI * 4.0);
+        assert!((color.0 - expected).abs() < 0.01, "{:?}", color.0);
+        // falling off to the side
+        let side = world.color_at(looking_down_at(2.0), None);
+        assert!(side.0 < color.0 / 2.0 && side.0 > 0.0);
+    }
+
+    #[test]
+    fn casts_shadows() {
+        let mut world = lit_floor();
+        let mut blocker = Sphere::default();
+        blocker
+            .set_transform(Matrix::translation(0.0, 1.0, 0.0).mul(&Matrix::scaling(0.5, 0.1, 0.5)));
+        world.add_object(Box::new(blocker));
+
+        assert!(world
+            .color_at(looking_down_at(0.0), None)
+            .equal(Color::new(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn soft_shadow_edges() {
+        // half of a wider lamp is covered, so the floor gets about half of it
+        let mut world = lit_floor();
+        world.objects[1].set_transform(Matrix::translation(0.0, 2.0, 0.0));
+        let mut cover = Quad::default();
+        cover.set_transform(
+            Matrix::translation(-1.0, 1.0, 0.0).mul(&Matrix::scaling(1.0, 1.0, 2.0)),
+        );
+        let full = world.color_at(looking_down_at(0.0), None);
+        world.add_object(Box::new(cover));
+        let half = world.color_at(looking_down_at(0.0), None);
+
+        assert!((half.0 / full.0 - 0.5).abs() < 0.1, "{}", half.0 / full.0);
+    }
+
+    #[test]
+    fn only_the_facing_side_is_lit() {
+        let world = lit_floor();
+        let ray = looking_down_at(0.0);
+        let intersections = world.intersect(ray);
+        let comps = prepare_computations(hit_index(&intersections).unwrap(), ray, intersections);
+        let white = Color::new(1.0, 1.0, 1.0);
+        assert!(world.emissive_light(&comps, white, 0.5, 0.5).0 > 0.0);
+
+        let mut below = lit_floor();
+        below.objects[1].set_transform(Matrix::translation(0.0, -2.0, 0.0));
+        assert!(below
+            .emissive_light(&comps, white, 0.5, 0.5)
+            .equal(Color::new(0.0, 0.0, 0.0)));
+    }
+}
@@ -1,7 +1,20 @@
+mod aperture;
 mod base;
 mod camera;
+mod emissive;
 mod objects;
+mod occlusion;
+mod path_tracer;
+mod projection;
+mod sampling;
+mod stereo;
 
+pub use aperture::*;
 pub use base::*;
 pub use camera::*;
 pub use objects::*;
+pub use occlusion::*;
+pub use path_tracer::*;
+pub use projection::*;
+pub use sampling::*;
+pub use stereo::*;
@@ -1,13 +1,51 @@
+use serde::{Deserialize, Serialize};
+
 use super::Object;
 use crate::base_types::{Point, Vector};
 use crate::visuals::Color;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Light {
     pub position: Point,
     pub color: Color,
 }
 
+// a point on a surface as seen from the eye, for lighting it
+#[derive(Clone, Copy)]
+pub struct SurfacePoint {
+    pub position: Point,
+    pub eye_normal: Vector,
+    pub surface_normal: Vector,
+    // when during the shutter it is seen, for moving objects
+    pub time: f64,
+    // how much of the ambient light reaches the position, 1 unless ambient
+    // occlusion darkens it
+    pub ambient_visibility: f64,
+}
+
+impl SurfacePoint {
+    pub fn new(position: Point, eye_normal: Vector, surface_normal: Vector) -> Self {
+        Self {
+            position,
+            eye_normal,
+            surface_normal,
+            time: 0.0,
+            ambient_visibility: 1.0,
+        }
+    }
+
+    pub fn with_time(self, time: f64) -> Self {
+        Self { time, ..self }
+    }
+
+    pub fn with_ambient_visibility(self, ambient_visibility: f64) -> Self {
+        Self {
+            ambient_visibility,
+            ..self
+        }
+    }
+}
+
 impl Light {
     pub fn new(position: Point, color: Color) -> Self {
         Self { position, color }
@@ -16,20 +54,26 @@ impl Light {
     pub fn lighting(
         self: Light,
         object: &dyn Object,
-        position: Point,
-        eye_normal: Vector,
-        surface_normal: Vector,
+        surface: &SurfacePoint,
         is_in_shadow: bool,
     ) -> Color {
+        let SurfacePoint {
+            position,
+            eye_normal,
+            surface_normal,
+            time,
+            ambient_visibility,
+        } = *surface;
+
         // combine the surface color with the light's color
-        let effective_color = object.color_at(position) * self.color;
+        let effective_color = object.color_at(position, time) * self.color;
         let material = object.get_material();
 
         // direction to the light source
         let light_direction = (self.position - position).normalize();
 
         // ambient light contribution
-        let ambient = effective_color * material.ambient;
+        let ambient = effective_color * material.ambient * ambient_visibility;
 
         let diffuse: Color;
         let specular: Color;
@@ -37,6 +81,21 @@ impl Light {
         // cosine between light normal and surface normal
         let light_dot_normal = light_direction.dot(surface_normal);
 
+        // physically based surfaces reflect the light in one go, with the
+        // same ambient term as Phong ones
+        if let Some(microfacet) = material.microfacet {
+            if is_in_shadow || light_dot_normal < 0.0 {
+                return ambient;
+            }
+            let reflectance = microfacet.reflectance(
+                object.color_at(position, time),
+                surface_normal,
+                eye_normal,
+                light_direction,
+            );
+            return ambient + reflectance * self.color * light_dot_normal;
+        }
+
         // if it's negative, light is behind surface
         if is_in_shadow || light_dot_normal < 0.0 {
             diffuse = Color::new(0.0, 0.0, 0.0);
@@ -67,6 +126,7 @@ impl Light {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::visuals::Microfacet;
     use crate::world::Sphere;
 
     #[test]
@@ -78,7 +138,11 @@ mod test {
         let light_point = Light::new(Point::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
         assert_eq!(
-            light_point.lighting(&sphere, position, eye_normal, surface_normal, false),
+            light_point.lighting(
+                &sphere,
+                &SurfacePoint::new(position, eye_normal, surface_normal),
+                false
+            ),
             Color::new(1.9, 1.9, 1.9)
         );
     }
@@ -92,7 +156,11 @@ mod test {
         let light_point = Light::new(Point::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
         assert_eq!(
-            light_point.lighting(&sphere, position, eye_normal, surface_normal, false),
+            light_point.lighting(
+                &sphere,
+                &SurfacePoint::new(position, eye_normal, surface_normal),
+                false
+            ),
             Color::new(1.0, 1.0, 1.0)
         );
     }
@@ -109,7 +177,11 @@ mod test {
         );
 
         assert!(light_point
-            .lighting(&sphere, position, eye_normal, surface_normal, false)
+            .lighting(
+                &sphere,
+                &SurfacePoint::new(position, eye_normal, surface_normal),
+                false
+            )
             .equal(Color::new(0.7364, 0.7364, 0.7364)));
     }
 
@@ -125,7 +197,11 @@ mod test {
         );
 
         assert!(light_point
-            .lighting(&sphere, position, eye_normal, surface_normal, false)
+            .lighting(
+                &sphere,
+                &SurfacePoint::new(position, eye_normal, surface_normal),
+                false
+            )
             .equal(Color::new(1.6364, 1.6364, 1.6364)));
     }
 
@@ -138,7 +214,11 @@ mod test {
         let light_point = Light::new(Point::new_point(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
 
         assert_eq!(
-            light_point.lighting(&sphere, position, eye_normal, surface_normal, false),
+            light_point.lighting(
+                &sphere,
+                &SurfacePoint::new(position, eye_normal, surface_normal),
+                false
+            ),
             Color::new(0.1, 0.1, 0.1)
         );
     }
@@ -152,8 +232,77 @@ mod test {
         let light_point = Light::new(Point::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
 
         assert_eq!(
-            light_point.lighting(&sphere, position, eye_normal, surface_normal, true),
+            light_point.lighting(
+                &sphere,
+                &SurfacePoint::new(position, eye_normal, surface_normal),
+                true
+            ),
             Color::new(0.1, 0.1, 0.1)
         );
     }
+
+    #[test]
+    fn lighting_microfacet() {
+        let mut sphere = Sphere::default();
+        let microfacet = Microfacet::new(0.5, 1.0);
+        sphere.get_material_mut().microfacet = Some(microfacet);
+        let position = Point::new_point(0.0, 0.0, 0.0);
+        let eye_normal = Vector::new_vector(0.0, 0.0, -1.0);
+        let surface_normal = Vector::new_vector(0.0, 0.0, -1.0);
+        let light_point = Light::new(
+            Point::new_point(0.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        );
+        let light_direction = Vector::new_vector(0.0, 1.0, -1.0).normalize();
+
+        let expected = Color::new(0.1, 0.1, 0.1)
+            + microfacet.reflectance(
+                Color::new(1.0, 1.0, 1.0),
+                surface_normal,
+                eye_normal,
+                light_direction,
+            ) * light_direction.dot(surface_normal);
+        assert!(light_point
+            .lighting(
+                &sphere,
+                &SurfacePoint::new(position, eye_normal, surface_normal),
+                false
+            )
+            .equal(expected));
+        // shadows leave the ambient term
+        assert!(light_point
+            .lighting(
+                &sphere,
+                &SurfacePoint::new(position, eye_normal, surface_normal),
+                true
+            )
+            .equal(Color::new(0.1, 0.1, 0.1)));
+    }
+
+    #[test]
+    fn lighting_with_ambient_visibility() {
+        let sphere = Sphere::default();
+        let position = Point::new_point(0.0, 0.0, 0.0);
+        let eye_normal = Vector::new_vector(0.0, 0.0, -1.0);
+        let surface_normal = Vector::new_vector(0.0, 0.0, -1.0);
+        let light_point = Light::new(Point::new_point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+
+        // only the ambient term is scaled
+        assert!(light_point
+            .lighting(
+                &sphere,
+                &SurfacePoint::new(position, eye_normal, surface_normal)
+                    .with_ambient_visibility(0.25),
+                true
+            )
+            .equal(Color::new(0.025, 0.025, 0.025)));
+        assert!(light_point
+            .lighting(
+                &sphere,
+                &SurfacePoint::new(position, eye_normal, surface_normal)
+                    .with_ambient_visibility(0.0),
+                false
+            )
+            .equal(Color::new(1.8, 1.8, 1.8)));
+    }
 }
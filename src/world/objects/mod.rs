@@ -1,51 +1,211 @@
 #[cfg(test)]
 use std::any::Any;
+use std::io::{Error, ErrorKind};
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 mod light;
 mod plane;
+mod quad;
 mod sphere;
 
 pub use light::*;
 pub use plane::*;
+pub use quad::*;
 pub use sphere::*;
 
 use crate::base_types::{Intersection, Point, Ray, Vector};
-use crate::matrices::Matrix;
+use crate::matrices::{Matrix, Transform};
 use crate::visuals::{Color, Material};
 
-pub trait Object {
-    fn get_transform(&self) -> Matrix;
+// a point on the surface of an object, for lighting with emissive objects
+pub struct SurfaceSample {
+    pub point: Point,
+    // unit length, pointing out of the object
+    pub normal: Vector,
+    // how likely the point was to be picked, per unit of surface area
+    pub pdf: f64,
+}
+
+pub trait Object: Send + Sync {
+    fn get_transform(&self) -> &Transform;
     fn set_transform(&mut self, transform: Matrix);
-    fn get_material(&self) -> Material;
+    // moves the object from start to end over the camera's shutter interval
+    fn set_moving_transform(&mut self, start: Matrix, end: Matrix);
+    fn get_material(&self) -> &Material;
     fn get_material_mut(&mut self) -> &mut Material;
     fn set_material(&mut self, material: Material);
+    fn to_data(&self) -> ObjectData;
     fn local_normal_at(&self, point: Point) -> Vector;
-    fn local_intersect(&self, ray: Ray) -> Option<Vec<Intersection>>;
-    fn color_at(&self, point: Point) -> Color {
-        let object_space_point = self.get_transform().inverse().unwrap().coords_mul(point);
+    fn local_intersect(&self, ray: Ray) -> Option<Vec<Intersection<'_>>>;
+    // time is the time of the ray that hit the object, see Ray
+    fn color_at(&self, point: Point, time: f64) -> Color {
+        let transform = self.get_transform().at(time);
+        let object_space_point = transform.inverse().unwrap().coords_mul(point);
         self.get_material().color_at(object_space_point)
     }
     // these two functions have default functionality that will be the same for all objects
-    fn normal_at(&self, point: Point) -> Vector {
+    fn normal_at(&self, point: Point, time: f64) -> Vector {
         // if inverse of transform does not exist, we should panic here
-        let transform_inverse = self.get_transform().inverse().unwrap();
-        let object_point = transform_inverse.coords_mul(point);
+        let transform = self.get_transform().at(time);
+        let object_point = transform.inverse().unwrap().coords_mul(point);
         let object_normal = self.local_normal_at(object_point);
-        let mut world_normal = transform_inverse.transpose().coords_mul(object_normal);
-        // hack to reset to vector, i.e., the above calc set w to 1 but we want it to be 0
-        world_normal.w = 0.0;
+        let world_normal = transform
+            .inverse_transpose()
+            .unwrap()
+            .coords_mul(object_normal);
 
         world_normal.normalize()
     }
-    fn intersect(&self, ray: Ray) -> Option<Vec<Intersection>> {
+    fn intersect(&self, ray: Ray) -> Option<Vec<Intersection<'_>>> {
         // every shape will need to transform the ray first
-        let transform_inverse = self.get_transform().inverse()?;
-        let transformed_ray = ray.transform(&transform_inverse);
+        let transform = self.get_transform().at(ray.time);
+        let transformed_ray = ray.transform(transform.inverse()?);
         self.local_intersect(transformed_ray)
     }
+    // a point spread evenly over the surface in object space, with u and v
+    // in [0, 1). None for shapes without a finite surface, which can not
+    // light the world even when they glow
+    fn local_sample(&self, _u: f64, _v: f64) -> Option<SurfaceSample> {
+        None
+    }
+    fn sample_surface(&self, u: f64, v: f64, time: f64) -> Option<SurfaceSample> {
+        let local = self.local_sample(u, v)?;
+        let transform = self.get_transform().at(time);
+        let normal = transform.inverse_transpose()?.coords_mul(local.normal);
+        // how much the transform stretches the surface around the point
+        let stretch = transform.matrix().determinant().abs() * normal.magnitude();
+
+        Some(SurfaceSample {
+            point: transform.matrix().coords_mul(local.point),
+            normal: normal.normalize(),
+            pdf: local.pdf / stretch,
+        })
+    }
 
     #[cfg(test)]
     fn as_any(&self) -> &dyn Any;
     #[cfg(test)]
     fn equal(&self, object: &dyn Object) -> bool;
 }
+
+// every kind of object in a form that can be saved and loaded
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObjectData {
+    Sphere {
+        #[serde(default = "sphere_center")]
+        center: Point,
+        #[serde(default = "sphere_radius")]
+        radius: f64,
+        #[serde(default = "Matrix::new_identity")]
+        transform: Matrix,
+        // only for moving objects
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end_transform: Option<Matrix>,
+        #[serde(default)]
+        material: Material,
+    },
+    Plane {
+        #[serde(default = "Matrix::new_identity")]
+        transform: Matrix,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end_transform: Option<Matrix>,
+        #[serde(default)]
+        material: Material,
+    },
+    Quad {
+        #[serde(default = "Matrix::new_identity")]
+        transform: Matrix,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end_transform: Option<Matrix>,
+        #[serde(default)]
+        material: Material,
+    },
+}
+
+impl ObjectData {
+    // fails for transforms that can not be inverted, which rays could never
+    // be moved into
+    pub fn into_object(self) -> Result<Box<dyn Object>, Error> {
+        let (mut object, transform, end_transform): (Box<dyn Object>, _, _) = match self {
+            Self::Sphere {
+                center,
+                radius,
+                transform,
+                end_transform,
+                material,
+            } => (
+                Box::new(Sphere::new(
+                    center,
+                    radius,
+                    Matrix::new_identity(),
+                    material,
+                )),
+                transform,
+                end_transform,
+            ),
+            Self::Plane {
+                transform,
+                end_transform,
+                material,
+            } => (
+                Box::new(Plane::new(Matrix::new_identity(), material)),
+                transform,
+                end_transform,
+            ),
+            Self::Quad {
+                transform,
+                end_transform,
+                material,
+            } => (
+                Box::new(Quad::new(Matrix::new_identity(), material)),
+                transform,
+                end_transform,
+            ),
+        };
+
+        if transform.inverse().is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "object transform can not be inverted",
+            ));
+        }
+        match end_transform {
+            Some(end_transform) if end_transform.inverse().is_none() => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "object end_transform can not be inverted",
+                ))
+            }
+            Some(end_transform) => object.set_moving_transform(transform, end_transform),
+            None => object.set_transform(transform),
+        }
+
+        Ok(object)
+    }
+}
+
+impl Serialize for dyn Object {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_data().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Object> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ObjectData::deserialize(deserializer)?
+            .into_object()
+            .map_err(D::Error::custom)
+    }
+}
+
+// the unit sphere, same as Sphere::default
+fn sphere_center() -> Point {
+    Point::new_point(0.0, 0.0, 0.0)
+}
+
+fn sphere_radius() -> f64 {
+    1.0
+}
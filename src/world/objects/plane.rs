@@ -1,6 +1,6 @@
-use super::Object;
+use super::{Object, ObjectData};
 use crate::base_types::{Intersection, Point, Ray, Vector};
-use crate::matrices::Matrix;
+use crate::matrices::{Matrix, Transform};
 use crate::utils;
 use crate::visuals::Material;
 
@@ -10,31 +10,36 @@ use std::any::Any;
 // will treat the plane as a plane on the x and z axes that goes through the origin.
 // any movement can be had through transformations
 #[cfg_attr(test, derive(PartialEq))]
+#[derive(Default)]
 pub struct Plane {
-    transform: Matrix,
+    transform: Transform,
     material: Material,
 }
 
 impl Plane {
     pub fn new(transform: Matrix, material: Material) -> Self {
         Self {
-            transform,
+            transform: Transform::new(transform),
             material,
         }
     }
 }
 
 impl Object for Plane {
-    fn get_transform(&self) -> Matrix {
-        self.transform.clone()
+    fn get_transform(&self) -> &Transform {
+        &self.transform
     }
 
     fn set_transform(&mut self, transform: Matrix) {
-        self.transform = transform;
+        self.transform = Transform::new(transform);
     }
 
-    fn get_material(&self) -> Material {
-        self.material.clone()
+    fn set_moving_transform(&mut self, start: Matrix, end: Matrix) {
+        self.transform = Transform::new_moving(start, end);
+    }
+
+    fn get_material(&self) -> &Material {
+        &self.material
     }
 
     fn get_material_mut(&mut self) -> &mut Material {
@@ -45,7 +50,15 @@ impl Object for Plane {
         self.material = material;
     }
 
-    fn local_intersect(&self, ray: Ray) -> Option<Vec<Intersection>> {
+    fn to_data(&self) -> ObjectData {
+        ObjectData::Plane {
+            transform: self.transform.matrix().clone(),
+            end_transform: self.transform.end().map(|end| end.matrix().clone()),
+            material: self.material.clone(),
+        }
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Option<Vec<Intersection<'_>>> {
         // if parallel or coplanar, no intersections
         if utils::float_equal(ray.direction.y, 0.0) {
             return None;
@@ -76,15 +89,6 @@ impl Object for Plane {
     }
 }
 
-impl Default for Plane {
-    fn default() -> Self {
-        Self {
-            transform: Matrix::new_identity(),
-            material: Material::default(),
-        }
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::{Object, Plane};
@@ -95,17 +99,17 @@ mod test {
         let plane = Plane::default();
 
         assert_eq!(
-            plane.normal_at(Point::new_point(0.0, 0.0, 0.0)),
+            plane.normal_at(Point::new_point(0.0, 0.0, 0.0), 0.0),
             Vector::new_vector(0.0, 1.0, 0.0)
         );
 
         assert_eq!(
-            plane.normal_at(Point::new_point(10.0, 0.0, -20.0)),
+            plane.normal_at(Point::new_point(10.0, 0.0, -20.0), 0.0),
             Vector::new_vector(0.0, 1.0, 0.0)
         );
 
         assert_eq!(
-            plane.normal_at(Point::new_point(0.0, -1.0, 70.0)),
+            plane.normal_at(Point::new_point(0.0, -1.0, 70.0), 0.0),
             Vector::new_vector(0.0, 1.0, 0.0)
         );
     }
@@ -0,0 +1,168 @@
+use super::{Object, ObjectData, SurfaceSample};
+use crate::base_types::{Intersection, Point, Ray, Vector};
+use crate::matrices::{Matrix, Transform};
+use crate::utils;
+use crate::visuals::Material;
+
+#[cfg(test)]
+use std::any::Any;
+
+// a square on the x and z axes going from -1 to 1 on both, facing up like
+// Plane. Transformations size and place it, e.g., as a ceiling light
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Default)]
+pub struct Quad {
+    transform: Transform,
+    material: Material,
+}
+
+impl Quad {
+    pub fn new(transform: Matrix, material: Material) -> Self {
+        Self {
+            transform: Transform::new(transform),
+            material,
+        }
+    }
+}
+
+impl Object for Quad {
+    fn get_transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        self.transform = Transform::new(transform);
+    }
+
+    fn set_moving_transform(&mut self, start: Matrix, end: Matrix) {
+        self.transform = Transform::new_moving(start, end);
+    }
+
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+
+    fn get_material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    fn to_data(&self) -> ObjectData {
+        ObjectData::Quad {
+            transform: self.transform.matrix().clone(),
+            end_transform: self.transform.end().map(|end| end.matrix().clone()),
+            material: self.material.clone(),
+        }
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Option<Vec<Intersection<'_>>> {
+        // if parallel or coplanar, no intersections
+        if utils::float_equal(ray.direction.y, 0.0) {
+            return None;
+        }
+
+        let distance = -ray.origin.y / ray.direction.y;
+        let point = ray.position(distance);
+        if point.x.abs() > 1.0 || point.z.abs() > 1.0 {
+            return None;
+        }
+
+        Some(vec![Intersection {
+            distance,
+            object: self,
+        }])
+    }
+
+    fn local_normal_at(&self, _: Point) -> Vector {
+        Vector::new_vector(0.0, 1.0, 0.0)
+    }
+
+    fn local_sample(&self, u: f64, v: f64) -> Option<SurfaceSample> {
+        Some(SurfaceSample {
+            point: Point::new_point(2.0 * u - 1.0, 0.0, 2.0 * v - 1.0),
+            normal: Vector::new_vector(0.0, 1.0, 0.0),
+            // 2 by 2
+            pdf: 0.25,
+        })
+    }
+
+    #[cfg(test)]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[cfg(test)]
+    fn equal(&self, object: &dyn Object) -> bool {
+        match object.as_any().downcast_ref::<Quad>() {
+            Some(quad) => *self == *quad,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::float_equal;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn intersect_inside() {
+        let quad = Quad::default();
+        let ray = Ray::new(
+            Point::new_point(0.5, 1.0, -0.9),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+
+        let intersections = quad.intersect(ray).unwrap();
+        assert_eq!(intersections.len(), 1);
+        assert_eq!(intersections[0].distance, 1.0);
+        assert!(intersections[0].object.equal(&quad));
+    }
+
+    #[test]
+    fn intersect_outside() {
+        let quad = Quad::default();
+        let past_edge = Ray::new(
+            Point::new_point(1.1, 1.0, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+        let parallel = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert!(quad.intersect(past_edge).is_none());
+        assert!(quad.intersect(parallel).is_none());
+    }
+
+    #[test]
+    fn normal() {
+        let mut quad = Quad::default();
+        quad.set_transform(Matrix::rotation_x(PI / 2.0));
+
+        assert!(quad
+            .normal_at(Point::new_point(0.5, 0.0, 0.0), 0.0)
+            .equal(Vector::new_vector(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn sample_surface() {
+        let mut quad = Quad::default();
+        quad.set_transform(
+            Matrix::scaling(2.0, 1.0, 3.0)
+                .rotate_x(PI)
+                .translate(0.0, 5.0, 0.0),
+        );
+        let sample = quad.sample_surface(1.0, 0.5, 0.0).unwrap();
+
+        assert!(sample.point.equal(Point::new_point(2.0, 5.0, 0.0)));
+        // turned over to face down
+        assert!(sample.normal.equal(Vector::new_vector(0.0, -1.0, 0.0)));
+        // spread over 4 by 6
+        assert!(float_equal(sample.pdf, 1.0 / 24.0));
+    }
+}
@@ -1,6 +1,8 @@
-use super::{Intersection, Material, Object, Ray};
+use std::f64::consts::PI;
+
+use super::{Intersection, Material, Object, ObjectData, Ray, SurfaceSample};
 use crate::base_types::{Point, Vector};
-use crate::matrices::Matrix;
+use crate::matrices::{Matrix, Transform};
 
 #[cfg(test)]
 use std::any::Any;
@@ -9,7 +11,7 @@ use std::any::Any;
 pub struct Sphere {
     center: Point,
     radius: f64,
-    transform: Matrix,
+    transform: Transform,
     material: Material,
 }
 
@@ -18,23 +20,27 @@ impl Sphere {
         Self {
             center,
             radius,
-            transform,
+            transform: Transform::new(transform),
             material,
         }
     }
 }
 
 impl Object for Sphere {
-    fn get_transform(&self) -> Matrix {
-        self.transform.clone()
+    fn get_transform(&self) -> &Transform {
+        &self.transform
     }
 
     fn set_transform(&mut self, transform: Matrix) {
-        self.transform = transform;
+        self.transform = Transform::new(transform);
+    }
+
+    fn set_moving_transform(&mut self, start: Matrix, end: Matrix) {
+        self.transform = Transform::new_moving(start, end);
     }
 
-    fn get_material(&self) -> Material {
-        self.material.clone()
+    fn get_material(&self) -> &Material {
+        &self.material
     }
 
     fn get_material_mut(&mut self) -> &mut Material {
@@ -45,7 +51,17 @@ impl Object for Sphere {
         self.material = material;
     }
 
-    fn local_intersect(&self, ray: Ray) -> Option<Vec<Intersection>> {
+    fn to_data(&self) -> ObjectData {
+        ObjectData::Sphere {
+            center: self.center,
+            radius: self.radius,
+            transform: self.transform.matrix().clone(),
+            end_transform: self.transform.end().map(|end| end.matrix().clone()),
+            material: self.material.clone(),
+        }
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Option<Vec<Intersection<'_>>> {
         let sphere_to_ray = ray.origin - self.center;
 
         let a = ray.direction.dot(ray.direction);
@@ -94,6 +110,20 @@ impl Object for Sphere {
         point - self.center
     }
 
+    fn local_sample(&self, u: f64, v: f64) -> Option<SurfaceSample> {
+        // even in height is even over the surface of a sphere
+        let y = 1.0 - 2.0 * u;
+        let ring_radius = (1.0 - y * y).max(0.0).sqrt();
+        let angle = 2.0 * PI * v;
+        let normal = Vector::new_vector(ring_radius * angle.cos(), y, ring_radius * angle.sin());
+
+        Some(SurfaceSample {
+            point: self.center + normal * self.radius,
+            normal,
+            pdf: 1.0 / (4.0 * PI * self.radius * self.radius),
+        })
+    }
+
     #[cfg(test)]
     fn as_any(&self) -> &dyn Any {
         self
@@ -113,7 +143,7 @@ impl Default for Sphere {
         Self {
             center: Point::new_point(0.0, 0.0, 0.0),
             radius: 1.0,
-            transform: Matrix::new_identity(),
+            transform: Transform::default(),
             material: Material::default(),
         }
     }
@@ -130,27 +160,30 @@ mod test {
         let sphere = Sphere::default();
 
         assert_eq!(
-            sphere.normal_at(Point::new_point(1.0, 0.0, 0.0)),
+            sphere.normal_at(Point::new_point(1.0, 0.0, 0.0), 0.0),
             Vector::new_vector(1.0, 0.0, 0.0)
         );
 
         assert_eq!(
-            sphere.normal_at(Point::new_point(0.0, 1.0, 0.0)),
+            sphere.normal_at(Point::new_point(0.0, 1.0, 0.0), 0.0),
             Vector::new_vector(0.0, 1.0, 0.0)
         );
 
         assert_eq!(
-            sphere.normal_at(Point::new_point(0.0, 0.0, 1.0)),
+            sphere.normal_at(Point::new_point(0.0, 0.0, 1.0), 0.0),
             Vector::new_vector(0.0, 0.0, 1.0)
         );
 
         let three: f64 = 3.0;
         assert_eq!(
-            sphere.normal_at(Point::new_point(
-                three.sqrt() / three,
-                three.sqrt() / three,
-                three.sqrt() / three
-            )),
+            sphere.normal_at(
+                Point::new_point(
+                    three.sqrt() / three,
+                    three.sqrt() / three,
+                    three.sqrt() / three
+                ),
+                0.0
+            ),
             Vector::new_vector(
                 three.sqrt() / three,
                 three.sqrt() / three,
@@ -165,17 +198,16 @@ mod test {
         sphere.set_transform(Matrix::translation(0.0, 1.0, 0.0));
 
         assert!(sphere
-            .normal_at(Point::new_point(0.0, 1.70711, -0.707111))
+            .normal_at(Point::new_point(0.0, 1.70711, -0.707111), 0.0)
             .equal(Vector::new_vector(0.0, 0.707111, -0.707111)));
 
         sphere.set_transform(Matrix::scaling(1.0, 0.5, 1.0).mul(&Matrix::rotation_z(PI / 5.0)));
 
         assert!(sphere
-            .normal_at(Point::new_point(
-                0.0,
-                (2.0 as f64).sqrt() / 2.0,
-                -(2.0 as f64).sqrt() / 2.0
-            ))
+            .normal_at(
+                Point::new_point(0.0, 2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0),
+                0.0
+            )
             .equal(Vector::new_vector(0.0, 0.97014, -0.24254)));
     }
 
@@ -279,6 +311,61 @@ mod test {
         assert!(intersections_opt.is_none());
     }
 
+    #[test]
+    fn intersect_moving() {
+        let ray = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let mut sphere = Sphere::default();
+        sphere.set_moving_transform(Matrix::new_identity(), Matrix::translation(4.0, 0.0, 0.0));
+
+        assert_eq!(sphere.intersect(ray).unwrap().len(), 2);
+        assert!(sphere.intersect(ray.with_time(0.5)).is_none());
+        // a quarter of the way the sphere still covers the ray, off center
+        let intersections = sphere.intersect(ray.with_time(0.125)).unwrap();
+        assert!(crate::utils::float_equal(
+            intersections[0].distance,
+            5.0 - 0.75_f64.sqrt()
+        ));
+        assert!(sphere
+            .normal_at(Point::new_point(4.0, 1.0, 0.0), 1.0)
+            .equal(Vector::new_vector(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn sample_surface() {
+        let mut sphere = Sphere::default();
+        sphere
+            .set_transform(Matrix::translation(1.0, 0.0, 0.0).mul(&Matrix::scaling(2.0, 2.0, 2.0)));
+        let sample = sphere.sample_surface(0.0, 0.3, 0.0).unwrap();
+
+        assert!(sample.point.equal(Point::new_point(1.0, 2.0, 0.0)));
+        assert!(sample.normal.equal(Vector::new_vector(0.0, 1.0, 0.0)));
+        assert!(crate::utils::float_equal(sample.pdf, 1.0 / (16.0 * PI)));
+    }
+
+    #[test]
+    fn sample_stretched_surface() {
+        // 1 / pdf averages out to the surface area, even where the
+        // transform stretches some parts more than others
+        let mut sphere = Sphere::default();
+        sphere.set_transform(Matrix::scaling(2.0, 1.0, 1.0));
+        let count = 100;
+        let mut area = 0.0;
+        for i in 0..count {
+            for j in 0..count {
+                let u = (i as f64 + 0.5) / count as f64;
+                let v = (j as f64 + 0.5) / count as f64;
+                area += 1.0 / sphere.sample_surface(u, v, 0.0).unwrap().pdf;
+            }
+        }
+        area /= (count * count) as f64;
+
+        // the surface area of a 2 by 1 by 1 spheroid
+        assert!((area - 21.478).abs() < 0.01, "{}", area);
+    }
+
     #[test]
     fn color_at_with_object_transform() {
         let color_a = Color::new(0.0, 0.0, 0.0);
@@ -287,14 +374,22 @@ mod test {
 
         let mut sphere = Sphere::default();
 
-        let mut material = Material::default();
-        material.patterns = vec![Box::new(pattern)];
+        let material = Material {
+            patterns: vec![Box::new(pattern)],
+            ..Material::default()
+        };
         sphere.set_material(material);
 
         sphere.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
 
-        assert_eq!(sphere.color_at(Point::new_point(1.5, 0.0, 0.0)), color_a);
-        assert_eq!(sphere.color_at(Point::new_point(3.5, 0.0, 0.0)), color_b);
+        assert_eq!(
+            sphere.color_at(Point::new_point(1.5, 0.0, 0.0), 0.0),
+            color_a
+        );
+        assert_eq!(
+            sphere.color_at(Point::new_point(3.5, 0.0, 0.0), 0.0),
+            color_b
+        );
     }
 
     #[test]
@@ -306,12 +401,20 @@ mod test {
 
         let mut sphere = Sphere::default();
 
-        let mut material = Material::default();
-        material.patterns = vec![Box::new(pattern)];
+        let material = Material {
+            patterns: vec![Box::new(pattern)],
+            ..Material::default()
+        };
         sphere.set_material(material);
 
-        assert_eq!(sphere.color_at(Point::new_point(1.5, 0.0, 0.0)), color_a);
-        assert_eq!(sphere.color_at(Point::new_point(3.5, 0.0, 0.0)), color_b);
+        assert_eq!(
+            sphere.color_at(Point::new_point(1.5, 0.0, 0.0), 0.0),
+            color_a
+        );
+        assert_eq!(
+            sphere.color_at(Point::new_point(3.5, 0.0, 0.0), 0.0),
+            color_b
+        );
     }
 
     #[test]
@@ -324,11 +427,19 @@ mod test {
         let mut sphere = Sphere::default();
         sphere.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
 
-        let mut material = Material::default();
-        material.patterns = vec![Box::new(pattern)];
+        let material = Material {
+            patterns: vec![Box::new(pattern)],
+            ..Material::default()
+        };
         sphere.set_material(material);
 
-        assert_eq!(sphere.color_at(Point::new_point(2.5, 0.0, 0.0)), color_a);
-        assert_eq!(sphere.color_at(Point::new_point(3.5, 0.0, 0.0)), color_b);
+        assert_eq!(
+            sphere.color_at(Point::new_point(2.5, 0.0, 0.0), 0.0),
+            color_a
+        );
+        assert_eq!(
+            sphere.color_at(Point::new_point(3.5, 0.0, 0.0), 0.0),
+            color_b
+        );
     }
 }
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+
+use super::{cosine_weighted_direction, halton, Random, World};
+use crate::base_types::{hit_index, prepare_computations, Point, Ray, Vector};
+
+// replaces the flat ambient term of the Whitted integrator with how much of
+// the surroundings each hit can see, darkening creases and the ground
+// under objects
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmbientOcclusion {
+    // rays traced around every hit, more give smoother shading
+    pub samples: usize,
+    // objects further away than this do not block ambient light, so open
+    // rooms are not dark all over
+    pub distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: usize, distance: f64) -> Self {
+        Self { samples, distance }
+    }
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self::new(16, 1.0)
+    }
+}
+
+impl World {
+    // how much of the ambient light is blocked where the ray hits, from 0
+    // when nothing is near to 1 in a closed corner. Misses are not blocked
+    pub fn occlusion_at(&self, ray: Ray, settings: &AmbientOcclusion) -> f64 {
+        let intersections = self.intersect(ray);
+        let Some(hit_index) = hit_index(&intersections) else {
+            return 0.0;
+        };
+        let comps = prepare_computations(hit_index, ray, intersections);
+
+        self.occlusion(comps.over_point, comps.surface_normal, comps.time, settings)
+    }
+
+    // the share of cosine weighted rays from the point that hit something
+    // within the distance, which matches how a diffuse surface takes in
+    // light from around it
+    pub(super) fn occlusion(
+        &self,
+        point: Point,
+        normal: Vector,
+        time: f64,
+        settings: &AmbientOcclusion,
+    ) -> f64 {
+        if settings.samples == 0 {
+            return 0.0;
+        }
+
+        // every point turns the same evenly spread directions by its own
+        // amount, so what is left of the pattern shows as fine noise
+        // instead of bands, and renders still come out the same each time
+        let mut random = Random::new(
+            point.x.to_bits()
+                ^ point.y.to_bits().rotate_left(21)
+                ^ point.z.to_bits().rotate_left(42),
+        );
+        let (shift_u, shift_v) = (random.next_f64(), random.next_f64());
+
+        let mut blocked = 0;
+        for sample in 1..=settings.samples {
+            let direction = cosine_weighted_direction(
+                normal,
+                (halton(sample, 2) + shift_u).fract(),
+                (halton(sample, 3) + shift_v).fract(),
+            );
+            let intersections = self.intersect(Ray::new(point, direction).with_time(time));
+            if hit_index(&intersections)
+                .is_some_and(|index| intersections[index].distance < settings.distance)
+            {
+                blocked += 1;
+            }
+        }
+
+        blocked as f64 / settings.samples as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::matrices::Matrix;
+    use crate::visuals::Color;
+    use crate::world::{Light, Object, Plane, Sphere};
+
+    // a floor with a ceiling half a unit above it
+    fn under_a_lid() -> World {
+        let mut lid = Plane::default();
+        lid.set_transform(Matrix::translation(0.0, 0.5, 0.0));
+
+        World {
+            objects: vec![Box::new(Plane::default()), Box::new(lid)],
+            ..World::new()
+        }
+    }
+
+    fn looking_down() -> Ray {
+        Ray::new(
+            Point::new_point(0.0, 0.25, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn open_surfaces() {
+        let world = World {
+            objects: vec![Box::new(Plane::default())],
+            ..World::new()
+        };
+
+        assert_eq!(
+            world.occlusion_at(looking_down(), &AmbientOcclusion::default()),
+            0.0
+        );
+        // and misses
+        let up = Ray::new(
+            Point::new_point(0.0, 1.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        );
+        assert_eq!(world.occlusion_at(up, &AmbientOcclusion::default()), 0.0);
+    }
+
+    #[test]
+    fn closed_in() {
+        let world = under_a_lid();
+
+        assert_eq!(
+            world.occlusion_at(looking_down(), &AmbientOcclusion::new(16, f64::INFINITY)),
+            1.0
+        );
+        // at a slant it gets too far away to count, a quarter of the
+        // cosine weighted rays go further than 1 before reaching it
+        let slanted = world.occlusion_at(looking_down(), &AmbientOcclusion::new(64, 1.0));
+        assert!((slanted - 0.75).abs() < 0.05, "{}", slanted);
+        // and the lid is too far away to count at all
+        assert_eq!(
+            world.occlusion_at(looking_down(), &AmbientOcclusion::new(16, 0.4)),
+            0.0
+        );
+        assert_eq!(
+            world.occlusion_at(looking_down(), &AmbientOcclusion::new(0, 1.0)),
+            0.0
+        );
+    }
+
+    #[test]
+    fn creases() {
+        // a floor meeting a wall on x = 1
+        let mut wall = Plane::default();
+        wall.set_transform(
+            Matrix::translation(1.0, 0.0, 0.0).mul(&Matrix::rotation_z(std::f64::consts::PI / 2.0)),
+        );
+        let world = World {
+            objects: vec![Box::new(Plane::default()), Box::new(wall)],
+            ..World::new()
+        };
+        let settings = AmbientOcclusion::new(256, 10.0);
+        let at = |x| {
+            let ray = Ray::new(
+                Point::new_point(x, 0.5, 0.0),
+                Vector::new_vector(0.0, -1.0, 0.0),
+            );
+            world.occlusion_at(ray, &settings)
+        };
+
+        // right in the crease the wall hides half of what the floor sees,
+        // less the further from it
+        assert!((at(0.999) - 0.5).abs() < 0.05, "{}", at(0.999));
+        assert!(at(0.5) < at(0.9));
+        assert!(at(0.5) > 0.0);
+    }
+
+    #[test]
+    fn darkens_the_ambient_term() {
+        // in the shadow of the lid only the ambient term is left
+        let mut world = under_a_lid();
+        world.objects.push(Box::new(Sphere::default()));
+        world.lights = vec![Light::new(
+            Point::new_point(0.0, 10.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        )];
+        let ray = Ray::new(
+            Point::new_point(2.0, 0.25, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+        assert!(world.color_at(ray, None).equal(Color::new(0.1, 0.1, 0.1)));
+
+        world.ambient_occlusion = Some(AmbientOcclusion::new(16, f64::INFINITY));
+        assert!(world.color_at(ray, None).equal(Color::new(0.0, 0.0, 0.0)));
+        world.ambient_occlusion = Some(AmbientOcclusion::new(16, 0.1));
+        assert!(world.color_at(ray, None).equal(Color::new(0.1, 0.1, 0.1)));
+    }
+
+    #[test]
+    fn serialize() {
+        assert_eq!(
+            serde_json::to_string(&AmbientOcclusion::new(8, 2.0)).unwrap(),
+            r#"{"samples":8,"distance":2.0}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<AmbientOcclusion>(r#"{"samples":4}"#).unwrap(),
+            AmbientOcclusion::new(4, 1.0)
+        );
+    }
+}
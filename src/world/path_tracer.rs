@@ -0,0 +1,410 @@
+use serde::{Deserialize, Serialize};
+
+use super::{cosine_weighted_direction, Random, World};
+use crate::base_types::{hit_index, prepare_computations, IntersectionDetails, Ray, Vector};
+use crate::visuals::Color;
+
+// bounces every path makes before russian roulette can end it
+const MIN_BOUNCES: usize = 3;
+// even bright paths can end, so every path ends sooner or later
+const MAX_SURVIVAL: f64 = 0.95;
+
+// how the world works out the light coming back along a camera ray
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Integrator {
+    // direct phong lighting and mirror reflections, with the flat ambient
+    // term standing in for light bouncing between surfaces
+    #[default]
+    Whitted,
+    // follows rays as they bounce around the world. Noisy at first, it
+    // settles on the right image as camera samples per pixel go up. The
+    // seed picks the noise, so the same seed gives the same image
+    PathTracer {
+        seed: u64,
+    },
+}
+
+impl Integrator {
+    pub(super) fn is_whitted(&self) -> bool {
+        matches!(self, Self::Whitted)
+    }
+}
+
+impl World {
+    // one random path from the camera ray, averaging many of them gives the
+    // light arriving along it.
+    //  - diffuse surfaces send the path on in a cosine weighted direction
+    //  - physically based ones are sampled the same way, so the smoother
+    //    they are the more samples their shine takes to settle
+    //  - reflective ones act as mirrors for that share of the paths
+    //  - point lights can not be hit by chance, so every diffuse hit looks
+    //    for them directly, with the same falloff free light as the
+    //    Whitted integrator
+    //  - every diffuse hit also picks a point on each emissive sphere and
+    //    quad to light it directly. Their light is then only counted when
+    //    the camera or a mirror sees them, or it would be counted twice.
+    //    Other emissive shapes can not be sampled, so their light is
+    //    counted wherever the path hits them
+    //  - transparency, like in the Whitted integrator, is not handled yet
+    // after a few bounces, paths that carry little light are ended at
+    // random and the ones that go on carry more to make up for them
+    pub fn trace_path(&self, mut ray: Ray, random: &mut Random) -> Color {
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        // how much of the light found further along reaches the camera
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        // whether the emitters were left out of the direct light at the
+        // last hit, the camera counts as a mirror
+        let mut mirrored = true;
+
+        for bounce in 0.. {
+            let intersections = self.intersect(ray);
+            let Some(hit_index) = hit_index(&intersections) else {
+                break;
+            };
+            let comps = prepare_computations(hit_index, ray, intersections);
+            let object = comps.intersection.object;
+            let material = object.get_material();
+            if mirrored || object.local_sample(0.0, 0.0).is_none() {
+                color = color + throughput * material.emitted();
+            }
+
+            mirrored = random.next_f64() < material.reflective;
+            if mirrored {
+                // picked as often as the surface reflects, so the mirror
+                // image comes through as is
+                ray = Ray::new(comps.over_point, comps.reflect_vector).with_time(comps.time);
+            } else {
+                let albedo = object.color_at(comps.point, comps.time) * material.diffuse;
+                color = color + throughput * self.direct_light(&comps, albedo);
+                color = color
+                    + throughput
+                        * self.emissive_light(&comps, albedo, random.next_f64(), random.next_f64());
+
+                let direction = cosine_weighted_direction(
+                    comps.surface_normal,
+                    random.next_f64(),
+                    random.next_f64(),
+                );
+                ray = Ray::new(comps.over_point, direction).with_time(comps.time);
+                // the cosine weighting cancels out the cosine of the
+                // surface, and the pi of the BRDF
+                throughput = throughput * surface_reflectance(&comps, albedo, direction);
+            }
+
+            if bounce + 1 >= MIN_BOUNCES {
+                let survival = throughput
+                    .0
+                    .max(throughput.1)
+                    .max(throughput.2)
+                    .min(MAX_SURVIVAL);
+                if random.next_f64() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+
+        color
+    }
+
+    // diffuse and specular light from every point light that can see the
+    // hit, without the ambient term
+    fn direct_light(&self, comps: &IntersectionDetails, albedo: Color) -> Color {
+        let material = comps.intersection.object.get_material();
+        let mut color = Color::new(0.0, 0.0, 0.0);
+
+        for (i, light) in self.lights.iter().enumerate() {
+            let light_direction = (light.position - comps.point).normalize();
+            let light_dot_normal = light_direction.dot(comps.surface_normal);
+            if light_dot_normal <= 0.0 || self.is_shadowed(comps.over_point, i, comps.time) {
+                continue;
+            }
+
+            color = color
+                + surface_reflectance(comps, albedo, light_direction)
+                    * light.color
+                    * light_dot_normal;
+            if material.microfacet.is_some() {
+                // its shine is part of the reflectance
+                continue;
+            }
+            let reflection_dot_eye = (-light_direction)
+                .reflect(comps.surface_normal)
+                .dot(comps.eye_normal);
+            if reflection_dot_eye > 0.0 {
+                color = color
+                    + light.color * material.specular * reflection_dot_eye.powf(material.shininess);
+            }
+        }
+
+        color
+    }
+}
+
+// light the hit reflects toward the eye for each unit arriving from the
+// direction, before the cosine and times pi like Microfacet::reflectance.
+// Phong surfaces count as just diffuse here, reflecting their albedo
+pub(super) fn surface_reflectance(
+    comps: &IntersectionDetails,
+    albedo: Color,
+    direction: Vector,
+) -> Color {
+    let object = comps.intersection.object;
+    match object.get_material().microfacet {
+        Some(microfacet) => microfacet.reflectance(
+            object.color_at(comps.point, comps.time),
+            comps.surface_normal,
+            comps.eye_normal,
+            direction,
+        ),
+        None => albedo,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base_types::{Point, Vector};
+    use crate::matrices::Matrix;
+    use crate::visuals::{Material, Microfacet};
+    use crate::world::{Camera, Light, Object, Plane, Quad, Sphere};
+    use std::f64::consts::PI;
+
+    fn average(world: &World, ray: Ray, count: usize) -> Color {
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for sample in 0..count {
+            total = total + world.trace_path(ray, &mut Random::for_sample(1, 0, 0, sample));
+        }
+
+        total / count as f64
+    }
+
+    // inside a sphere that gives off light and reflects some of it
+    fn furnace(albedo: f64) -> World {
+        let mut sphere = Sphere::default();
+        sphere.set_material(Material {
+            color: Color::new(albedo, albedo, albedo),
+            diffuse: 1.0,
+            specular: 0.0,
+            emission: Color::new(1.0, 1.0, 1.0),
+            ..Material::default()
+        });
+
+        World {
+            objects: vec![Box::new(sphere)],
+            lights: Vec::new(),
+            ..World::new()
+        }
+    }
+
+    #[test]
+    fn misses_are_black() {
+        let world = World::new();
+        let ray = Ray::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert_eq!(
+            world.trace_path(ray, &mut Random::new(0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn emission_is_seen_directly() {
+        let mut world = furnace(0.0);
+        world.objects[0].set_transform(Matrix::translation(0.0, 0.0, 5.0));
+        let ray = Ray::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+
+        // nothing is reflected, so every path sees just the emission
+        assert!(world
+            .trace_path(ray, &mut Random::new(0))
+            .equal(Color::new(1.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn furnace_converges() {
+        // every bounce adds the emission again, scaled down by the albedo,
+        // so the light inside adds up to 1 / (1 - albedo)
+        let world = furnace(0.5);
+        let ray = Ray::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.3, 0.2, 1.0).normalize(),
+        );
+        let color = average(&world, ray, 4000);
+
+        assert!((color.0 - 2.0).abs() < 0.05, "{:?}", color.0);
+    }
+
+    #[test]
+    fn point_lights_light_diffuse_surfaces() {
+        // a white floor lit from straight above
+        let mut floor = Plane::default();
+        floor.set_material(Material {
+            diffuse: 0.5,
+            specular: 0.0,
+            ..Material::default()
+        });
+        let world = World {
+            objects: vec![Box::new(floor)],
+            lights: vec![Light::new(
+                Point::new_point(0.0, 10.0, 0.0),
+                Color::new(1.0, 1.0, 1.0),
+            )],
+            ..World::new()
+        };
+        let ray = Ray::new(
+            Point::new_point(0.0, 1.0, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+
+        // no other surface to bounce light back, so it is all direct
+        assert!(world
+            .trace_path(ray, &mut Random::new(5))
+            .equal(Color::new(0.5, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn microfacet_surfaces() {
+        // a brushed gold floor, lit and seen from straight above
+        let gold = Color::new(1.0, 0.8, 0.3);
+        let microfacet = Microfacet::new(0.6, 1.0);
+        let mut floor = Plane::default();
+        floor.set_material(Material {
+            color: gold,
+            microfacet: Some(microfacet),
+            ..Material::default()
+        });
+        let world = World {
+            objects: vec![Box::new(floor)],
+            lights: vec![Light::new(
+                Point::new_point(0.0, 10.0, 0.0),
+                Color::new(1.0, 1.0, 1.0),
+            )],
+            ..World::new()
+        };
+        let ray = Ray::new(
+            Point::new_point(0.0, 1.0, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+        let up = Vector::new_vector(0.0, 1.0, 0.0);
+
+        assert!(world
+            .trace_path(ray, &mut Random::new(5))
+            .equal(microfacet.reflectance(gold, up, up, up)));
+    }
+
+    #[test]
+    fn emissive_shapes_light_diffuse_surfaces() {
+        let mut floor = Plane::default();
+        floor.set_material(Material {
+            ambient: 0.0,
+            specular: 0.0,
+            ..Material::default()
+        });
+        let lamp = Quad::new(
+            Matrix::translation(0.0, 2.0, 0.0),
+            Material {
+                diffuse: 0.0,
+                specular: 0.0,
+                emission: Color::new(1.0, 1.0, 1.0),
+                emission_strength: 3.0,
+                ..Material::default()
+            },
+        );
+        let world = World {
+            objects: vec![Box::new(floor), Box::new(lamp)],
+            lights: Vec::new(),
+            ..World::new()
+        };
+        let ray = Ray::new(
+            Point::new_point(0.5, 1.0, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+
+        // found by sampling the lamp, not by bounces hitting it, so it
+        // settles on the same light as the Whitted integrator
+        let whitted = world.color_at(ray, None);
+        let path_traced = average(&world, ray, 2000);
+        assert!(whitted.0 > 0.1);
+        assert!((path_traced.0 - whitted.0).abs() < 0.02 * whitted.0);
+    }
+
+    #[test]
+    fn mirrors() {
+        let mut mirror = Plane::default();
+        mirror.set_material(Material {
+            color: Color::new(0.0, 0.0, 0.0),
+            reflective: 1.0,
+            ..Material::default()
+        });
+        mirror.set_transform(Matrix::rotation_x(PI / 2.0));
+        let mut lamp = Sphere::default();
+        lamp.set_material(Material {
+            emission: Color::new(0.0, 2.0, 0.0),
+            ..Material::default()
+        });
+        lamp.set_transform(Matrix::translation(0.0, 0.0, -5.0));
+        let world = World {
+            objects: vec![Box::new(mirror), Box::new(lamp)],
+            lights: Vec::new(),
+            ..World::new()
+        };
+        // looks at the mirror from the other side of the lamp
+        let ray = Ray::new(
+            Point::new_point(0.0, 0.0, -2.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+
+        assert!(world
+            .trace_path(ray, &mut Random::new(9))
+            .equal(Color::new(0.0, 2.0, 0.0)));
+    }
+
+    #[test]
+    fn camera_renders_are_seeded() {
+        let mut world = World {
+            integrator: Integrator::PathTracer { seed: 3 },
+            ..World::default()
+        };
+        let mut camera = Camera::new(8, 6, PI / 3.0);
+        camera.set_transform(Matrix::view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+        camera.samples_per_pixel = 2;
+
+        let first = camera.render(&world);
+        camera.threads = 3;
+        assert!(camera.render(&world).approx_equal(&first, 0.0));
+
+        world.integrator = Integrator::PathTracer { seed: 4 };
+        assert!(!camera.render(&world).approx_equal(&first, 0.0));
+    }
+
+    #[test]
+    fn brighter_than_whitted_ambient_in_corners() {
+        // light bouncing off the floor reaches the underside of the sphere,
+        // where the Whitted integrator only has the flat ambient term
+        let mut world = World::default();
+        world.objects.truncate(1);
+        let mut floor = Plane::default();
+        floor.set_transform(Matrix::translation(0.0, -1.0, 0.0));
+        world.objects.push(Box::new(floor));
+        let ray = Ray::new(
+            Point::new_point(0.0, -0.9, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+
+        let whitted = world.color_at(ray, None);
+        let path_traced = average(&world, ray, 500);
+        assert!(path_traced.1 > whitted.1);
+    }
+}
@@ -0,0 +1,199 @@
+use std::f64::consts::PI;
+use std::io::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_types::{Point, Vector};
+
+// how the camera maps pixels to rays
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Projection {
+    // rays spread out from a point, field of view is the angle across the
+    // longer side of the image
+    #[default]
+    Perspective,
+    // parallel rays, for technical drawings. Width is how much of the scene
+    // fits across the longer side of the image, in world units
+    Orthographic {
+        width: f64,
+    },
+    // equidistant, the angle from the view direction grows in step with the
+    // distance from the center. Field of view is the angle across the longer
+    // side of the image, and can go up to 2 pi
+    Fisheye,
+    // the whole sphere around the camera as a 360 degree panorama, longitude
+    // across and latitude down. Images are usually twice as wide as tall
+    Equirectangular,
+}
+
+impl Projection {
+    // perspective cameras are left as they were, a field of view outside
+    // of 0 to pi just flips or blows up the image
+    pub fn validate(&self, field_of_view: f64) -> Result<(), Error> {
+        let message = match *self {
+            Self::Orthographic { width } if !(width > 0.0 && width.is_finite()) => {
+                "orthographic width must be above 0"
+            }
+            Self::Fisheye if !(field_of_view > 0.0 && field_of_view <= 2.0 * PI) => {
+                "fisheye field of view must be above 0 and at most 2 pi"
+            }
+            _ => return Ok(()),
+        };
+
+        Err(Error::new(ErrorKind::InvalidInput, message))
+    }
+
+    // half the longer side of the image, in the units screen positions are
+    // given in. Perspective cameras keep the canvas one unit away, so it
+    // depends on the field of view, the others just go from -1 to 1
+    pub(super) fn half_view(&self, field_of_view: f64) -> f64 {
+        match self {
+            Self::Perspective => (field_of_view / 2.0).tan(),
+            _ => 1.0,
+        }
+    }
+
+    // the ray through a position on the screen, in camera space where the
+    // camera looks toward -z and +x is to the left. half_size is how far the
+    // screen goes in x and y. The flat projections give directions that
+    // end on the z = -1 plane and the others unit directions, so the focal
+    // plane or sphere is always at the focal distance along them
+    pub(super) fn camera_ray(
+        &self,
+        x: f64,
+        y: f64,
+        field_of_view: f64,
+        half_size: (f64, f64),
+    ) -> (Point, Vector) {
+        let origin = Point::new_point(0.0, 0.0, 0.0);
+
+        match *self {
+            Self::Perspective => (origin, Vector::new_vector(x, y, -1.0)),
+            Self::Orthographic { width } => (
+                Point::new_point(x * width / 2.0, y * width / 2.0, 0.0),
+                Vector::new_vector(0.0, 0.0, -1.0),
+            ),
+            Self::Fisheye => {
+                let radius = x.hypot(y);
+                if radius == 0.0 {
+                    return (origin, Vector::new_vector(0.0, 0.0, -1.0));
+                }
+
+                let angle = radius * field_of_view / 2.0;
+                let sideways = angle.sin() / radius;
+                (
+                    origin,
+                    Vector::new_vector(x * sideways, y * sideways, -angle.cos()),
+                )
+            }
+            Self::Equirectangular => {
+                // +x is to the left, so longitude grows toward the right
+                let longitude = -x / half_size.0 * PI;
+                let latitude = y / half_size.1 * PI / 2.0;
+                (
+                    origin,
+                    Vector::new_vector(
+                        -longitude.sin() * latitude.cos(),
+                        latitude.sin(),
+                        -longitude.cos() * latitude.cos(),
+                    ),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::float_equal;
+
+    const HALF_SIZE: (f64, f64) = (1.0, 0.5);
+
+    #[test]
+    fn perspective() {
+        let (origin, direction) = Projection::Perspective.camera_ray(0.5, -0.25, 1.0, HALF_SIZE);
+
+        assert!(origin.equal(Point::new_point(0.0, 0.0, 0.0)));
+        assert!(direction.equal(Vector::new_vector(0.5, -0.25, -1.0)));
+        assert!(float_equal(
+            Projection::Perspective.half_view(PI / 2.0),
+            1.0
+        ));
+    }
+
+    #[test]
+    fn orthographic() {
+        let projection = Projection::Orthographic { width: 10.0 };
+        let (origin, direction) = projection.camera_ray(1.0, -0.5, 1.0, HALF_SIZE);
+
+        assert!(origin.equal(Point::new_point(5.0, -2.5, 0.0)));
+        assert!(direction.equal(Vector::new_vector(0.0, 0.0, -1.0)));
+        assert_eq!(projection.half_view(1.0), 1.0);
+    }
+
+    #[test]
+    fn fisheye() {
+        let fisheye = Projection::Fisheye;
+        let (_, center) = fisheye.camera_ray(0.0, 0.0, PI, HALF_SIZE);
+        assert!(center.equal(Vector::new_vector(0.0, 0.0, -1.0)));
+
+        // a 180 degree lens sees straight to the side at the edge
+        let (_, edge) = fisheye.camera_ray(1.0, 0.0, PI, HALF_SIZE);
+        assert!(edge.equal(Vector::new_vector(1.0, 0.0, 0.0)));
+        // and the angle grows evenly toward it
+        let (_, halfway) = fisheye.camera_ray(0.0, 0.5, PI, HALF_SIZE);
+        let diagonal = 0.5_f64.sqrt();
+        assert!(halfway.equal(Vector::new_vector(0.0, diagonal, -diagonal)));
+        // and wider lenses see behind the camera
+        let (_, behind) = fisheye.camera_ray(1.0, 0.0, 2.0 * PI, HALF_SIZE);
+        assert!(behind.equal(Vector::new_vector(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn equirectangular() {
+        let panorama = Projection::Equirectangular;
+        let direction = |x, y| panorama.camera_ray(x, y, 1.0, HALF_SIZE).1;
+
+        assert!(direction(0.0, 0.0).equal(Vector::new_vector(0.0, 0.0, -1.0)));
+        // a quarter of the way to the right edge is a quarter turn right,
+        // which is -x in camera space
+        assert!(direction(-0.5, 0.0).equal(Vector::new_vector(-1.0, 0.0, 0.0)));
+        // both edges look behind
+        assert!(direction(1.0, 0.0).equal(Vector::new_vector(0.0, 0.0, 1.0)));
+        assert!(direction(-1.0, 0.0).equal(Vector::new_vector(0.0, 0.0, 1.0)));
+        // and the top edge looks straight up
+        assert!(direction(0.3, 0.5).equal(Vector::new_vector(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn validate() {
+        assert!(Projection::Perspective.validate(1.0).is_ok());
+        assert!(Projection::Orthographic { width: 2.0 }
+            .validate(0.0)
+            .is_ok());
+        assert!(Projection::Fisheye.validate(2.0 * PI).is_ok());
+        assert!(Projection::Equirectangular.validate(0.0).is_ok());
+
+        let error = Projection::Orthographic { width: 0.0 }
+            .validate(1.0)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert_eq!(error.to_string(), "orthographic width must be above 0");
+        assert!(Projection::Fisheye.validate(7.0).is_err());
+    }
+
+    #[test]
+    fn serialize() {
+        assert_eq!(
+            serde_json::to_string(&Projection::Orthographic { width: 4.0 }).unwrap(),
+            r#"{"type":"orthographic","width":4.0}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Projection>(r#"{"type":"fisheye"}"#).unwrap(),
+            Projection::Fisheye
+        );
+    }
+}
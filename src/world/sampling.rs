@@ -0,0 +1,150 @@
+use std::f64::consts::PI;
+
+use crate::base_types::Vector;
+
+// small, fast and seedable random numbers (splitmix64). Every sample of
+// every pixel gets its own generator, so renders come out the same however
+// the rows are split between threads
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // a generator for one sample of one pixel
+    pub fn for_sample(seed: u64, x: usize, y: usize, sample: usize) -> Self {
+        let mut random = Self::new(seed);
+        for value in [x, y, sample] {
+            random.state ^= value as u64;
+            random.state = random.next_u64();
+        }
+
+        random
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        // the top 53 bits fill the mantissa
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+// index-th value of the halton sequence for the given base, in [0, 1)
+pub(super) fn halton(mut index: usize, base: usize) -> f64 {
+    let mut fraction = 1.0;
+    let mut value = 0.0;
+
+    while index > 0 {
+        fraction /= base as f64;
+        value += fraction * (index % base) as f64;
+        index /= base;
+    }
+
+    value
+}
+
+// a direction on the hemisphere around the normal, more likely the closer
+// it is to the normal, matching how much light a diffuse surface takes in
+// from it. u and v are in [0, 1)
+pub fn cosine_weighted_direction(normal: Vector, u: f64, v: f64) -> Vector {
+    let radius = u.sqrt();
+    let angle = 2.0 * PI * v;
+    let (tangent, bitangent) = orthonormal_basis(normal);
+
+    tangent * (radius * angle.cos())
+        + bitangent * (radius * angle.sin())
+        + normal * (1.0 - u).max(0.0).sqrt()
+}
+
+// two unit vectors at right angles to each other and the normal
+fn orthonormal_basis(normal: Vector) -> (Vector, Vector) {
+    // cross with whichever axis is furthest from the normal
+    let axis = if normal.x.abs() > 0.9 {
+        Vector::new_vector(0.0, 1.0, 0.0)
+    } else {
+        Vector::new_vector(1.0, 0.0, 0.0)
+    };
+    let tangent = normal.cross(axis).normalize();
+
+    (tangent, normal.cross(tangent))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::float_equal;
+
+    #[test]
+    fn seeded() {
+        let mut first = Random::new(7);
+        let mut second = Random::new(7);
+        let mut other = Random::new(8);
+
+        let values: Vec<u64> = (0..4).map(|_| first.next_u64()).collect();
+        assert_eq!(
+            values,
+            (0..4).map(|_| second.next_u64()).collect::<Vec<_>>()
+        );
+        assert_ne!(values[0], other.next_u64());
+    }
+
+    #[test]
+    fn samples_get_their_own_numbers() {
+        let value = |x, y, sample| Random::for_sample(1, x, y, sample).next_u64();
+
+        assert_eq!(value(3, 4, 5), value(3, 4, 5));
+        assert_ne!(value(3, 4, 5), value(4, 3, 5));
+        assert_ne!(value(3, 4, 5), value(3, 4, 6));
+        assert_ne!(value(0, 0, 0), Random::for_sample(2, 0, 0, 0).next_u64());
+    }
+
+    #[test]
+    fn uniform() {
+        let mut random = Random::new(42);
+        let values: Vec<f64> = (0..10_000).map(|_| random.next_f64()).collect();
+
+        assert!(values.iter().all(|value| (0.0..1.0).contains(value)));
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        assert!((mean - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn halton_sequence() {
+        assert_eq!(halton(1, 2), 0.5);
+        assert_eq!(halton(2, 2), 0.25);
+        assert_eq!(halton(3, 2), 0.75);
+        assert!(float_equal(halton(1, 3), 1.0 / 3.0));
+        assert!(float_equal(halton(2, 3), 2.0 / 3.0));
+        assert!(float_equal(halton(3, 3), 1.0 / 9.0));
+    }
+
+    #[test]
+    fn cosine_weighted() {
+        let normal = Vector::new_vector(1.0, 2.0, -0.5).normalize();
+        let mut random = Random::new(3);
+        let count = 10_000;
+        let mut cosine_sum = 0.0;
+
+        for _ in 0..count {
+            let direction = cosine_weighted_direction(normal, random.next_f64(), random.next_f64());
+            assert!(float_equal(direction.magnitude(), 1.0));
+            assert!(direction.dot(normal) >= 0.0);
+            cosine_sum += direction.dot(normal);
+        }
+
+        // the average cosine of a cosine weighted hemisphere is 2/3
+        assert!((cosine_sum / count as f64 - 2.0 / 3.0).abs() < 0.01);
+        assert!(cosine_weighted_direction(normal, 0.0, 0.3).equal(normal));
+    }
+}
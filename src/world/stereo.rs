@@ -0,0 +1,148 @@
+use super::{Camera, World};
+use crate::visuals::{Canvas, StereoLayout};
+
+// a pair of eyes built from a camera, which sits halfway between them. Each
+// eye keeps every other setting of the camera
+pub struct StereoCamera {
+    pub camera: Camera,
+    // how far apart the eyes are, in scene units
+    pub interocular_distance: f64,
+    // how far ahead the eyes turn in to look at the same point, things at
+    // that distance look like they are on the screen. Infinity keeps the
+    // eyes parallel, which is what headsets expect
+    pub convergence_distance: f64,
+}
+
+impl StereoCamera {
+    pub fn new(camera: Camera, interocular_distance: f64) -> Self {
+        Self {
+            camera,
+            interocular_distance,
+            convergence_distance: f64::INFINITY,
+        }
+    }
+
+    pub fn left_eye(&self) -> Camera {
+        // camera looks toward -z, so +x is to the left
+        self.eye(self.interocular_distance / 2.0)
+    }
+
+    pub fn right_eye(&self) -> Camera {
+        self.eye(-self.interocular_distance / 2.0)
+    }
+
+    // offset is how far the eye is along the camera's x axis
+    fn eye(&self, offset: f64) -> Camera {
+        let toe_in = (offset / self.convergence_distance).atan();
+        let mut eye = self
+            .camera
+            .resized(self.camera.hsize(), self.camera.vsize());
+        // move the world the other way from the eye, then turn it
+        eye.set_transform(
+            self.camera
+                .get_transform()
+                .matrix()
+                .clone()
+                .translate(-offset, 0.0, 0.0)
+                .rotate_y(-toe_in),
+        );
+
+        eye
+    }
+
+    // (left, right)
+    pub fn render(&self, world: &World) -> (Canvas, Canvas) {
+        (
+            self.left_eye().render(world),
+            self.right_eye().render(world),
+        )
+    }
+
+    pub fn render_layout(&self, world: &World, layout: StereoLayout) -> Canvas {
+        let (left, right) = self.render(world);
+        layout
+            .compose(&left, &right)
+            .expect("both eyes render at the camera's size")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base_types::{Point, Vector};
+    use crate::matrices::Matrix;
+    use crate::visuals::Color;
+    use crate::world::{Object, Sphere};
+    use std::f64::consts::PI;
+
+    fn looking_down_z() -> Camera {
+        let mut camera = Camera::new(21, 21, PI / 3.0);
+        camera.set_transform(Matrix::view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+        camera
+    }
+
+    #[test]
+    fn parallel_eyes() {
+        let stereo = StereoCamera::new(looking_down_z(), 0.5);
+        let left = stereo.left_eye().ray_for_pixel(10, 10);
+        let right = stereo.right_eye().ray_for_pixel(10, 10);
+
+        // looking down +z the camera's left is -x
+        assert!(left.origin.equal(Point::new_point(-0.25, 0.0, -5.0)));
+        assert!(right.origin.equal(Point::new_point(0.25, 0.0, -5.0)));
+        assert!(left.direction.equal(Vector::new_vector(0.0, 0.0, 1.0)));
+        assert!(right.direction.equal(Vector::new_vector(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn converging_eyes() {
+        let mut stereo = StereoCamera::new(looking_down_z(), 0.5);
+        stereo.convergence_distance = 5.0;
+        let origin = Point::new_point(0.0, 0.0, 0.0);
+
+        for eye in [stereo.left_eye(), stereo.right_eye()] {
+            let ray = eye.ray_for_pixel(10, 10);
+            let to_origin = (origin - ray.origin).normalize();
+            assert!(ray.direction.equal(to_origin));
+        }
+    }
+
+    #[test]
+    fn eyes_keep_camera_settings() {
+        let mut camera = looking_down_z();
+        camera.samples_per_pixel = 3;
+        camera.aperture = 0.1;
+        let stereo = StereoCamera::new(camera, 0.5);
+        let eye = stereo.right_eye();
+
+        assert_eq!((eye.hsize(), eye.vsize()), (21, 21));
+        assert_eq!(eye.samples_per_pixel, 3);
+        assert_eq!(eye.aperture, 0.1);
+    }
+
+    #[test]
+    fn render_pair() {
+        let mut world = World::new();
+        let mut sphere = Sphere::default();
+        sphere.set_transform(Matrix::scaling(0.5, 0.5, 0.5));
+        world.add_object(Box::new(sphere));
+        let mut stereo = StereoCamera::new(looking_down_z(), 2.0);
+        stereo.convergence_distance = 5.0;
+
+        let (left, right) = stereo.render(&world);
+        // converged on the sphere, it is in the middle of both
+        let background = Color::new(0.0, 0.0, 0.0);
+        assert!(!left.pixel_at(10, 10).equal(background));
+        assert!(!right.pixel_at(10, 10).equal(background));
+        // but each eye sees it from its own side
+        assert!(!left.pixel_at(10, 10).equal(right.pixel_at(10, 10)));
+
+        let side_by_side = stereo.render_layout(&world, StereoLayout::SideBySide);
+        assert_eq!((side_by_side.width(), side_by_side.height()), (42, 21));
+        assert!(side_by_side.pixel_at(31, 10).equal(right.pixel_at(10, 10)));
+    }
+}
@@ -0,0 +1,77 @@
+// counts heap allocations made while shading, to make sure materials are
+// borrowed rather than cloned for every hit. benches/render.rs times the
+// same scenes, from examples::patterned_scene
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use ray_tracer::base_types::Point;
+use ray_tracer::examples::{patterned_material, patterned_scene};
+use ray_tracer::matrices::Matrix;
+use ray_tracer::visuals::Color;
+use ray_tracer::world::{Light, Object, Sphere, SurfacePoint};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+// the counter is shared, so only one test can count at a time
+static COUNTING: Mutex<()> = Mutex::new(());
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations<F: FnOnce()>(func: F) -> usize {
+    let _guard = COUNTING.lock().unwrap();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    func();
+
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+#[test]
+fn lighting_does_not_allocate() {
+    let sphere = Sphere::new(
+        Point::new_point(0.0, 0.0, 0.0),
+        1.0,
+        Matrix::new_identity(),
+        patterned_material(3),
+    );
+    let light = Light::new(
+        Point::new_point(-10.0, 10.0, -10.0),
+        Color::new(1.0, 1.0, 1.0),
+    );
+    let point = Point::new_point(0.0, 0.0, -1.0);
+
+    let allocations = count_allocations(|| {
+        let normal = sphere.normal_at(point, 0.0);
+        light.lighting(&sphere, &SurfacePoint::new(point, -normal, normal), false);
+    });
+
+    assert_eq!(allocations, 0);
+}
+
+#[test]
+fn render_allocations_do_not_depend_on_patterns() {
+    let (plain_world, plain_camera) = patterned_scene(16, 16, 0);
+    let (patterned_world, patterned_camera) = patterned_scene(16, 16, 4);
+
+    let plain = count_allocations(|| {
+        plain_camera.render(&plain_world);
+    });
+    let patterned = count_allocations(|| {
+        patterned_camera.render(&patterned_world);
+    });
+
+    assert_eq!(plain, patterned);
+}
@@ -0,0 +1,74 @@
+// renders the example scenes at a small size and compares them against the
+// reference images in tests/golden. Run with UPDATE_GOLDEN=1 to re-create
+// the references after an intentional change to the output.
+use std::env;
+use std::fs;
+
+use ray_tracer::examples;
+use ray_tracer::visuals::Canvas;
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+const FAILURE_DIR: &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/golden");
+const UPDATE_ENV_VAR: &str = "UPDATE_GOLDEN";
+// allows for 8 bit rounding in the reference plus a little float noise
+const TOLERANCE: f64 = 2.0 / 255.0;
+
+fn check_golden(name: &str, canvas: &Canvas) {
+    let reference_path = format!("{}/{}.png", GOLDEN_DIR, name);
+    if env::var_os(UPDATE_ENV_VAR).is_some() {
+        canvas.save_canvas(&reference_path).unwrap();
+        return;
+    }
+
+    let reference = Canvas::load_canvas(&reference_path).unwrap_or_else(|error| {
+        panic!(
+            "could not load {} ({}), run with {}=1 to create it",
+            reference_path, error, UPDATE_ENV_VAR
+        )
+    });
+    if canvas.approx_equal(&reference, TOLERANCE) {
+        return;
+    }
+
+    fs::create_dir_all(FAILURE_DIR).unwrap();
+    let actual_path = format!("{}/{}_actual.png", FAILURE_DIR, name);
+    canvas.save_canvas(&actual_path).unwrap();
+
+    match canvas.diff_canvas(&reference) {
+        Ok(diff) => {
+            let diff_path = format!("{}/{}_diff.png", FAILURE_DIR, name);
+            diff.save_canvas(&diff_path).unwrap();
+            panic!(
+                "{} differs from its reference (psnr {:.2} dB), see {} and {}",
+                name,
+                canvas.psnr(&reference).unwrap(),
+                actual_path,
+                diff_path
+            );
+        }
+        Err(error) => panic!("{}: {}, see {}", name, error, actual_path),
+    }
+}
+
+#[test]
+fn world() {
+    let (world, camera) = examples::world_scene(64, 44);
+    check_golden("world", &camera.render(&world));
+}
+
+#[test]
+fn plane() {
+    let (world, camera) = examples::plane_scene(64, 44);
+    check_golden("plane", &camera.render(&world));
+}
+
+#[test]
+fn perturbed() {
+    let (world, camera) = examples::perturbed_scene(64, 32);
+    check_golden("perturbed", &camera.render(&world));
+}
+
+#[test]
+fn shaded_sphere() {
+    check_golden("shaded_sphere", &examples::render_shaded_sphere(64));
+}